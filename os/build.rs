@@ -44,9 +44,10 @@ fn insert_app_data() -> Result<()> {
         .collect();
     apps.sort();
 
-    writeln!(f, "pub static APP_BIN: &[&[u8]] = &[").unwrap();
+    // the kernel loads their `PT_LOAD` segments itself
+    writeln!(f, "pub static APP_ELF: &[&[u8]] = &[").unwrap();
     for app in &apps {
-        writeln!(f, "    include_bytes!(\"{TARGET_PATH}{app}.bin\"),").unwrap();
+        writeln!(f, "    include_bytes!(\"{TARGET_PATH}{app}\"),").unwrap();
     }
     writeln!(f, "];").unwrap();
 
@@ -0,0 +1,40 @@
+//! Kernel command line, taken from `/chosen/bootargs` of the device tree
+//!
+//! It is a list of whitespace separated `key=value` words, e.g.
+//! `log=info,toyos::task=trace color=off`.
+
+use alloc::string::String;
+
+use spin::Once;
+
+use crate::fdt::Fdt;
+
+static BOOTARGS: Once<String> = Once::new();
+
+/// Copy the command line out of the device tree at `dtb`, it may be
+/// overwritten later on.
+pub fn init(dtb: usize) {
+    BOOTARGS.call_once(|| {
+        let fdt = unsafe { Fdt::from_addr(dtb) };
+        fdt.and_then(|fdt| fdt.property_str("/chosen", "bootargs"))
+            .map(String::from)
+            .unwrap_or_default()
+    });
+}
+
+/// the whole command line, empty before [`init`] or without bootargs
+pub fn bootargs() -> &'static str {
+    BOOTARGS.get().map_or("", |bootargs| bootargs.as_str())
+}
+
+/// The value of `key`, the last one wins if it is given more than once. A
+/// bare `key` without `=` has an empty value.
+pub fn get(key: &str) -> Option<&'static str> {
+    bootargs()
+        .split_whitespace()
+        .filter_map(|word| match word.split_once('=') {
+            Some((k, value)) => (k == key).then(|| value),
+            None => (word == key).then(|| ""),
+        })
+        .last()
+}
@@ -0,0 +1,124 @@
+//! Just enough of a flattened device tree parser to find a few properties
+//!
+//! The bootloader hands us the address of the device tree blob in `a1`. We
+//! do not build a tree, we walk the structure block once per lookup.
+
+use core::{mem::size_of, slice, str};
+
+const FDT_MAGIC: u32 = 0xd00dfeed;
+const FDT_BEGIN_NODE: u32 = 1;
+const FDT_END_NODE: u32 = 2;
+const FDT_PROP: u32 = 3;
+const FDT_NOP: u32 = 4;
+
+/// the part of the header we need, every field is big endian
+#[repr(C)]
+struct FdtHeader {
+    magic: u32,
+    totalsize: u32,
+    off_dt_struct: u32,
+    off_dt_strings: u32,
+}
+
+pub struct Fdt {
+    data: &'static [u8],
+    structs: usize,
+    strings: usize,
+}
+
+impl Fdt {
+    /// # Safety
+    ///
+    /// `addr` has to be 0 or point to a device tree blob that stays mapped
+    /// and unchanged.
+    pub unsafe fn from_addr(addr: usize) -> Option<Self> {
+        if addr == 0 || addr % size_of::<u32>() != 0 {
+            return None;
+        }
+        let header = &*(addr as *const FdtHeader);
+        if u32::from_be(header.magic) != FDT_MAGIC {
+            return None;
+        }
+        let data =
+            slice::from_raw_parts(addr as *const u8, u32::from_be(header.totalsize) as usize);
+        Some(Self {
+            data,
+            structs: u32::from_be(header.off_dt_struct) as usize,
+            strings: u32::from_be(header.off_dt_strings) as usize,
+        })
+    }
+
+    fn u32_at(&self, offset: usize) -> Option<u32> {
+        let bytes = self.data.get(offset..offset + 4)?;
+        Some(u32::from_be_bytes(bytes.try_into().unwrap()))
+    }
+
+    /// the NUL-terminated string at `offset`
+    fn str_at(&self, offset: usize) -> Option<&'static str> {
+        let data = self.data.get(offset..)?;
+        let len = data.iter().position(|c| *c == 0)?;
+        str::from_utf8(&data[..len]).ok()
+    }
+
+    /// The value of property `name` of the node at `path`, e.g.
+    /// `/chosen`. Unit addresses may be left out of `path`.
+    pub fn property(&self, path: &str, name: &str) -> Option<&'static [u8]> {
+        let mut wanted = path.split('/').filter(|part| !part.is_empty());
+        let mut next_wanted = wanted.next();
+        // depth of the node we are in, and how many levels of `path` match
+        let (mut depth, mut matched) = (0usize, 0usize);
+        let mut offset = self.structs;
+        loop {
+            let token = self.u32_at(offset)?;
+            offset += 4;
+            match token {
+                FDT_BEGIN_NODE => {
+                    let node = self.str_at(offset)?;
+                    offset = align4(offset + node.len() + 1);
+                    // the root node has an empty name
+                    if depth > 0 && matched == depth - 1 {
+                        let base = node.split('@').next().unwrap();
+                        if next_wanted.map_or(false, |part| part == node || part == base) {
+                            matched += 1;
+                            next_wanted = wanted.next();
+                        }
+                    }
+                    depth += 1;
+                }
+                FDT_END_NODE => {
+                    depth = depth.checked_sub(1)?;
+                    if matched >= depth && depth > 0 {
+                        // left a node on the path without finding it
+                        return None;
+                    }
+                }
+                FDT_PROP => {
+                    let len = self.u32_at(offset)? as usize;
+                    let name_offset = self.u32_at(offset + 4)? as usize;
+                    let value = self.data.get(offset + 8..offset + 8 + len)?;
+                    offset = align4(offset + 8 + len);
+                    if next_wanted.is_none()
+                        && matched + 1 == depth
+                        && self.str_at(self.strings + name_offset)? == name
+                    {
+                        return Some(value);
+                    }
+                }
+                FDT_NOP => {}
+                // FDT_END, or garbage
+                _ => return None,
+            }
+        }
+    }
+
+    /// a string property, without its trailing NUL
+    pub fn property_str(&self, path: &str, name: &str) -> Option<&'static str> {
+        let value = self.property(path, name)?;
+        let len = value.iter().position(|c| *c == 0).unwrap_or(value.len());
+        str::from_utf8(&value[..len]).ok()
+    }
+}
+
+fn align4(offset: usize) -> usize {
+    (offset + 3) & !3
+}
@@ -0,0 +1,30 @@
+//! File abstractions behind the per-process fd table
+
+mod pidfd;
+mod pipe;
+mod procfs;
+mod stdio;
+
+pub use pidfd::PidFd;
+pub use pipe::make_pipe;
+pub use procfs::open_proc;
+pub use stdio::{Stdin, Stdout};
+
+/// Anything that can sit in a process' fd table
+pub trait File: Send + Sync {
+    fn readable(&self) -> bool;
+    fn writable(&self) -> bool;
+    /// read into `buf`, returns the number of bytes read, 0 means EOF, or
+    /// a negative errno
+    fn read(&self, buf: &mut [u8]) -> Result<usize, isize>;
+    /// write `buf`, returns the number of bytes written or a negative errno
+    fn write(&self, buf: &[u8]) -> Result<usize, isize>;
+    /// whether this is a terminal, the tty ioctls only work on those
+    fn is_tty(&self) -> bool {
+        false
+    }
+    /// the process this is a pidfd of
+    fn pidfd(&self) -> Option<usize> {
+        None
+    }
+}
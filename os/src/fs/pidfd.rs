@@ -0,0 +1,30 @@
+//! pidfds, fds that stand for a process
+//!
+//! They can be neither read nor written. `sys_pidfd_getfd` copies an open
+//! file of the process through one, which is how the end of a pipe gets
+//! from the process that made it to another.
+
+use super::File;
+use crate::syscall::errno::EBADF;
+
+pub struct PidFd {
+    pub pid: usize,
+}
+
+impl File for PidFd {
+    fn readable(&self) -> bool {
+        false
+    }
+    fn writable(&self) -> bool {
+        false
+    }
+    fn read(&self, _buf: &mut [u8]) -> Result<usize, isize> {
+        Err(-EBADF)
+    }
+    fn write(&self, _buf: &[u8]) -> Result<usize, isize> {
+        Err(-EBADF)
+    }
+    fn pidfd(&self) -> Option<usize> {
+        Some(self.pid)
+    }
+}
@@ -0,0 +1,229 @@
+//! Anonymous pipes backed by a kernel ring buffer
+//!
+//! Both ends share one [`PipeRingBuffer`]. A reader that finds the buffer
+//! empty (or a writer that finds it full) parks itself in the buffer's wait
+//! queue and blocks; the other side wakes it up once it has made progress.
+//! The buffer only keeps weak references to the ends, so dropping the last
+//! write end is what turns an empty buffer into EOF.
+//!
+//! The ends are shared by the threads of a process, and get to other
+//! processes through `sys_pidfd_getfd`. A wait nothing can end, like a
+//! process writing more than the buffer holds into a pipe only it can read,
+//! fails with `-EDEADLK` once every task is blocked. Writing once every
+//! read end is gone raises SIGPIPE and fails with `-EPIPE`, as on Linux.
+
+use alloc::{
+    collections::VecDeque,
+    sync::{Arc, Weak},
+};
+
+use super::File;
+use crate::{
+    sync::UPSafeCell,
+    syscall::errno::{EDEADLK, EPIPE},
+    task::{block_current_interruptible_and_run_next, signal::SIGPIPE, wakeup_task, TASK_MANAGER},
+};
+
+const RING_BUFFER_SIZE: usize = 512;
+
+pub struct Pipe {
+    readable: bool,
+    writable: bool,
+    buffer: Arc<UPSafeCell<PipeRingBuffer>>,
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum RingBufferStatus {
+    Full,
+    Empty,
+    Normal,
+}
+
+pub struct PipeRingBuffer {
+    arr: [u8; RING_BUFFER_SIZE],
+    head: usize,
+    tail: usize,
+    status: RingBufferStatus,
+    read_end: Option<Weak<Pipe>>,
+    write_end: Option<Weak<Pipe>>,
+    read_waiters: VecDeque<usize>,
+    write_waiters: VecDeque<usize>,
+}
+
+impl PipeRingBuffer {
+    fn new() -> Self {
+        Self {
+            arr: [0; RING_BUFFER_SIZE],
+            head: 0,
+            tail: 0,
+            status: RingBufferStatus::Empty,
+            read_end: None,
+            write_end: None,
+            read_waiters: VecDeque::new(),
+            write_waiters: VecDeque::new(),
+        }
+    }
+    fn read_byte(&mut self) -> u8 {
+        self.status = RingBufferStatus::Normal;
+        let c = self.arr[self.head];
+        self.head = (self.head + 1) % RING_BUFFER_SIZE;
+        if self.head == self.tail {
+            self.status = RingBufferStatus::Empty;
+        }
+        c
+    }
+    fn write_byte(&mut self, byte: u8) {
+        self.status = RingBufferStatus::Normal;
+        self.arr[self.tail] = byte;
+        self.tail = (self.tail + 1) % RING_BUFFER_SIZE;
+        if self.tail == self.head {
+            self.status = RingBufferStatus::Full;
+        }
+    }
+    fn available_read(&self) -> usize {
+        if self.status == RingBufferStatus::Empty {
+            0
+        } else if self.tail > self.head {
+            self.tail - self.head
+        } else {
+            self.tail + RING_BUFFER_SIZE - self.head
+        }
+    }
+    fn available_write(&self) -> usize {
+        if self.status == RingBufferStatus::Full {
+            0
+        } else {
+            RING_BUFFER_SIZE - self.available_read()
+        }
+    }
+    fn all_write_ends_closed(&self) -> bool {
+        self.write_end.as_ref().unwrap().upgrade().is_none()
+    }
+    fn all_read_ends_closed(&self) -> bool {
+        self.read_end.as_ref().unwrap().upgrade().is_none()
+    }
+}
+
+/// wake up every task in `queue`, they will re-check the buffer themselves
+fn wakeup_all(queue: &mut VecDeque<usize>) {
+    while let Some(task_id) = queue.pop_front() {
+        wakeup_task(task_id);
+    }
+}
+
+/// create a pipe, returns (read end, write end)
+pub fn make_pipe() -> (Arc<Pipe>, Arc<Pipe>) {
+    let buffer = Arc::new(unsafe { UPSafeCell::new(PipeRingBuffer::new()) });
+    let read_end = Arc::new(Pipe {
+        readable: true,
+        writable: false,
+        buffer: buffer.clone(),
+    });
+    let write_end = Arc::new(Pipe {
+        readable: false,
+        writable: true,
+        buffer: buffer.clone(),
+    });
+    let mut ring = buffer.exclusive_access();
+    ring.read_end = Some(Arc::downgrade(&read_end));
+    ring.write_end = Some(Arc::downgrade(&write_end));
+    drop(ring);
+    (read_end, write_end)
+}
+
+impl File for Pipe {
+    fn readable(&self) -> bool {
+        self.readable
+    }
+    fn writable(&self) -> bool {
+        self.writable
+    }
+    /// Block until at least one byte is available, or return 0 on EOF.
+    /// `-EDEADLK` if the wait is given up.
+    fn read(&self, buf: &mut [u8]) -> Result<usize, isize> {
+        assert!(self.readable);
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            let mut ring = self.buffer.exclusive_access();
+            let available = ring.available_read();
+            if available == 0 {
+                if ring.all_write_ends_closed() {
+                    return Ok(0);
+                }
+                let tid = TASK_MANAGER.get_current_task();
+                ring.read_waiters.push_back(tid);
+                drop(ring);
+                if block_current_interruptible_and_run_next() {
+                    let mut ring = self.buffer.exclusive_access();
+                    ring.read_waiters.retain(|waiter| *waiter != tid);
+                    return Err(-EDEADLK);
+                }
+                continue;
+            }
+            let len = available.min(buf.len());
+            for byte in buf[..len].iter_mut() {
+                *byte = ring.read_byte();
+            }
+            wakeup_all(&mut ring.write_waiters);
+            return Ok(len);
+        }
+    }
+    /// Block until the whole `buf` is written. Once every read end is gone,
+    /// raises SIGPIPE and stops early; if the wait is given up, stops too.
+    /// Returns what was written so far, `-EPIPE` or `-EDEADLK` if that is
+    /// nothing.
+    fn write(&self, buf: &[u8]) -> Result<usize, isize> {
+        assert!(self.writable);
+        let mut written = 0;
+        while written < buf.len() {
+            let mut ring = self.buffer.exclusive_access();
+            if ring.all_read_ends_closed() {
+                drop(ring);
+                TASK_MANAGER.send_signal(TASK_MANAGER.get_current_pid(), SIGPIPE);
+                return match written {
+                    0 => Err(-EPIPE),
+                    written => Ok(written),
+                };
+            }
+            let available = ring.available_write();
+            if available == 0 {
+                wakeup_all(&mut ring.read_waiters);
+                let tid = TASK_MANAGER.get_current_task();
+                ring.write_waiters.push_back(tid);
+                drop(ring);
+                if block_current_interruptible_and_run_next() {
+                    let mut ring = self.buffer.exclusive_access();
+                    ring.write_waiters.retain(|waiter| *waiter != tid);
+                    return match written {
+                        0 => Err(-EDEADLK),
+                        written => Ok(written),
+                    };
+                }
+                continue;
+            }
+            let len = available.min(buf.len() - written);
+            for &byte in &buf[written..written + len] {
+                ring.write_byte(byte);
+            }
+            written += len;
+            wakeup_all(&mut ring.read_waiters);
+        }
+        Ok(written)
+    }
+}
+
+impl Drop for Pipe {
+    /// the other side may be sleeping on an end that will never make progress
+    /// again, let it observe EOF (or the broken pipe)
+    fn drop(&mut self) {
+        let mut ring = self.buffer.exclusive_access();
+        if self.writable {
+            wakeup_all(&mut ring.read_waiters);
+        }
+        if self.readable {
+            wakeup_all(&mut ring.write_waiters);
+        }
+    }
+}
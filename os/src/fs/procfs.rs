@@ -0,0 +1,235 @@
+//! Synthetic `/proc`
+//!
+//! Nothing is stored: a file renders its text from the kernel state on the
+//! first read, and later reads of the same open file continue from that
+//! snapshot, so a reader never sees a torn line.
+
+use alloc::{string::String, sync::Arc};
+use core::{fmt::Write, sync::atomic::Ordering};
+
+use super::File;
+use crate::{
+    config::{APP_BASE_ADDRESS, APP_SIZE_LIMIT},
+    link_app::APP_NUM,
+    mm::{heap_usage, prot_str},
+    sync::UPSafeCell,
+    task::{TaskStatus, TASK_MANAGER},
+    timer::timer_now,
+    trap::TRAP_STATS,
+    vvar,
+};
+
+#[derive(Copy, Clone)]
+enum ProcEntry {
+    Status(usize),
+    Syscalls(usize),
+    Maps(usize),
+    MemInfo,
+    Uptime,
+    Interrupts,
+}
+
+pub struct ProcFile {
+    entry: ProcEntry,
+    inner: UPSafeCell<ProcFileInner>,
+}
+
+struct ProcFileInner {
+    content: Option<String>,
+    offset: usize,
+}
+
+/// open `path` under `/proc`, `None` if there is no such file
+pub fn open_proc(path: &str) -> Option<Arc<ProcFile>> {
+    let path = path.strip_prefix("/proc/")?;
+    let entry = match path {
+        "meminfo" => ProcEntry::MemInfo,
+        "uptime" => ProcEntry::Uptime,
+        "interrupts" => ProcEntry::Interrupts,
+        _ => {
+            let (pid, file) = path.split_once('/')?;
+            let pid: usize = pid.parse().ok()?;
+            if pid >= APP_NUM {
+                return None;
+            }
+            match file {
+                "status" => ProcEntry::Status(pid),
+                "syscalls" => ProcEntry::Syscalls(pid),
+                "maps" => ProcEntry::Maps(pid),
+                _ => return None,
+            }
+        }
+    };
+    Some(Arc::new(ProcFile {
+        entry,
+        inner: unsafe {
+            UPSafeCell::new(ProcFileInner {
+                content: None,
+                offset: 0,
+            })
+        },
+    }))
+}
+
+fn state_name(status: TaskStatus, stopped: bool) -> &'static str {
+    match status {
+        TaskStatus::Exited => "X (dead)",
+        _ if stopped => "T (stopped)",
+        TaskStatus::Running => "R (running)",
+        TaskStatus::Ready => "R (ready)",
+        TaskStatus::Blocked => "S (sleeping)",
+        TaskStatus::Uninit => "I (idle)",
+    }
+}
+
+impl ProcEntry {
+    fn render(self) -> String {
+        let mut out = String::new();
+        // writing into a `String` cannot fail
+        let _ = match self {
+            ProcEntry::Status(pid) => render_status(&mut out, pid),
+            ProcEntry::Syscalls(pid) => render_syscalls(&mut out, pid),
+            ProcEntry::Maps(pid) => render_maps(&mut out, pid),
+            ProcEntry::MemInfo => render_meminfo(&mut out),
+            ProcEntry::Uptime => render_uptime(&mut out),
+            ProcEntry::Interrupts => render_interrupts(&mut out),
+        };
+        out
+    }
+}
+
+fn render_status(out: &mut String, pid: usize) -> core::fmt::Result {
+    let info = TASK_MANAGER.get_task_info(pid).unwrap();
+    let stat = TASK_MANAGER.get_process_stat(pid).unwrap();
+    writeln!(out, "Name:\t{}", info.name)?;
+    writeln!(out, "Pid:\t{}", pid)?;
+    writeln!(out, "State:\t{}", state_name(info.status, stat.stopped))?;
+    writeln!(out, "Threads:\t{}", stat.threads.len())?;
+    writeln!(out, "FDs:\t{}", stat.open_fds)?;
+    writeln!(out, "SigPnd:\t{:016x}", stat.signal_pending)?;
+    writeln!(out, "SigBlk:\t{:016x}", stat.signal_mask)?;
+    writeln!(out, "UserTime:\t{}", info.user_time.as_micros())?;
+    writeln!(out, "KernelTime:\t{}", info.kernel_time.as_micros())?;
+    writeln!(
+        out,
+        "StartTime:\t{}",
+        info.start_time.map_or(0, |time| time.as_micros())
+    )?;
+    writeln!(out, "DeadlineMisses:\t{}", info.deadline_misses)?;
+    if let Some(exit_code) = stat.exit_code {
+        writeln!(out, "ExitCode:\t{}", exit_code)?;
+    }
+    Ok(())
+}
+
+fn render_syscalls(out: &mut String, pid: usize) -> core::fmt::Result {
+    let info = TASK_MANAGER.get_task_info(pid).unwrap();
+    for syscall in info.call.iter() {
+        writeln!(
+            out,
+            "{}\t{:?}\t{}",
+            syscall.id as usize, syscall.id, syscall.times
+        )?;
+    }
+    Ok(())
+}
+
+fn render_maps(out: &mut String, pid: usize) -> core::fmt::Result {
+    let info = TASK_MANAGER.get_task_info(pid).unwrap();
+    let stat = TASK_MANAGER.get_process_stat(pid).unwrap();
+    for (range, prot, name) in stat.regions {
+        let name = if range.start == APP_BASE_ADDRESS + pid * APP_SIZE_LIMIT {
+            info.name
+        } else {
+            name
+        };
+        writeln!(
+            out,
+            "{:08x}-{:08x} {} {}",
+            range.start,
+            range.end,
+            prot_str(prot),
+            name
+        )?;
+    }
+    for (tid, _, ustack) in stat.threads {
+        writeln!(
+            out,
+            "{:08x}-{:08x} rw- [stack:{}]",
+            ustack.start, ustack.end, tid
+        )?;
+    }
+    let vvar = vvar::range();
+    writeln!(out, "{:08x}-{:08x} r-- [vvar]", vvar.start, vvar.end)
+}
+
+fn render_meminfo(out: &mut String) -> core::fmt::Result {
+    let (total, used) = heap_usage();
+    writeln!(out, "HeapTotal:\t{} kB", total / 1024)?;
+    writeln!(out, "HeapUsed:\t{} kB", used / 1024)?;
+    writeln!(out, "HeapFree:\t{} kB", (total - used) / 1024)?;
+    writeln!(out, "AppTotal:\t{} kB", APP_NUM * APP_SIZE_LIMIT / 1024)?;
+    Ok(())
+}
+
+fn render_uptime(out: &mut String) -> core::fmt::Result {
+    let uptime = timer_now();
+    writeln!(
+        out,
+        "{}.{:02}",
+        uptime.as_secs(),
+        uptime.subsec_millis() / 10
+    )
+}
+
+fn render_interrupts(out: &mut String) -> core::fmt::Result {
+    let rows = [
+        ("timer", &TRAP_STATS.timer, "Supervisor timer"),
+        (
+            "syscall",
+            &TRAP_STATS.syscall,
+            "Environment call from U-mode",
+        ),
+        ("fault", &TRAP_STATS.memory_fault, "Load/store/fetch fault"),
+        (
+            "illegal",
+            &TRAP_STATS.illegal_instruction,
+            "Illegal instruction",
+        ),
+    ];
+    writeln!(out, "{:>12}{:>12}", "", "CPU0")?;
+    for (name, count, description) in rows {
+        writeln!(
+            out,
+            "{:>11}:{:>12}  {}",
+            name,
+            count.load(Ordering::Relaxed),
+            description
+        )?;
+    }
+    Ok(())
+}
+
+impl File for ProcFile {
+    fn readable(&self) -> bool {
+        true
+    }
+    fn writable(&self) -> bool {
+        false
+    }
+    fn read(&self, buf: &mut [u8]) -> Result<usize, isize> {
+        let mut inner = self.inner.exclusive_access();
+        if inner.content.is_none() {
+            inner.content = Some(self.entry.render());
+        }
+        let offset = inner.offset;
+        let content = inner.content.as_ref().unwrap().as_bytes();
+        let len = buf.len().min(content.len() - offset);
+        buf[..len].copy_from_slice(&content[offset..offset + len]);
+        inner.offset += len;
+        Ok(len)
+    }
+    fn write(&self, _buf: &[u8]) -> Result<usize, isize> {
+        Ok(0)
+    }
+}
@@ -0,0 +1,61 @@
+//! Console backed stdin/stdout
+
+use super::File;
+use crate::{
+    sbi::{console_getchar, console_putchar},
+    task::suspend_current_and_run_next,
+};
+
+pub struct Stdin;
+
+pub struct Stdout;
+
+impl File for Stdin {
+    fn readable(&self) -> bool {
+        true
+    }
+    fn writable(&self) -> bool {
+        false
+    }
+    /// read one byte at a time, yield until the console has something for us
+    fn read(&self, buf: &mut [u8]) -> Result<usize, isize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let c = loop {
+            match console_getchar() {
+                usize::MAX => suspend_current_and_run_next(),
+                c => break c,
+            }
+        };
+        buf[0] = c as u8;
+        Ok(1)
+    }
+    fn write(&self, _buf: &[u8]) -> Result<usize, isize> {
+        panic!("Cannot write to stdin!");
+    }
+    fn is_tty(&self) -> bool {
+        true
+    }
+}
+
+impl File for Stdout {
+    fn readable(&self) -> bool {
+        false
+    }
+    fn writable(&self) -> bool {
+        true
+    }
+    fn read(&self, _buf: &mut [u8]) -> Result<usize, isize> {
+        panic!("Cannot read from stdout!");
+    }
+    fn write(&self, buf: &[u8]) -> Result<usize, isize> {
+        for &i in buf {
+            console_putchar(i as usize);
+        }
+        Ok(buf.len())
+    }
+    fn is_tty(&self) -> bool {
+        true
+    }
+}
@@ -3,17 +3,26 @@
 #![feature(asm_const)]
 #![feature(fn_align)]
 #![feature(naked_functions)]
+#![feature(alloc_error_handler)]
 #![no_std]
 
+extern crate alloc;
+
 use core::arch::global_asm;
 
 #[macro_use]
 pub mod console;
+pub mod bootargs;
 pub mod config;
+pub mod fdt;
+pub mod fs;
 mod lang;
 pub mod link_app;
 pub mod loader;
 pub mod logging;
+pub mod mm;
+pub mod profile;
+pub mod rtc;
 pub mod sbi;
 pub mod stack_trace;
 pub mod sync;
@@ -21,6 +30,7 @@ pub mod syscall;
 pub mod task;
 pub mod timer;
 pub mod trap;
+pub mod vvar;
 
 global_asm!(
     "
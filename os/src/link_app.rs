@@ -1,17 +1,42 @@
-pub static APP_BIN: &[&[u8]] = &[
-    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/00sleep.bin"),
-    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/01power_5.bin"),
-    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/02power_7.bin"),
-    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/04power_3.bin"),
-    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/0yield.bin"),
-    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/float.bin"),
-    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/get_taskinfo.bin"),
-    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/hello_world.bin"),
-    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/power.bin"),
-    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/priv_csr.bin"),
-    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/priv_inst.bin"),
-    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/store_fault.bin"),
-    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/unsafe_syswrite.bin"),
+pub static APP_ELF: &[&[u8]] = &[
+    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/00sleep"),
+    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/01power_5"),
+    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/02power_7"),
+    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/04power_3"),
+    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/0yield"),
+    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/blocked_fault"),
+    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/clock_test"),
+    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/deadline_test"),
+    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/dmesg"),
+    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/exec_args"),
+    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/float"),
+    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/get_taskinfo"),
+    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/hello_world"),
+    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/itimer_test"),
+    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/kernel_sync"),
+    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/linux_compat"),
+    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/log_filter"),
+    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/mmap_test"),
+    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/pipe_consumer"),
+    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/pipe_producer"),
+    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/pipetest"),
+    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/power"),
+    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/priv_csr"),
+    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/priv_inst"),
+    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/profile"),
+    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/ps"),
+    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/sched_latency"),
+    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/sched_test"),
+    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/sig_tests"),
+    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/sleep_test"),
+    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/store_fault"),
+    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/strace_test"),
+    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/sync_futex"),
+    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/threads"),
+    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/top"),
+    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/unsafe_syswrite"),
+    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/user_ptr_test"),
+    include_bytes!("../../user/target/riscv64gc-unknown-none-elf/release/vdso_test"),
 ];
 pub static APP_NAME: &[&str] = &[
     "00sleep",
@@ -19,13 +44,38 @@ pub static APP_NAME: &[&str] = &[
     "02power_7",
     "04power_3",
     "0yield",
+    "blocked_fault",
+    "clock_test",
+    "deadline_test",
+    "dmesg",
+    "exec_args",
     "float",
     "get_taskinfo",
     "hello_world",
+    "itimer_test",
+    "kernel_sync",
+    "linux_compat",
+    "log_filter",
+    "mmap_test",
+    "pipe_consumer",
+    "pipe_producer",
+    "pipetest",
     "power",
     "priv_csr",
     "priv_inst",
+    "profile",
+    "ps",
+    "sched_latency",
+    "sched_test",
+    "sig_tests",
+    "sleep_test",
     "store_fault",
+    "strace_test",
+    "sync_futex",
+    "threads",
+    "top",
     "unsafe_syswrite",
+    "user_ptr_test",
+    "vdso_test",
 ];
-pub const APP_NUM: usize = 13;
+pub const APP_NUM: usize = 38;
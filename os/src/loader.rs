@@ -1,7 +1,17 @@
+use alloc::{
+    alloc::{alloc_zeroed, Layout},
+    boxed::Box,
+    vec::Vec,
+};
+use core::ops::Range;
+
 use crate::{
-    config::{APP_BASE_ADDRESS, APP_SIZE_LIMIT, KERNEL_STACK_SIZE, USER_STACK_SIZE},
-    link_app::{APP_BIN, APP_NUM},
+    config::{APP_BASE_ADDRESS, APP_SIZE_LIMIT, KERNEL_STACK_SIZE, PAGE_SIZE, USER_STACK_SIZE},
+    link_app::{APP_ELF, APP_NAME, APP_NUM},
+    syscall::errno::E2BIG,
+    timer::get_cycle,
     trap::context::TrapContext,
+    vvar::{self, AT_VVAR},
 };
 
 #[repr(align(4096))]
@@ -26,7 +36,17 @@ pub static USER_STACK: [UserStack; APP_NUM] = [UserStack {
     data: [0; USER_STACK_SIZE],
 }; APP_NUM];
 
+/// allocate a zeroed `T` straight on the heap, stacks are too large to be
+/// built on the kernel stack first. `None` if the heap is full.
+fn boxed_zeroed<T>() -> Option<Box<T>> {
+    let ptr = unsafe { alloc_zeroed(Layout::new::<T>()) } as *mut T;
+    (!ptr.is_null()).then(|| unsafe { Box::from_raw(ptr) })
+}
+
 impl KernelStack {
+    pub fn new_boxed() -> Option<Box<Self>> {
+        boxed_zeroed()
+    }
     pub fn get_sp(&self) -> usize {
         self.data.as_ptr() as usize + KERNEL_STACK_SIZE
     }
@@ -43,32 +63,274 @@ impl KernelStack {
 }
 
 impl UserStack {
-    fn get_sp(&self) -> usize {
-        self.data.as_ptr() as usize + KERNEL_STACK_SIZE
+    pub fn new_boxed() -> Option<Box<Self>> {
+        boxed_zeroed()
+    }
+    pub fn get_sp(&self) -> usize {
+        self.data.as_ptr() as usize + USER_STACK_SIZE
     }
     pub fn get_stack(&self) -> &[u8] {
         &self.data
     }
 }
 
+pub fn app_base(app_id: usize) -> usize {
+    APP_BASE_ADDRESS + app_id * APP_SIZE_LIMIT
+}
+
+/// the app `path` names, `name`, `/name` and `/bin/name` all work
+pub fn find_app(path: &str) -> Option<usize> {
+    let name = path.strip_prefix("/bin/").unwrap_or(path);
+    let name = name.strip_prefix('/').unwrap_or(name);
+    APP_NAME.iter().position(|app| *app == name)
+}
+
+fn read_le(data: &[u8], offset: usize, len: usize) -> usize {
+    data[offset..offset + len]
+        .iter()
+        .rev()
+        .fold(0, |val, byte| val << 8 | *byte as usize)
+}
+
+const PT_LOAD: usize = 1;
+const ET_EXEC: usize = 2;
+const EM_RISCV: usize = 243;
+/// the size of an ELF64 program header
+const PHENT_SIZE: usize = 0x38;
+
+/// A program header of an ELF64
+struct ProgramHeader {
+    p_type: usize,
+    offset: usize,
+    vaddr: usize,
+    filesz: usize,
+    memsz: usize,
+}
+
+/// The ELF of an app, as built into the kernel by `link_app.rs`
+struct Elf(&'static [u8]);
+
+impl Elf {
+    fn of(app_id: usize) -> Self {
+        Self(APP_ELF[app_id])
+    }
+    fn entry(&self) -> usize {
+        read_le(self.0, 0x18, 8)
+    }
+    /// where the program headers are in the file
+    fn phdrs(&self) -> Range<usize> {
+        let phoff = read_le(self.0, 0x20, 8);
+        phoff..phoff + self.phnum() * PHENT_SIZE
+    }
+    fn phnum(&self) -> usize {
+        read_le(self.0, 0x38, 2)
+    }
+    fn program_headers(&self) -> impl Iterator<Item = ProgramHeader> + '_ {
+        self.phdrs().step_by(PHENT_SIZE).map(|ph| ProgramHeader {
+            p_type: read_le(self.0, ph, 4),
+            offset: read_le(self.0, ph + 0x08, 8),
+            vaddr: read_le(self.0, ph + 0x10, 8),
+            filesz: read_le(self.0, ph + 0x20, 8),
+            memsz: read_le(self.0, ph + 0x28, 8),
+        })
+    }
+    fn loads(&self) -> impl Iterator<Item = ProgramHeader> + '_ {
+        self.program_headers().filter(|ph| ph.p_type == PT_LOAD)
+    }
+
+    /// Whether this is a RISC-V ELF64 executable whose `PT_LOAD` segments
+    /// and entry fall in the slot of `app_id`; nothing is relocated, so it
+    /// must have been linked there.
+    fn check(&self, app_id: usize) -> Result<(), &'static str> {
+        let data = self.0;
+        if data.len() < 0x40 || data[..4] != *b"\x7fELF" || data[4] != 2 || data[5] != 1 {
+            return Err("not a little-endian ELF64");
+        }
+        if read_le(data, 0x10, 2) != ET_EXEC || read_le(data, 0x12, 2) != EM_RISCV {
+            return Err("not a RISC-V executable");
+        }
+        if read_le(data, 0x36, 2) != PHENT_SIZE || self.phdrs().end > data.len() {
+            return Err("bad program headers");
+        }
+        let slot = app_base(app_id)..app_base(app_id) + APP_SIZE_LIMIT;
+        for ph in self.loads() {
+            if ph.filesz > ph.memsz || ph.offset + ph.filesz > data.len() {
+                return Err("bad PT_LOAD segment");
+            }
+            if ph.vaddr < slot.start || ph.vaddr + ph.memsz > slot.end {
+                return Err("PT_LOAD segment outside its slot");
+            }
+        }
+        if !slot.contains(&self.entry()) {
+            return Err("entry outside its slot");
+        }
+        Ok(())
+    }
+
+    /// the end of the memory the segments take up, `.bss` included
+    fn mem_end(&self) -> usize {
+        self.loads()
+            .map(|ph| ph.vaddr + ph.memsz)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// where a `PT_LOAD` segment puts the program headers, if one does
+    fn loaded_phdrs(&self) -> Option<usize> {
+        let phdrs = self.phdrs();
+        self.loads()
+            .find(|ph| ph.offset <= phdrs.start && phdrs.end <= ph.offset + ph.filesz)
+            .map(|ph| ph.vaddr + phdrs.start - ph.offset)
+    }
+}
+
+/// how much of its slot `app_id` takes up once loaded, `.bss` included
+pub fn app_mem_len(app_id: usize) -> usize {
+    Elf::of(app_id).mem_end() - app_base(app_id)
+}
+
+/// Copy the `PT_LOAD` segments of `app_id` to their addresses in its slot,
+/// the rest of the slot, `.bss` included, is zeroed.
+pub fn load_app(app_id: usize) {
+    let elf = Elf::of(app_id);
+    if let Err(err) = elf.check(app_id) {
+        panic!("[kernel] cannot load app {}: {}", APP_NAME[app_id], err);
+    }
+    let slot =
+        unsafe { core::slice::from_raw_parts_mut(app_base(app_id) as *mut u8, APP_SIZE_LIMIT) };
+    slot.fill(0);
+    for ph in elf.loads() {
+        let start = ph.vaddr - app_base(app_id);
+        slot[start..start + ph.filesz].copy_from_slice(&elf.0[ph.offset..ph.offset + ph.filesz]);
+    }
+}
+
 // load app to specific address
 pub fn load_apps() {
-    let srcs = APP_BIN;
-    let dsts = unsafe {
-        core::slice::from_raw_parts_mut(APP_BASE_ADDRESS as *mut [u8; APP_SIZE_LIMIT], APP_NUM)
+    (0..APP_NUM).for_each(load_app);
+}
+
+pub const AT_NULL: usize = 0;
+pub const AT_PHDR: usize = 3;
+pub const AT_PHENT: usize = 4;
+pub const AT_PHNUM: usize = 5;
+pub const AT_PAGESZ: usize = 6;
+pub const AT_ENTRY: usize = 9;
+pub const AT_UID: usize = 11;
+pub const AT_EUID: usize = 12;
+pub const AT_GID: usize = 13;
+pub const AT_EGID: usize = 14;
+pub const AT_RANDOM: usize = 25;
+pub const AT_EXECFN: usize = 31;
+
+/// Bytes for `AT_RANDOM`. There is no entropy source, the cycle counter
+/// mixed with splitmix64 has to do.
+fn random_bytes() -> [u8; 16] {
+    let mut state = get_cycle();
+    let mut next = || {
+        state = state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    };
+    let mut bytes = [0u8; 16];
+    bytes[..8].copy_from_slice(&next().to_ne_bytes());
+    bytes[8..].copy_from_slice(&next().to_ne_bytes());
+    bytes
+}
+
+/// Lay out the start of a program on the main user stack of `app_id`, the
+/// System V way:
+///
+/// ```text
+/// sp -> argc
+///       argv[0] .. argv[argc - 1], NULL
+///       envp[0] .. NULL
+///       auxv (type, value) pairs .. AT_NULL
+///       AT_RANDOM bytes, the strings, the program headers
+/// ```
+///
+/// The program headers are only copied to the stack when no segment loads
+/// them, `AT_PHDR` points at them either way. The returned context starts at
+/// `e_entry` with `sp` there, and also has argc, argv and envp in `a0`..`a2`.
+/// `-E2BIG` if it does not fit the stack, which is checked before the stack
+/// is touched.
+pub fn app_context(app_id: usize, args: &[&str], envs: &[&str]) -> Result<TrapContext, isize> {
+    const AUXV_LEN: usize = 13;
+    let elf = Elf::of(app_id);
+    let phdrs = &elf.0[elf.phdrs()];
+    let phdrs_len = match elf.loaded_phdrs() {
+        Some(_) => 0,
+        None => phdrs.len(),
     };
+    let word = core::mem::size_of::<usize>();
+    let strings: usize = [APP_NAME[app_id]]
+        .iter()
+        .chain(args)
+        .chain(envs)
+        .map(|s| s.len() + 1)
+        .sum();
+    let words = 1 + (args.len() + 1) + (envs.len() + 1) + 2 * AUXV_LEN;
+    // 16 random bytes, and at most 16 lost aligning sp
+    if phdrs_len + 16 + strings + 16 + words * word > USER_STACK_SIZE {
+        return Err(-E2BIG);
+    }
 
-    for (src, dst) in srcs.iter().zip(dsts.iter_mut()) {
-        dst[0..src.len()].copy_from_slice(src);
-        dst[src.len()..].fill(0);
+    let mut top = USER_STACK[app_id].get_sp();
+    let mut push = |bytes: &[u8]| {
+        top -= bytes.len();
+        unsafe {
+            core::slice::from_raw_parts_mut(top as *mut u8, bytes.len()).copy_from_slice(bytes)
+        };
+        top
+    };
+    // a multiple of 8 bytes, which keeps them aligned
+    let phdr = elf.loaded_phdrs().unwrap_or_else(|| push(phdrs));
+    let random = push(&random_bytes());
+    let mut push_str = |s: &str| {
+        push(&[0]);
+        push(s.as_bytes())
+    };
+    let execfn = push_str(APP_NAME[app_id]);
+    let argv: Vec<usize> = args.iter().map(|arg| push_str(arg)).collect();
+    let envp: Vec<usize> = envs.iter().map(|env| push_str(env)).collect();
+    let entry = elf.entry();
+    let auxv: [(usize, usize); AUXV_LEN] = [
+        (AT_PHDR, phdr),
+        (AT_PHENT, PHENT_SIZE),
+        (AT_PHNUM, elf.phnum()),
+        (AT_PAGESZ, PAGE_SIZE),
+        (AT_ENTRY, entry),
+        (AT_UID, 0),
+        (AT_EUID, 0),
+        (AT_GID, 0),
+        (AT_EGID, 0),
+        (AT_RANDOM, random),
+        (AT_EXECFN, execfn),
+        (AT_VVAR, vvar::range().start),
+        (AT_NULL, 0),
+    ];
+
+    let mut vector = Vec::with_capacity(words);
+    vector.push(args.len());
+    vector.extend(argv.iter().chain(&[0]));
+    vector.extend(envp.iter().chain(&[0]));
+    vector.extend(auxv.iter().flat_map(|(key, value)| [*key, *value]));
+    let sp = (top - words * word) & !0xf;
+    for (i, value) in vector.iter().enumerate() {
+        unsafe { (sp as *mut usize).add(i).write(*value) };
     }
+
+    let mut cx = TrapContext::init_app_context(entry, sp);
+    cx.x10 = args.len();
+    cx.x11 = sp + word;
+    cx.x12 = cx.x11 + (args.len() + 1) * word;
+    Ok(cx)
 }
 
+/// the context a process starts with at boot, `argv` is just its name
 pub fn init_app_cx(app_id: usize) -> &'static mut TrapContext {
-    let get_base_i = |app_id| APP_BASE_ADDRESS + app_id * APP_SIZE_LIMIT;
-
-    KERNEL_STACK[app_id].push_context(TrapContext::init_app_context(
-        get_base_i(app_id),
-        USER_STACK[app_id].get_sp(),
-    ))
+    let cx = app_context(app_id, &[APP_NAME[app_id]], &[]).expect("the name fits the stack");
+    KERNEL_STACK[app_id].push_context(cx)
 }
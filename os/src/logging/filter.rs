@@ -0,0 +1,63 @@
+//! Log filter in the `env_logger` syntax
+//!
+//! A filter is a comma separated list of directives. A bare level sets the
+//! default, `path=level` sets the level of module `path` and everything
+//! below it, the longest matching path wins:
+//! `info,toyos::task=trace,toyos::trap=off`.
+
+use alloc::{string::String, vec::Vec};
+
+use log::{Level, LevelFilter};
+
+pub struct LogFilter {
+    default: LevelFilter,
+    /// (module path, level)
+    directives: Vec<(String, LevelFilter)>,
+}
+
+impl LogFilter {
+    pub const fn new(default: LevelFilter) -> Self {
+        Self {
+            default,
+            directives: Vec::new(),
+        }
+    }
+
+    /// `None` if some directive does not parse
+    pub fn parse(spec: &str) -> Option<Self> {
+        let mut filter = Self::new(LevelFilter::Error);
+        for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            match directive.split_once('=') {
+                Some((path, level)) => {
+                    let level = level.parse().ok()?;
+                    filter.directives.push((String::from(path), level));
+                }
+                None => filter.default = directive.parse().ok()?,
+            }
+        }
+        Some(filter)
+    }
+
+    /// the most verbose level any module may log at
+    pub fn max_level(&self) -> LevelFilter {
+        self.directives
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(self.default, LevelFilter::max)
+    }
+
+    pub fn enabled(&self, target: &str, level: Level) -> bool {
+        let is_under = |path: &str| {
+            target
+                .strip_prefix(path)
+                .map_or(false, |rest| rest.is_empty() || rest.starts_with("::"))
+        };
+        let filter = self
+            .directives
+            .iter()
+            .filter(|(path, _)| is_under(path))
+            .max_by_key(|(path, _)| path.len())
+            .map_or(self.default, |(_, level)| *level);
+        level <= filter
+    }
+}
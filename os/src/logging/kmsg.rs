@@ -0,0 +1,233 @@
+//! Kernel log ring buffer
+//!
+//! Every record that passes the log filter is kept here, whether or not it
+//! is printed, so the boot log can be read back with `sys_syslog` later on.
+//! Once the ring is full the oldest records are overwritten.
+
+use core::{
+    fmt::{self, Write},
+    time::Duration,
+};
+
+use log::{Level, Record};
+use spin::Mutex;
+
+const KMSG_RECORDS: usize = 128;
+const MESSAGE_LEN: usize = 120;
+
+#[derive(Copy, Clone)]
+pub struct KmsgRecord {
+    pub seq: usize,
+    pub level: Level,
+    pub timestamp: Duration,
+    pub cpu: usize,
+    pub module: &'static str,
+    message: [u8; MESSAGE_LEN],
+    message_len: usize,
+}
+
+impl KmsgRecord {
+    const fn empty() -> Self {
+        Self {
+            seq: 0,
+            level: Level::Trace,
+            timestamp: Duration::ZERO,
+            cpu: 0,
+            module: "",
+            message: [0; MESSAGE_LEN],
+            message_len: 0,
+        }
+    }
+
+    /// the message, cut short if it did not fit
+    pub fn message(&self) -> &str {
+        // truncation may have split a character
+        match core::str::from_utf8(&self.message[..self.message_len]) {
+            Ok(message) => message,
+            Err(e) => core::str::from_utf8(&self.message[..e.valid_up_to()]).unwrap(),
+        }
+    }
+
+    /// syslog priority of the record, as in `<6>` for info
+    pub fn priority(&self) -> usize {
+        level_priority(self.level)
+    }
+}
+
+pub fn level_priority(level: Level) -> usize {
+    match level {
+        Level::Error => 3,
+        Level::Warn => 4,
+        Level::Info => 6,
+        Level::Debug | Level::Trace => 7,
+    }
+}
+
+impl fmt::Display for KmsgRecord {
+    /// `<6>[    1.234567] [0] toyos::task: message`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "<{}>[{:5}.{:06}] [{}] {}: {}",
+            self.priority(),
+            self.timestamp.as_secs(),
+            self.timestamp.subsec_micros(),
+            self.cpu,
+            self.module,
+            self.message()
+        )
+    }
+}
+
+/// writes into a record's message, dropping what does not fit
+struct MessageWriter<'a> {
+    record: &'a mut KmsgRecord,
+}
+
+impl Write for MessageWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let record = &mut self.record;
+        let len = s.len().min(MESSAGE_LEN - record.message_len);
+        record.message[record.message_len..record.message_len + len]
+            .copy_from_slice(&s.as_bytes()[..len]);
+        record.message_len += len;
+        Ok(())
+    }
+}
+
+pub struct Kmsg {
+    records: [KmsgRecord; KMSG_RECORDS],
+    /// seq of the next record to be logged
+    next_seq: usize,
+    /// seq of the oldest record not consumed by a destructive read
+    read_seq: usize,
+    /// seq of the oldest record not cleared
+    clear_seq: usize,
+}
+
+pub static KMSG: Mutex<Kmsg> = Mutex::new(Kmsg {
+    records: [KmsgRecord::empty(); KMSG_RECORDS],
+    next_seq: 0,
+    read_seq: 0,
+    clear_seq: 0,
+});
+
+impl Kmsg {
+    pub fn push(&mut self, record: &Record, timestamp: Duration, cpu: usize) {
+        let seq = self.next_seq;
+        let slot = &mut self.records[seq % KMSG_RECORDS];
+        *slot = KmsgRecord {
+            seq,
+            level: record.level(),
+            timestamp,
+            cpu,
+            module: record.module_path_static().unwrap_or("?"),
+            message: [0; MESSAGE_LEN],
+            message_len: 0,
+        };
+        let _ = write!(MessageWriter { record: slot }, "{}", record.args());
+        self.next_seq += 1;
+    }
+
+    /// seq of the oldest record still in the ring
+    fn first_seq(&self) -> usize {
+        self.next_seq.saturating_sub(KMSG_RECORDS)
+    }
+
+    fn get(&self, seq: usize) -> &KmsgRecord {
+        &self.records[seq % KMSG_RECORDS]
+    }
+
+    /// Render the records in `seq..` into `buf`, as many whole lines as fit.
+    /// Returns the bytes written and the seq of the first record left out.
+    pub fn render(&self, seq: usize, buf: &mut [u8]) -> (usize, usize) {
+        let mut written = 0;
+        let mut seq = seq.max(self.first_seq());
+        while seq < self.next_seq {
+            let mut line = LineBuffer::new();
+            let _ = write!(line, "{}", self.get(seq));
+            let line = line.as_bytes();
+            if written + line.len() > buf.len() {
+                break;
+            }
+            buf[written..written + line.len()].copy_from_slice(line);
+            written += line.len();
+            seq += 1;
+        }
+        (written, seq)
+    }
+
+    /// Render the newest records not cleared yet that fit into `buf`, oldest
+    /// first.
+    pub fn render_all(&self, buf: &mut [u8]) -> usize {
+        // walk backwards to find how many of the newest records fit
+        let first = self.clear_seq.max(self.first_seq());
+        let mut start = self.next_seq;
+        let mut total = 0;
+        while start > first {
+            let mut line = LineBuffer::new();
+            let _ = write!(line, "{}", self.get(start - 1));
+            if total + line.as_bytes().len() > buf.len() {
+                break;
+            }
+            total += line.as_bytes().len();
+            start -= 1;
+        }
+        self.render(start, buf).0
+    }
+
+    /// destructive read, returns the bytes written
+    pub fn read(&mut self, buf: &mut [u8]) -> usize {
+        let (written, seq) = self.render(self.read_seq, buf);
+        self.read_seq = seq;
+        written
+    }
+
+    pub fn clear(&mut self) {
+        self.clear_seq = self.next_seq;
+    }
+
+    /// bytes a destructive read would return right now
+    pub fn unread_size(&self) -> usize {
+        (self.read_seq.max(self.first_seq())..self.next_seq)
+            .map(|seq| {
+                let mut line = LineBuffer::new();
+                let _ = write!(line, "{}", self.get(seq));
+                line.as_bytes().len()
+            })
+            .sum()
+    }
+}
+
+/// the longest a rendered record can get
+const LINE_LEN: usize = MESSAGE_LEN + 128;
+
+struct LineBuffer {
+    buf: [u8; LINE_LEN],
+    len: usize,
+}
+
+impl LineBuffer {
+    fn new() -> Self {
+        Self {
+            buf: [0; LINE_LEN],
+            len: 0,
+        }
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+}
+
+impl Write for LineBuffer {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let len = s.len().min(LINE_LEN - self.len);
+        self.buf[self.len..self.len + len].copy_from_slice(&s.as_bytes()[..len]);
+        self.len += len;
+        Ok(())
+    }
+}
+
+/// size of the whole ring in bytes, as `SYSLOG_ACTION_SIZE_BUFFER` reports
+pub const KMSG_BUFFER_SIZE: usize = KMSG_RECORDS * LINE_LEN;
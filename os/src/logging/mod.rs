@@ -0,0 +1,157 @@
+mod filter;
+mod kmsg;
+
+use core::{
+    arch::asm,
+    fmt,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    time::Duration,
+};
+
+pub use filter::LogFilter;
+pub use kmsg::{level_priority, KMSG, KMSG_BUFFER_SIZE};
+use log::{Level, Metadata, Record};
+struct SimpleLogger;
+use log::{LevelFilter, SetLoggerError};
+use owo_colors::{
+    AnsiColors::{Blue, Cyan, Green, Red, Yellow},
+    OwoColorize,
+};
+use spin::Mutex;
+
+use crate::{
+    bootargs,
+    timer::{realtime_at, timer_now, Utc},
+};
+
+static LOGGER: SimpleLogger = SimpleLogger;
+
+static FILTER: Mutex<LogFilter> = Mutex::new(LogFilter::new(LevelFilter::Debug));
+
+/// `color=off` on the command line, for when the console is captured
+static COLOR: AtomicBool = AtomicBool::new(true);
+
+/// `logtime=utc` on the command line stamps console records with the
+/// wall-clock time instead of the time since boot
+static UTC: AtomicBool = AtomicBool::new(false);
+
+/// the stamp of a console record taken at `uptime`
+struct Stamp(Duration);
+
+impl fmt::Display for Stamp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match UTC.load(Ordering::Relaxed) {
+            true => write!(f, "{}", Utc(realtime_at(self.0))),
+            false => write!(f, "{:.6}", self.0.as_secs_f64()),
+        }
+    }
+}
+
+/// Records whose syslog priority is below this are printed, the others only
+/// go into the ring buffer. 8 prints everything.
+pub const DEFAULT_CONSOLE_LOGLEVEL: usize = 8;
+/// the lowest level `SYSLOG_ACTION_CONSOLE_OFF` may go, only emergencies
+pub const MINIMUM_CONSOLE_LOGLEVEL: usize = 1;
+
+static CONSOLE_LOGLEVEL: AtomicUsize = AtomicUsize::new(DEFAULT_CONSOLE_LOGLEVEL);
+
+pub fn console_loglevel() -> usize {
+    CONSOLE_LOGLEVEL.load(Ordering::Relaxed)
+}
+
+/// returns the old level
+pub fn set_console_loglevel(level: usize) -> usize {
+    CONSOLE_LOGLEVEL.swap(level, Ordering::Relaxed)
+}
+
+/// Install the logger. The filter comes from `log=` on the command line,
+/// `default` is used if there is none.
+pub fn init(default: LevelFilter) -> Result<(), SetLoggerError> {
+    COLOR.store(bootargs::get("color") != Some("off"), Ordering::Relaxed);
+    UTC.store(bootargs::get("logtime") == Some("utc"), Ordering::Relaxed);
+    let spec = bootargs::get("log");
+    let filter = spec.and_then(LogFilter::parse);
+    let invalid = spec.is_some() && filter.is_none();
+    set_filter(filter.unwrap_or_else(|| LogFilter::new(default)));
+    log::set_logger(&LOGGER)?;
+    if invalid {
+        log::warn!("invalid log filter {:?}, using {}", spec.unwrap(), default);
+    }
+    Ok(())
+}
+
+/// replace the log filter, e.g. for `sys_set_log_filter`
+pub fn set_filter(filter: LogFilter) {
+    log::set_max_level(filter.max_level());
+    *FILTER.lock() = filter;
+}
+
+fn cpuid() -> usize {
+    let mut cpuid;
+    unsafe {
+        asm!("mv {cpuid}, tp",
+            cpuid = out(reg) cpuid)
+    }
+    cpuid
+}
+
+impl log::Log for SimpleLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        FILTER.lock().enabled(metadata.target(), metadata.level())
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            let timestamp = timer_now();
+            // a record logged while the ring is being read is not stored,
+            // rather than deadlocking
+            if let Some(mut kmsg) = KMSG.try_lock() {
+                kmsg.push(record, timestamp, cpuid());
+            }
+            if level_priority(record.level()) >= console_loglevel() {
+                return;
+            }
+
+            let file = record.file().map_or("", |s| s);
+            let line = record.line().map_or(0, |s| s);
+
+            if !COLOR.load(Ordering::Relaxed) {
+                println!(
+                    "[{}][K][{}] [{:<5}] {}:{}  {}",
+                    Stamp(timestamp),
+                    cpuid(),
+                    record.level(),
+                    file,
+                    line,
+                    record.args()
+                );
+                return;
+            }
+
+            let level = match record.level() {
+                Level::Error => "[ERRO]".color(Red),
+                Level::Warn => "[WARN]".color(Yellow),
+                Level::Info => "[INFO]".color(Blue),
+                Level::Debug => "[DEBU]".color(Green),
+                Level::Trace => "[TRAC]".color(Cyan),
+            };
+
+            println!(
+                "{}{}{}{}{}{}{} {} {}:{}  {}",
+                "[".green(),
+                Stamp(timestamp).green(),
+                "]".green(),
+                "[K]".green(),
+                "[".green(),
+                cpuid().green(),
+                "]".green(),
+                level.bold(),
+                file.cyan(),
+                line.cyan(),
+                record.args()
+            );
+        }
+    }
+
+    fn flush(&self) {}
+}
@@ -2,15 +2,32 @@
 #![no_main]
 #![feature(asm_const)]
 
-use log::LevelFilter;
+use log::{info, LevelFilter};
 use toyos::task::run_first_task;
 
 #[no_mangle]
-pub fn main() -> ! {
+pub fn main(hartid: usize, dtb: usize) -> ! {
     toyos::clear_bss();
+    toyos::mm::init_heap();
+    toyos::bootargs::init(dtb);
+    toyos::timer::init(dtb);
+    // before the logger, so records are stamped with the right time
+    let now = toyos::rtc::init(dtb);
     toyos::logging::init(LevelFilter::Debug).unwrap();
+    info!(
+        "hart {} booting, bootargs: {:?}",
+        hartid,
+        toyos::bootargs::bootargs()
+    );
+    match now {
+        Some(now) => info!("rtc: {}", toyos::timer::Utc(now)),
+        None => log::warn!("no rtc, the wall clock starts at the epoch"),
+    }
     toyos::trap::init();
+    toyos::vvar::init();
     toyos::loader::load_apps();
+    toyos::syscall::trace::init();
+    toyos::profile::init();
 
     toyos::trap::enable_timer_interrupt();
     toyos::timer::set_next_trigger();
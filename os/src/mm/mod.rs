@@ -0,0 +1,12 @@
+//! Memory management
+
+mod heap_allocator;
+mod user_memory;
+mod user_ptr;
+
+pub use heap_allocator::{heap_usage, init_heap};
+pub use user_memory::{
+    page_align_up, prot_str, UserMemory, MAP_ANONYMOUS, MAP_FIXED, MAP_FIXED_NOREPLACE,
+    MAP_PRIVATE, MAP_SHARED, PROT_EXEC, PROT_NONE, PROT_READ, PROT_WRITE,
+};
+pub use user_ptr::{read_cstr, UserPtr, UserSlice};
@@ -0,0 +1,259 @@
+//! The memory of a process: program break and anonymous mappings
+//!
+//! There is no address translation, so a process owns exactly its app slot.
+//! The image sits at the bottom of the slot, the heap grows up from its end
+//! through `brk` and anonymous mappings are placed top down from the end of
+//! the slot. Protections are tracked per page and checked whenever the
+//! kernel touches user memory, user mode accesses cannot be policed without
+//! page tables.
+
+use alloc::collections::BTreeMap;
+use core::ops::Range;
+
+use crate::{config::PAGE_SIZE, syscall::errno};
+
+pub const PROT_NONE: usize = 0;
+pub const PROT_READ: usize = 1;
+pub const PROT_WRITE: usize = 2;
+pub const PROT_EXEC: usize = 4;
+
+pub const MAP_SHARED: usize = 0x01;
+pub const MAP_PRIVATE: usize = 0x02;
+pub const MAP_FIXED: usize = 0x10;
+pub const MAP_ANONYMOUS: usize = 0x20;
+pub const MAP_FIXED_NOREPLACE: usize = 0x100000;
+
+/// what the image and the heap may be used for
+const IMAGE_PROT: usize = PROT_READ | PROT_WRITE | PROT_EXEC;
+const HEAP_PROT: usize = PROT_READ | PROT_WRITE;
+
+pub fn page_align_up(addr: usize) -> usize {
+    (addr + PAGE_SIZE - 1) & !(PAGE_SIZE - 1)
+}
+
+pub struct UserMemory {
+    slot: Range<usize>,
+    /// end of the loaded image and its `.bss`, the heap starts here
+    brk_start: usize,
+    brk: usize,
+    /// anonymous mappings, start -> (end, prot), page aligned and disjoint
+    areas: BTreeMap<usize, (usize, usize)>,
+}
+
+impl UserMemory {
+    /// `image_len` is what the app takes up at the bottom of `slot`, `.bss`
+    /// included
+    pub fn new(slot: Range<usize>, image_len: usize) -> Self {
+        let brk_start = page_align_up(slot.start + image_len);
+        UserMemory {
+            slot,
+            brk_start,
+            brk: brk_start,
+            areas: BTreeMap::new(),
+        }
+    }
+
+    pub fn brk(&self) -> usize {
+        self.brk
+    }
+
+    /// the heap may reach up to the lowest mapping
+    fn heap_limit(&self) -> usize {
+        self.areas.keys().next().copied().unwrap_or(self.slot.end)
+    }
+
+    /// Move the program break to `new_brk`, new memory reads as zero.
+    /// Returns the new break, `-ENOMEM` if it does not fit.
+    pub fn set_brk(&mut self, new_brk: usize) -> Result<usize, isize> {
+        // the limit is page aligned, so the last heap page fits as well
+        if new_brk < self.brk_start || new_brk > self.heap_limit() {
+            return Err(-errno::ENOMEM);
+        }
+        if new_brk > self.brk {
+            zero(self.brk..new_brk);
+        }
+        self.brk = new_brk;
+        Ok(new_brk)
+    }
+
+    fn overlaps(&self, range: &Range<usize>) -> bool {
+        self.areas
+            .range(..range.end)
+            .next_back()
+            .map_or(false, |(_, &(end, _))| end > range.start)
+    }
+
+    /// the highest free gap of `len` bytes above the heap
+    fn find_free(&self, len: usize) -> Option<usize> {
+        let mut end = self.slot.end;
+        for (&start, &(area_end, _)) in self.areas.iter().rev() {
+            if end - area_end >= len {
+                break;
+            }
+            end = start;
+        }
+        let start = end.checked_sub(len)?;
+        (start >= page_align_up(self.brk)).then_some(start)
+    }
+
+    /// Map `len` bytes of zeroed anonymous memory, returns the start.
+    /// `addr` is a hint unless `MAP_FIXED` or `MAP_FIXED_NOREPLACE` is set.
+    pub fn mmap(
+        &mut self,
+        addr: usize,
+        len: usize,
+        prot: usize,
+        flags: usize,
+    ) -> Result<usize, isize> {
+        if len == 0
+            || addr % PAGE_SIZE != 0
+            || prot & !IMAGE_PROT != 0
+            || flags & MAP_ANONYMOUS == 0
+            || flags & (MAP_SHARED | MAP_PRIVATE) == 0
+        {
+            return Err(-errno::EINVAL);
+        }
+        if len > self.slot.len() {
+            return Err(-errno::ENOMEM);
+        }
+        let len = page_align_up(len);
+        let fixed = flags & (MAP_FIXED | MAP_FIXED_NOREPLACE) != 0;
+        let wanted = addr..addr.saturating_add(len);
+        let start = if addr != 0 || fixed {
+            let in_slot = wanted.start >= page_align_up(self.brk) && wanted.end <= self.slot.end;
+            if !in_slot {
+                if fixed {
+                    return Err(-errno::ENOMEM);
+                }
+                self.find_free(len).ok_or(-errno::ENOMEM)?
+            } else if !self.overlaps(&wanted) {
+                addr
+            } else if flags & MAP_FIXED_NOREPLACE != 0 {
+                return Err(-errno::EEXIST);
+            } else if flags & MAP_FIXED != 0 {
+                self.munmap(addr, len)?;
+                addr
+            } else {
+                self.find_free(len).ok_or(-errno::ENOMEM)?
+            }
+        } else {
+            self.find_free(len).ok_or(-errno::ENOMEM)?
+        };
+        zero(start..start + len);
+        self.areas.insert(start, (start + len, prot));
+        Ok(start)
+    }
+
+    /// split the area containing `addr` in two at `addr`
+    fn split_at(&mut self, addr: usize) {
+        let found = self.areas.range(..addr).next_back().map(|(&s, &a)| (s, a));
+        if let Some((start, (end, prot))) = found {
+            if end > addr {
+                self.areas.insert(start, (addr, prot));
+                self.areas.insert(addr, (end, prot));
+            }
+        }
+    }
+
+    fn check_range(&self, addr: usize, len: usize) -> Result<Range<usize>, isize> {
+        if len == 0 || len > self.slot.len() || addr % PAGE_SIZE != 0 {
+            return Err(-errno::EINVAL);
+        }
+        let end = addr.checked_add(page_align_up(len)).ok_or(-errno::EINVAL)?;
+        if addr < self.slot.start || end > self.slot.end {
+            return Err(-errno::EINVAL);
+        }
+        Ok(addr..end)
+    }
+
+    /// Unmap whatever mappings lie in the range, unmapped pages are skipped
+    /// like on Linux.
+    pub fn munmap(&mut self, addr: usize, len: usize) -> Result<(), isize> {
+        let range = self.check_range(addr, len)?;
+        self.split_at(range.start);
+        self.split_at(range.end);
+        self.areas.retain(|start, _| !range.contains(start));
+        Ok(())
+    }
+
+    /// Change the protection of mapped pages. The whole range must be
+    /// mapped by `mmap`, else `-ENOMEM`.
+    pub fn mprotect(&mut self, addr: usize, len: usize, prot: usize) -> Result<(), isize> {
+        let range = self.check_range(addr, len)?;
+        if prot & !IMAGE_PROT != 0 {
+            return Err(-errno::EINVAL);
+        }
+        let mut next = range.start;
+        for (&start, &(end, _)) in self.areas.range(..range.end) {
+            if end <= next {
+                continue;
+            }
+            if start > next {
+                return Err(-errno::ENOMEM);
+            }
+            next = end;
+        }
+        if next < range.end {
+            return Err(-errno::ENOMEM);
+        }
+        self.split_at(range.start);
+        self.split_at(range.end);
+        for (_, (_, area_prot)) in self.areas.range_mut(range) {
+            *area_prot = prot;
+        }
+        Ok(())
+    }
+
+    /// The protection all of `range` has, `None` if part of it is not
+    /// mapped at all. Pages of the slot between heap and mappings are not
+    /// mapped.
+    pub fn prot_of(&self, range: Range<usize>) -> Option<usize> {
+        if range.start < self.slot.start || range.end > self.slot.end {
+            return None;
+        }
+        let mut prot = IMAGE_PROT;
+        let mut next = range.start;
+        if next < self.brk_start {
+            next = range.end.min(self.brk_start);
+        }
+        // the heap reaches to the end of the page the break is in
+        if next < range.end && next < page_align_up(self.brk) {
+            prot &= HEAP_PROT;
+            next = range.end.min(page_align_up(self.brk));
+        }
+        for (&start, &(end, area_prot)) in self.areas.range(..range.end) {
+            if end <= next {
+                continue;
+            }
+            if start > next {
+                return None;
+            }
+            prot &= area_prot;
+            next = end;
+        }
+        (next >= range.end).then_some(prot)
+    }
+
+    /// (range, prot, name) of the image, the heap and each mapping
+    pub fn regions(&self) -> impl Iterator<Item = (Range<usize>, usize, &'static str)> + '_ {
+        let image = (self.slot.start..self.brk_start, IMAGE_PROT, "");
+        let heap = (self.brk_start..page_align_up(self.brk), HEAP_PROT, "[heap]");
+        let heap = (self.brk > self.brk_start).then_some(heap);
+        core::iter::once(image).chain(heap).chain(
+            self.areas
+                .iter()
+                .map(|(&start, &(end, prot))| (start..end, prot, "")),
+        )
+    }
+}
+
+/// `rwx` like in `/proc/<pid>/maps`
+pub fn prot_str(prot: usize) -> &'static str {
+    const NAMES: [&str; 8] = ["---", "r--", "-w-", "rw-", "--x", "r-x", "-wx", "rwx"];
+    NAMES[prot & IMAGE_PROT]
+}
+
+fn zero(range: Range<usize>) {
+    // the range lies in the app slot, which the kernel can always write
+    unsafe { core::slice::from_raw_parts_mut(range.start as *mut u8, range.len()).fill(0) }
+}
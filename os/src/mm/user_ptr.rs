@@ -0,0 +1,234 @@
+//! Access to user memory from syscalls
+//!
+//! A syscall never dereferences a pointer it got from user space. It wraps it
+//! in a [`UserPtr`] or a [`UserSlice`], whose copies check every page against
+//! the memory of the current process first and give `-EFAULT` instead of
+//! faulting in the kernel. Buffers are walked page by page and each page is
+//! translated on its own: the translation is the identity while there is no
+//! paging, but nothing here relies on neighbouring user pages being
+//! neighbours in physical memory.
+
+use alloc::{string::String, vec::Vec};
+use core::{marker::PhantomData, mem::MaybeUninit, ops::Range};
+
+use super::{PROT_READ, PROT_WRITE};
+use crate::{
+    config::PAGE_SIZE,
+    syscall::errno::{EFAULT, EINVAL, ENAMETOOLONG},
+    task::TASK_MANAGER,
+};
+
+/// the physical range behind the user range `range`, which lies in one page
+fn translate(range: Range<usize>, prot: usize) -> Option<Range<usize>> {
+    TASK_MANAGER
+        .current_access_ok(range.clone(), prot)
+        .then_some(range)
+}
+
+/// user `addr..end` cut at page boundaries
+#[derive(Clone)]
+struct PageChunks {
+    addr: usize,
+    end: usize,
+}
+
+impl Iterator for PageChunks {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Range<usize>> {
+        if self.addr >= self.end {
+            return None;
+        }
+        let page_end = (self.addr | (PAGE_SIZE - 1)).saturating_add(1);
+        let chunk = self.addr..page_end.min(self.end);
+        self.addr = chunk.end;
+        Some(chunk)
+    }
+}
+
+/// `len` bytes of user memory at `addr`
+#[derive(Copy, Clone, Debug)]
+pub struct UserSlice {
+    addr: usize,
+    len: usize,
+}
+
+impl UserSlice {
+    pub fn new(addr: usize, len: usize) -> Self {
+        UserSlice { addr, len }
+    }
+
+    pub fn addr(&self) -> usize {
+        self.addr
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// the first `len` bytes
+    pub fn prefix(&self, len: usize) -> UserSlice {
+        UserSlice::new(self.addr, self.len.min(len))
+    }
+
+    /// Translate every page, the whole slice is checked before anything is
+    /// handed out so a failing copy has no effect.
+    fn translate_all(&self, prot: usize) -> Result<Vec<Range<usize>>, isize> {
+        let end = self.addr.checked_add(self.len).ok_or(-EFAULT)?;
+        PageChunks {
+            addr: self.addr,
+            end,
+        }
+        .map(|chunk| translate(chunk, prot).ok_or(-EFAULT))
+        .collect()
+    }
+
+    /// the slice as one kernel slice per page, for reading
+    pub fn chunks(&self) -> Result<impl Iterator<Item = &'static [u8]>, isize> {
+        let chunks = self.translate_all(PROT_READ)?;
+        Ok(chunks.into_iter().map(|chunk| unsafe {
+            core::slice::from_raw_parts(chunk.start as *const u8, chunk.len())
+        }))
+    }
+
+    /// the slice as one kernel slice per page, for writing
+    pub fn chunks_mut(&self) -> Result<impl Iterator<Item = &'static mut [u8]>, isize> {
+        let chunks = self.translate_all(PROT_WRITE)?;
+        Ok(chunks.into_iter().map(|chunk| unsafe {
+            core::slice::from_raw_parts_mut(chunk.start as *mut u8, chunk.len())
+        }))
+    }
+
+    /// check that the slice may be written, e.g. before consuming data that
+    /// is to be copied into it
+    pub fn check_writable(&self) -> Result<(), isize> {
+        self.translate_all(PROT_WRITE).map(|_| ())
+    }
+
+    /// Copy as much of the slice as fits into `dst`, returns the bytes
+    /// copied.
+    pub fn copy_from_user(&self, dst: &mut [u8]) -> Result<usize, isize> {
+        let mut copied = 0;
+        for chunk in self.prefix(dst.len()).chunks()? {
+            dst[copied..copied + chunk.len()].copy_from_slice(chunk);
+            copied += chunk.len();
+        }
+        Ok(copied)
+    }
+
+    /// Copy as much of `src` as fits into the slice, returns the bytes
+    /// copied.
+    pub fn copy_to_user(&self, src: &[u8]) -> Result<usize, isize> {
+        let mut copied = 0;
+        for chunk in self.prefix(src.len()).chunks_mut()? {
+            chunk.copy_from_slice(&src[copied..copied + chunk.len()]);
+            copied += chunk.len();
+        }
+        Ok(copied)
+    }
+}
+
+/// a `T` in user memory
+pub struct UserPtr<T> {
+    addr: usize,
+    _marker: PhantomData<*mut T>,
+}
+
+impl<T> Clone for UserPtr<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for UserPtr<T> {}
+
+impl<T> UserPtr<T> {
+    pub fn new(addr: usize) -> Self {
+        UserPtr {
+            addr,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn addr(&self) -> usize {
+        self.addr
+    }
+
+    pub fn is_null(&self) -> bool {
+        self.addr == 0
+    }
+
+    fn bytes(&self) -> UserSlice {
+        UserSlice::new(self.addr, core::mem::size_of::<T>())
+    }
+
+    /// The object in place, for the atomics `sys_futex` waits on. It must be
+    /// aligned and must not cross a page.
+    pub fn as_ref(&self) -> Result<&'static T, isize> {
+        if self.addr % core::mem::align_of::<T>() != 0 {
+            return Err(-EFAULT);
+        }
+        let mut chunks = self.bytes().translate_all(PROT_READ | PROT_WRITE)?;
+        match chunks.pop() {
+            Some(chunk) if chunks.is_empty() => Ok(unsafe { &*(chunk.start as *const T) }),
+            _ => Err(-EFAULT),
+        }
+    }
+}
+
+impl<T: Copy> UserPtr<T> {
+    /// copy the object out of user memory
+    pub fn read(&self) -> Result<T, isize> {
+        let mut val = MaybeUninit::<T>::uninit();
+        let dst = unsafe {
+            core::slice::from_raw_parts_mut(val.as_mut_ptr() as *mut u8, core::mem::size_of::<T>())
+        };
+        self.bytes().copy_from_user(dst)?;
+        Ok(unsafe { val.assume_init() })
+    }
+
+    /// copy `val` into user memory
+    pub fn write(&self, val: T) -> Result<(), isize> {
+        let src = unsafe {
+            core::slice::from_raw_parts(&val as *const T as *const u8, core::mem::size_of::<T>())
+        };
+        self.bytes().copy_to_user(src).map(|_| ())
+    }
+
+    /// copy the first `len` bytes of `val` into user memory, for structs
+    /// that grow by version
+    pub fn write_prefix(&self, val: T, len: usize) -> Result<(), isize> {
+        let src = unsafe {
+            core::slice::from_raw_parts(&val as *const T as *const u8, core::mem::size_of::<T>())
+        };
+        self.bytes().prefix(len).copy_to_user(src).map(|_| ())
+    }
+}
+
+/// Copy the NUL-terminated string at `addr` out of user space. Pages are
+/// only checked as far as the string goes. `Err` holds `-EFAULT`,
+/// `-ENAMETOOLONG` if there is no NUL in `max_len` bytes, or `-EINVAL` if it
+/// is not UTF-8.
+pub fn read_cstr(addr: usize, max_len: usize) -> Result<String, isize> {
+    let chunks = PageChunks {
+        addr,
+        end: addr.saturating_add(max_len),
+    };
+    let mut bytes = Vec::new();
+    for chunk in chunks {
+        let chunk = translate(chunk, PROT_READ).ok_or(-EFAULT)?;
+        let chunk = unsafe { core::slice::from_raw_parts(chunk.start as *const u8, chunk.len()) };
+        match chunk.iter().position(|c| *c == 0) {
+            Some(nul) => {
+                bytes.extend_from_slice(&chunk[..nul]);
+                return String::from_utf8(bytes).map_err(|_| -EINVAL);
+            }
+            None => bytes.extend_from_slice(chunk),
+        }
+    }
+    Err(-ENAMETOOLONG)
+}
@@ -0,0 +1,233 @@
+//! Sampling CPU profiler
+//!
+//! When profiling is on, every `SupervisorTimer` tick records where the
+//! interrupted task was: `sepc`, the task, the privilege mode and the return
+//! addresses found by walking its frame pointers. Samples go into a buffer
+//! per CPU and can be dumped as a flat text profile, or as folded stacks for
+//! `flamegraph.pl`, through `sys_profile` or on the console at shutdown.
+//!
+//! The kernel runs with interrupts off, so a tick that comes due in the
+//! kernel is only taken once it returns to user mode. [`sample_kernel`]
+//! looks for such a tick on the way out and charges it to the kernel, at the
+//! user `sepc` the trap returns to, which tells what the kernel time was
+//! spent on; the interrupt taken right after is then not counted again.
+//!
+//! Turn it on at boot with `profile` on the command line, `profile=text`
+//! picks the format of the shutdown dump, folded stacks by default.
+
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+use core::{
+    fmt::Write,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+
+use riscv::register::{sip, sstatus::SPP};
+
+use crate::{
+    bootargs, link_app::APP_NAME, stack_trace::backtrace, sync::UPSafeCell, task::TASK_MANAGER,
+    trap::context::TrapContext,
+};
+
+pub const MAX_CPUS: usize = 1;
+/// samples kept per CPU, the oldest are dropped after that
+const MAX_SAMPLES: usize = 1024;
+const MAX_DEPTH: usize = 8;
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum ProfileFormat {
+    /// `count pid tid mode sepc` per distinct location, hottest first
+    Text,
+    /// `app;caller;...;sepc count` per distinct stack, `;[kernel]` is added
+    /// to kernel samples
+    Folded,
+}
+
+#[derive(Copy, Clone)]
+struct Sample {
+    sepc: usize,
+    pid: usize,
+    tid: usize,
+    user: bool,
+    depth: usize,
+    /// return addresses, innermost first
+    stack: [usize; MAX_DEPTH],
+}
+
+struct SampleBuffer {
+    samples: Vec<Sample>,
+    /// where the next sample goes once `samples` is full
+    next: usize,
+    dropped: usize,
+}
+
+impl SampleBuffer {
+    const fn new() -> Self {
+        Self {
+            samples: Vec::new(),
+            next: 0,
+            dropped: 0,
+        }
+    }
+
+    fn push(&mut self, sample: Sample) {
+        if self.samples.len() < MAX_SAMPLES {
+            self.samples.push(sample);
+        } else {
+            self.samples[self.next] = sample;
+            self.next = (self.next + 1) % MAX_SAMPLES;
+            self.dropped += 1;
+        }
+    }
+
+    fn clear(&mut self) {
+        self.samples.clear();
+        self.next = 0;
+        self.dropped = 0;
+    }
+}
+
+static PROFILING: AtomicBool = AtomicBool::new(false);
+/// where user mode resumed with a tick pending that was charged to the
+/// kernel already, 0 for none
+static KERNEL_SAMPLED_AT: AtomicUsize = AtomicUsize::new(0);
+
+lazy_static::lazy_static! {
+    static ref SAMPLES: [UPSafeCell<SampleBuffer>; MAX_CPUS] =
+        [(); MAX_CPUS].map(|_| unsafe { UPSafeCell::new(SampleBuffer::new()) });
+}
+
+/// there is only the boot hart for now
+fn this_cpu() -> usize {
+    0
+}
+
+/// Apply `profile` of the command line.
+pub fn init() {
+    if bootargs::get("profile").is_some() {
+        start();
+    }
+}
+
+pub fn is_profiling() -> bool {
+    PROFILING.load(Ordering::Relaxed)
+}
+
+/// drop the samples so far and start sampling
+pub fn start() {
+    for buffer in SAMPLES.iter() {
+        let mut buffer = buffer.exclusive_access();
+        buffer.clear();
+        buffer.samples.reserve_exact(MAX_SAMPLES);
+    }
+    PROFILING.store(true, Ordering::Relaxed);
+}
+
+pub fn stop() {
+    PROFILING.store(false, Ordering::Relaxed);
+}
+
+/// called on every timer tick with the interrupted context
+pub fn sample(cx: &TrapContext) {
+    if !is_profiling() || KERNEL_SAMPLED_AT.swap(0, Ordering::Relaxed) == cx.sepc {
+        return;
+    }
+    record(cx, cx.sstatus.spp() == SPP::User);
+}
+
+/// Called on the way back to user mode with the context it returns to. A
+/// tick that came due meanwhile was spent in the kernel.
+pub fn sample_kernel(cx: &TrapContext) {
+    if !is_profiling() || !sip::read().stimer() {
+        return;
+    }
+    KERNEL_SAMPLED_AT.store(cx.sepc, Ordering::Relaxed);
+    record(cx, false);
+}
+
+/// a sample at `cx`, in user mode or in the kernel on behalf of it
+fn record(cx: &TrapContext, user: bool) {
+    let mut stack = [0; MAX_DEPTH];
+    let depth = if cx.sstatus.spp() == SPP::User {
+        let ustack = TASK_MANAGER.get_current_task_stack();
+        backtrace(
+            cx.x8,
+            ustack.start as usize..ustack.end as usize,
+            &mut stack,
+        )
+    } else {
+        0
+    };
+    SAMPLES[this_cpu()].exclusive_access().push(Sample {
+        sepc: cx.sepc,
+        pid: TASK_MANAGER.get_current_pid(),
+        tid: TASK_MANAGER.get_current_task(),
+        user,
+        depth,
+        stack,
+    });
+}
+
+/// render all samples taken so far
+pub fn dump(format: ProfileFormat) -> String {
+    let mut out = String::new();
+    match format {
+        ProfileFormat::Text => {
+            let mut counts: BTreeMap<(usize, usize, bool, usize), usize> = BTreeMap::new();
+            let (mut total, mut dropped) = (0, 0);
+            for buffer in SAMPLES.iter() {
+                let buffer = buffer.exclusive_access();
+                dropped += buffer.dropped;
+                for sample in buffer.samples.iter() {
+                    *counts
+                        .entry((sample.pid, sample.tid, sample.user, sample.sepc))
+                        .or_default() += 1;
+                    total += 1;
+                }
+            }
+            let mut counts: Vec<_> = counts.into_iter().collect();
+            counts.sort_by(|a, b| b.1.cmp(&a.1));
+            let _ = writeln!(out, "# {} samples, {} older ones dropped", total, dropped);
+            let _ = writeln!(out, "# count pid tid mode sepc");
+            for ((pid, tid, user, sepc), count) in counts {
+                let mode = if user { "U" } else { "S" };
+                let _ = writeln!(out, "{} {} {} {} {:#x}", count, pid, tid, mode, sepc);
+            }
+        }
+        ProfileFormat::Folded => {
+            let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+            for buffer in SAMPLES.iter() {
+                for sample in buffer.exclusive_access().samples.iter() {
+                    let mut line = String::from(APP_NAME[sample.pid]);
+                    // outermost caller first
+                    for ra in sample.stack[..sample.depth].iter().rev() {
+                        let _ = write!(line, ";{:#x}", ra);
+                    }
+                    let _ = write!(line, ";{:#x}", sample.sepc);
+                    if !sample.user {
+                        line.push_str(";[kernel]");
+                    }
+                    *counts.entry(line).or_default() += 1;
+                }
+            }
+            for (line, count) in counts {
+                let _ = writeln!(out, "{} {}", line, count);
+            }
+        }
+    }
+    out
+}
+
+/// print the profile on the console right before shutting down
+pub fn dump_at_shutdown() {
+    if !is_profiling() {
+        return;
+    }
+    stop();
+    let format = match bootargs::get("profile") {
+        Some("text") => ProfileFormat::Text,
+        _ => ProfileFormat::Folded,
+    };
+    println!("[kernel] ==== profile begin ====");
+    print!("{}", dump(format));
+    println!("[kernel] ==== profile end ====");
+}
@@ -0,0 +1,44 @@
+//! Goldfish RTC, the real-time clock of QEMU virt
+//!
+//! It counts nanoseconds since the Unix epoch in two 32-bit registers,
+//! reading `TIME_LOW` latches `TIME_HIGH`. Only used at boot to set the
+//! wall-clock time, which the timer keeps from then on.
+
+use core::{ptr::read_volatile, time::Duration};
+
+use crate::{fdt::Fdt, timer::set_realtime};
+
+const TIME_LOW: usize = 0x00;
+const TIME_HIGH: usize = 0x04;
+
+/// where virt puts it, for when there is no device tree
+const VIRT_RTC_BASE: usize = 0x101000;
+
+/// the MMIO base from `reg` of `/soc/rtc` in the device tree at `dtb`
+fn find(dtb: usize) -> Option<usize> {
+    let fdt = match unsafe { Fdt::from_addr(dtb) } {
+        Some(fdt) => fdt,
+        None => return Some(VIRT_RTC_BASE),
+    };
+    if fdt.property_str("/soc/rtc", "compatible")? != "google,goldfish-rtc" {
+        return None;
+    }
+    // two address cells on virt
+    let reg = fdt.property("/soc/rtc", "reg")?;
+    Some(u64::from_be_bytes(reg.get(..8)?.try_into().unwrap()) as usize)
+}
+
+/// time since the epoch of the RTC at `base`
+fn read(base: usize) -> Duration {
+    let low = unsafe { read_volatile((base + TIME_LOW) as *const u32) };
+    let high = unsafe { read_volatile((base + TIME_HIGH) as *const u32) };
+    Duration::from_nanos((high as u64) << 32 | low as u64)
+}
+
+/// Set the wall-clock time from the RTC, if there is one. Returns the time
+/// read.
+pub fn init(dtb: usize) -> Option<Duration> {
+    let now = read(find(dtb)?);
+    set_realtime(now)?;
+    Some(now)
+}
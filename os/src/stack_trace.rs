@@ -1,4 +1,4 @@
-use core::arch::asm;
+use core::{arch::asm, ops::Range};
 
 use owo_colors::OwoColorize;
 
@@ -20,3 +20,29 @@ pub unsafe fn get_fp() -> *const usize {
     asm!("mv {}, fp", out(reg) fp);
     fp
 }
+
+/// Collect the return addresses of the frames starting at `fp` into `out`,
+/// innermost first, for as long as the frame pointers stay inside `stack`.
+/// Returns how many were found.
+pub fn backtrace(mut fp: usize, stack: Range<usize>, out: &mut [usize]) -> usize {
+    let mut depth = 0;
+    while depth < out.len()
+        && fp % core::mem::size_of::<usize>() == 0
+        && fp >= stack.start + 2 * core::mem::size_of::<usize>()
+        && fp <= stack.end
+    {
+        let fp_ptr = fp as *const usize;
+        let (saved_ra, saved_fp) = unsafe { (*fp_ptr.sub(1), *fp_ptr.sub(2)) };
+        if saved_ra == 0 {
+            break;
+        }
+        out[depth] = saved_ra;
+        depth += 1;
+        // frames only go up the stack
+        if saved_fp <= fp {
+            break;
+        }
+        fp = saved_fp;
+    }
+    depth
+}
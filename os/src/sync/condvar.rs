@@ -0,0 +1,52 @@
+//! Condition variable managed by the kernel
+
+use alloc::collections::VecDeque;
+
+use super::{Mutex, UPSafeCell};
+use crate::task::{block_current_and_run_next, wakeup_task, TASK_MANAGER};
+
+pub struct Condvar {
+    wait_queue: UPSafeCell<VecDeque<usize>>,
+}
+
+impl Condvar {
+    pub fn new() -> Self {
+        Self {
+            wait_queue: unsafe { UPSafeCell::new(VecDeque::new()) },
+        }
+    }
+
+    /// wake up one waiter, if any
+    pub fn signal(&self) {
+        let mut wait_queue = self.wait_queue.exclusive_access();
+        while let Some(tid) = wait_queue.pop_front() {
+            if wakeup_task(tid) {
+                break;
+            }
+        }
+    }
+
+    /// Release `mutex`, sleep until signaled, then lock `mutex` again. As
+    /// usual the caller has to re-check its condition afterwards. Returns
+    /// `None` without waiting if current task does not hold `mutex`.
+    pub fn wait(&self, mutex: &Mutex) -> Option<()> {
+        // queue up first, so a signal right after the unlock is not lost
+        self.wait_queue
+            .exclusive_access()
+            .push_back(TASK_MANAGER.get_current_task());
+        if mutex.unlock().is_none() {
+            self.wait_queue.exclusive_access().pop_back();
+            return None;
+        }
+        block_current_and_run_next();
+        mutex.lock();
+        Some(())
+    }
+
+    /// task `tid` is gone, stop waiting for it
+    pub fn cancel_wait(&self, tid: usize) {
+        self.wait_queue
+            .exclusive_access()
+            .retain(|waiter| *waiter != tid);
+    }
+}
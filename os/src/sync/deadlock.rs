@@ -0,0 +1,99 @@
+//! Deadlock detection with the banker's algorithm
+//!
+//! Each mutex is a resource with one unit, each semaphore one with as many
+//! units as its count. Before a thread blocks on a resource, the request is
+//! added to its need and we check that the threads can still all finish in
+//! some order, assuming every thread releases what it holds once its needs
+//! are met. If they cannot, the request is refused instead.
+
+use alloc::{collections::BTreeMap, vec::Vec};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Resource {
+    Mutex(usize),
+    Semaphore(usize),
+}
+
+#[derive(Default)]
+pub struct DeadlockDetector {
+    pub enabled: bool,
+    available: BTreeMap<Resource, usize>,
+    /// units held, by (tid, resource)
+    allocation: BTreeMap<(usize, Resource), usize>,
+    /// units waited for, by (tid, resource)
+    need: BTreeMap<(usize, Resource), usize>,
+}
+
+impl DeadlockDetector {
+    pub fn add_resource(&mut self, resource: Resource, units: usize) {
+        self.available.insert(resource, units);
+    }
+
+    /// Record that `tid` is about to wait for one unit of `resource`. Returns
+    /// `false`, and records nothing, if detection is on and the wait could
+    /// end in a deadlock.
+    pub fn request(&mut self, tid: usize, resource: Resource) -> bool {
+        *self.need.entry((tid, resource)).or_default() += 1;
+        if self.enabled && !self.is_safe() {
+            Self::take(&mut self.need, (tid, resource));
+            return false;
+        }
+        true
+    }
+
+    /// the unit requested by `tid` has been handed to it
+    pub fn grant(&mut self, tid: usize, resource: Resource) {
+        Self::take(&mut self.need, (tid, resource));
+        *self.allocation.entry((tid, resource)).or_default() += 1;
+        let available = self.available.entry(resource).or_default();
+        *available = available.saturating_sub(1);
+    }
+
+    /// `tid` gives one unit of `resource` back
+    pub fn release(&mut self, tid: usize, resource: Resource) {
+        // a semaphore may well be upped by a thread that never downed it
+        Self::take(&mut self.allocation, (tid, resource));
+        *self.available.entry(resource).or_default() += 1;
+    }
+
+    fn take(map: &mut BTreeMap<(usize, Resource), usize>, key: (usize, Resource)) {
+        if let Some(units) = map.get_mut(&key) {
+            *units -= 1;
+            if *units == 0 {
+                map.remove(&key);
+            }
+        }
+    }
+
+    /// whether every thread can still get what it needs in some order
+    fn is_safe(&self) -> bool {
+        let mut work = self.available.clone();
+        let mut unfinished: Vec<usize> = self
+            .allocation
+            .keys()
+            .chain(self.need.keys())
+            .map(|(tid, _)| *tid)
+            .collect();
+        unfinished.sort_unstable();
+        unfinished.dedup();
+        loop {
+            let runnable = unfinished.iter().position(|tid| {
+                self.need
+                    .range((*tid, Resource::Mutex(0))..=(*tid, Resource::Semaphore(usize::MAX)))
+                    .all(|((_, resource), units)| {
+                        work.get(resource).copied().unwrap_or(0) >= *units
+                    })
+            });
+            let tid = match runnable {
+                Some(i) => unfinished.swap_remove(i),
+                None => return unfinished.is_empty(),
+            };
+            for ((_, resource), units) in self
+                .allocation
+                .range((tid, Resource::Mutex(0))..=(tid, Resource::Semaphore(usize::MAX)))
+            {
+                *work.entry(*resource).or_default() += units;
+            }
+        }
+    }
+}
@@ -0,0 +1,74 @@
+//! Futex wait queues keyed on user addresses
+//!
+//! User space does the fast path with atomics and only comes here on
+//! contention. Waiters are hashed by address into a fixed number of buckets;
+//! since the kernel is never preempted, comparing the futex word and queueing
+//! the waiter cannot race with a wake.
+
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use super::UPSafeCell;
+use crate::task::{block_current_and_run_next, wakeup_task, TASK_MANAGER};
+
+const FUTEX_BUCKETS: usize = 64;
+
+struct FutexWaiter {
+    uaddr: usize,
+    task_id: usize,
+}
+
+lazy_static::lazy_static! {
+    static ref FUTEX_QUEUES: UPSafeCell<[Vec<FutexWaiter>; FUTEX_BUCKETS]> =
+        unsafe { UPSafeCell::new([(); FUTEX_BUCKETS].map(|_| Vec::new())) };
+}
+
+fn bucket_of(uaddr: usize) -> usize {
+    (uaddr >> 2) % FUTEX_BUCKETS
+}
+
+/// Block current task on `uaddr` if it still holds `val`. Returns `false`
+/// without blocking if the value has already changed.
+pub fn futex_wait(uaddr: &AtomicU32, val: u32) -> bool {
+    let addr = uaddr as *const AtomicU32 as usize;
+    let mut queues = FUTEX_QUEUES.exclusive_access();
+    if uaddr.load(Ordering::SeqCst) != val {
+        return false;
+    }
+    queues[bucket_of(addr)].push(FutexWaiter {
+        uaddr: addr,
+        task_id: TASK_MANAGER.get_current_task(),
+    });
+    drop(queues);
+    block_current_and_run_next();
+    true
+}
+
+/// Wake at most `count` tasks blocked on `uaddr`, returns how many were woken.
+pub fn futex_wake(uaddr: &AtomicU32, count: usize) -> usize {
+    let addr = uaddr as *const AtomicU32 as usize;
+    let mut queues = FUTEX_QUEUES.exclusive_access();
+    let bucket = &mut queues[bucket_of(addr)];
+    let mut woken = 0;
+    let mut i = 0;
+    while i < bucket.len() && woken < count {
+        if bucket[i].uaddr == addr {
+            // waiters of a task that has been killed since are just dropped
+            if wakeup_task(bucket.remove(i).task_id) {
+                woken += 1;
+            }
+        } else {
+            i += 1;
+        }
+    }
+    woken
+}
+
+/// Forget every wait of task `tid`, which is gone, so the task that gets its
+/// tid next is not woken in its place.
+pub fn futex_cancel(tid: usize) {
+    let mut queues = FUTEX_QUEUES.exclusive_access();
+    for bucket in queues.iter_mut() {
+        bucket.retain(|waiter| waiter.task_id != tid);
+    }
+}
@@ -1,5 +1,15 @@
 //! Synchronization and interior mutability primitives
 
+mod condvar;
+mod deadlock;
+mod futex;
+mod mutex;
+mod semaphore;
 mod up;
 
+pub use condvar::Condvar;
+pub use deadlock::{DeadlockDetector, Resource};
+pub use futex::{futex_cancel, futex_wait, futex_wake};
+pub use mutex::Mutex;
+pub use semaphore::Semaphore;
 pub use up::UPSafeCell;
@@ -0,0 +1,73 @@
+//! Blocking mutex managed by the kernel
+
+use alloc::collections::VecDeque;
+
+use super::UPSafeCell;
+use crate::task::{block_current_and_run_next, wakeup_task, TASK_MANAGER};
+
+pub struct Mutex {
+    inner: UPSafeCell<MutexInner>,
+}
+
+struct MutexInner {
+    /// tid of the holder
+    owner: Option<usize>,
+    wait_queue: VecDeque<usize>,
+}
+
+impl Mutex {
+    pub fn new() -> Self {
+        Self {
+            inner: unsafe {
+                UPSafeCell::new(MutexInner {
+                    owner: None,
+                    wait_queue: VecDeque::new(),
+                })
+            },
+        }
+    }
+
+    /// block until current task holds the mutex
+    pub fn lock(&self) {
+        let tid = TASK_MANAGER.get_current_task();
+        loop {
+            let mut inner = self.inner.exclusive_access();
+            if inner.owner.is_none() {
+                inner.owner = Some(tid);
+                return;
+            }
+            inner.wait_queue.push_back(tid);
+            drop(inner);
+            block_current_and_run_next();
+        }
+    }
+
+    /// whether current task holds the mutex
+    pub fn is_held(&self) -> bool {
+        self.inner.exclusive_access().owner == Some(TASK_MANAGER.get_current_task())
+    }
+
+    /// returns `None` if current task does not hold the mutex
+    pub fn unlock(&self) -> Option<()> {
+        let mut inner = self.inner.exclusive_access();
+        if inner.owner != Some(TASK_MANAGER.get_current_task()) {
+            return None;
+        }
+        inner.owner = None;
+        // waiters that have been killed since do not count
+        while let Some(tid) = inner.wait_queue.pop_front() {
+            if wakeup_task(tid) {
+                break;
+            }
+        }
+        Some(())
+    }
+
+    /// task `tid` is gone, stop waiting for it
+    pub fn cancel_wait(&self, tid: usize) {
+        self.inner
+            .exclusive_access()
+            .wait_queue
+            .retain(|waiter| *waiter != tid);
+    }
+}
@@ -0,0 +1,60 @@
+//! Counting semaphore managed by the kernel
+
+use alloc::collections::VecDeque;
+
+use super::UPSafeCell;
+use crate::task::{block_current_and_run_next, wakeup_task, TASK_MANAGER};
+
+pub struct Semaphore {
+    inner: UPSafeCell<SemaphoreInner>,
+}
+
+struct SemaphoreInner {
+    count: usize,
+    wait_queue: VecDeque<usize>,
+}
+
+impl Semaphore {
+    pub fn new(count: usize) -> Self {
+        Self {
+            inner: unsafe {
+                UPSafeCell::new(SemaphoreInner {
+                    count,
+                    wait_queue: VecDeque::new(),
+                })
+            },
+        }
+    }
+
+    pub fn up(&self) {
+        let mut inner = self.inner.exclusive_access();
+        inner.count += 1;
+        while let Some(tid) = inner.wait_queue.pop_front() {
+            if wakeup_task(tid) {
+                break;
+            }
+        }
+    }
+
+    /// block until the count is positive, then take one
+    pub fn down(&self) {
+        loop {
+            let mut inner = self.inner.exclusive_access();
+            if inner.count > 0 {
+                inner.count -= 1;
+                return;
+            }
+            inner.wait_queue.push_back(TASK_MANAGER.get_current_task());
+            drop(inner);
+            block_current_and_run_next();
+        }
+    }
+
+    /// task `tid` is gone, stop waiting for it
+    pub fn cancel_wait(&self, tid: usize) {
+        self.inner
+            .exclusive_access()
+            .wait_queue
+            .retain(|waiter| *waiter != tid);
+    }
+}
@@ -0,0 +1,22 @@
+//! Error numbers, syscalls return them negated, the values follow Linux
+
+pub const EPERM: isize = 1;
+pub const ENOENT: isize = 2;
+pub const ESRCH: isize = 3;
+pub const E2BIG: isize = 7;
+pub const ENOEXEC: isize = 8;
+pub const EBADF: isize = 9;
+pub const EAGAIN: isize = 11;
+pub const ENOMEM: isize = 12;
+pub const EACCES: isize = 13;
+pub const EFAULT: isize = 14;
+pub const EBUSY: isize = 16;
+pub const EEXIST: isize = 17;
+pub const ENOTDIR: isize = 20;
+pub const EINVAL: isize = 22;
+pub const EMFILE: isize = 24;
+pub const ENOTTY: isize = 25;
+pub const EPIPE: isize = 32;
+pub const EDEADLK: isize = 35;
+pub const ENAMETOOLONG: isize = 36;
+pub const ENOSYS: isize = 38;
@@ -1,28 +1,276 @@
 //! File and filesystem-related syscalls
 
-use super::check_buf;
-use crate::sbi::console_putchar;
+use alloc::{format, vec, vec::Vec};
 
-const FD_STDOUT: usize = 1;
+use super::errno::{EACCES, EBADF, EINVAL, ENOENT, ENOTDIR, ENOTTY};
+use crate::{
+    config::PAGE_SIZE,
+    fs::{make_pipe, open_proc},
+    mm::{read_cstr, UserPtr, UserSlice},
+    task::TASK_MANAGER,
+};
+
+const O_RDONLY: usize = 0;
+const O_ACCMODE: usize = 3;
+const MAX_PATH_LEN: usize = 256;
+/// a read returns at most this much at once
+const MAX_READ_LEN: usize = 4 * PAGE_SIZE;
+
+/// `dirfd` of `openat` that stands for the working directory, which is `/`
+pub const AT_FDCWD: isize = -100;
+
+/// Open the file at `path`, relative ones are looked up in `dirfd`. There are
+/// no directories to open, so only `AT_FDCWD` works for them, and only the
+/// read-only files under `/proc` exist.
+pub fn sys_openat(dirfd: isize, path: UserPtr<u8>, flags: usize) -> isize {
+    let path = match read_cstr(path.addr(), MAX_PATH_LEN) {
+        Ok(path) => path,
+        Err(errno) => return errno,
+    };
+    let path = match (path.starts_with('/'), dirfd) {
+        (true, _) => path,
+        (false, AT_FDCWD) => format!("/{}", path),
+        (false, _) => match TASK_MANAGER.get_current_fd(dirfd as usize) {
+            Some(_) => return -ENOTDIR,
+            None => return -EBADF,
+        },
+    };
+    let file = match open_proc(&path) {
+        Some(file) => file,
+        None => return -ENOENT,
+    };
+    if flags & O_ACCMODE != O_RDONLY {
+        return -EACCES;
+    }
+    match TASK_MANAGER.alloc_current_fd(file) {
+        Ok(fd) => fd as isize,
+        Err(errno) => errno,
+    }
+}
 
 /// write buf of length `len`  to a file with `fd`
-pub fn sys_write(fd: usize, buf: *const u8, len: usize) -> isize {
-    if !check_buf(buf, len) {
-        return 0;
+pub fn sys_write(fd: usize, buf: UserSlice) -> isize {
+    let chunks = match buf.chunks() {
+        Ok(chunks) => chunks,
+        Err(errno) => return errno,
+    };
+
+    match TASK_MANAGER.get_current_fd(fd) {
+        Some(file) if file.writable() => {
+            let mut written = 0;
+            for chunk in chunks {
+                match file.write(chunk) {
+                    Err(errno) if written == 0 => return errno,
+                    Err(_) => break,
+                    Ok(n) => {
+                        written += n;
+                        if n < chunk.len() {
+                            break;
+                        }
+                    }
+                }
+            }
+            written as isize
+        }
+        _ => -EBADF,
     }
+}
 
-    match fd {
-        FD_STDOUT => {
-            let slice = unsafe { core::slice::from_raw_parts(buf, len) };
+/// read at most `len` bytes from a file with `fd` into buf
+pub fn sys_read(fd: usize, buf: UserSlice) -> isize {
+    // check before anything is consumed
+    if let Err(errno) = buf.check_writable() {
+        return errno;
+    }
 
-            for &i in slice {
-                console_putchar(i as usize);
+    match TASK_MANAGER.get_current_fd(fd) {
+        Some(file) if file.readable() => {
+            // read into the kernel first, the file may block and must see
+            // one buffer however many pages `buf` spans
+            let mut kernel_buf = vec![0u8; buf.len().min(MAX_READ_LEN)];
+            let n = match file.read(&mut kernel_buf) {
+                Ok(n) => n,
+                Err(errno) => return errno,
+            };
+            match buf.copy_to_user(&kernel_buf[..n]) {
+                Ok(n) => n as isize,
+                Err(errno) => errno,
             }
+        }
+        _ => -EBADF,
+    }
+}
+
+pub fn sys_close(fd: usize) -> isize {
+    match TASK_MANAGER.take_current_fd(fd) {
+        Some(file) => {
+            drop(file);
+            0
+        }
+        None => -EBADF,
+    }
+}
+
+/// `pipe2`: create a pipe, `pipe[0]` becomes the read end and `pipe[1]`
+/// the write end. None of the `O_*` flags is supported.
+pub fn sys_pipe(pipe: UserPtr<[i32; 2]>, flags: usize) -> isize {
+    if flags != 0 {
+        return -EINVAL;
+    }
+    let (pipe_read, pipe_write) = make_pipe();
+    let read_fd = match TASK_MANAGER.alloc_current_fd(pipe_read) {
+        Ok(fd) => fd,
+        Err(errno) => return errno,
+    };
+    let write_fd = match TASK_MANAGER.alloc_current_fd(pipe_write) {
+        Ok(fd) => fd,
+        Err(errno) => {
+            TASK_MANAGER.take_current_fd(read_fd);
+            return errno;
+        }
+    };
+    match pipe.write([read_fd as i32, write_fd as i32]) {
+        Ok(()) => 0,
+        Err(errno) => {
+            TASK_MANAGER.take_current_fd(read_fd);
+            TASK_MANAGER.take_current_fd(write_fd);
+            errno
+        }
+    }
+}
 
-            len as isize
+/// Duplicate the file `targetfd` of the process `pidfd` refers to into the
+/// fd table of current process, which is how a pipe gets to another process
+/// since nothing is inherited.
+pub fn sys_pidfd_getfd(pidfd: usize, targetfd: usize, flags: usize) -> isize {
+    if flags != 0 {
+        return -EINVAL;
+    }
+    let pid = match TASK_MANAGER
+        .get_current_fd(pidfd)
+        .and_then(|file| file.pidfd())
+    {
+        Some(pid) => pid,
+        None => return -EBADF,
+    };
+    match TASK_MANAGER.get_fd(pid, targetfd) {
+        Ok(file) => match TASK_MANAGER.alloc_current_fd(file) {
+            Ok(fd) => fd as isize,
+            Err(errno) => errno,
+        },
+        Err(errno) => errno,
+    }
+}
+
+/// `struct iovec` of `readv` and `writev`
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct IoVec {
+    pub base: usize,
+    pub len: usize,
+}
+
+const IOV_MAX: usize = 1024;
+
+fn read_iovecs(iov: UserPtr<IoVec>, iovcnt: usize) -> Result<Vec<UserSlice>, isize> {
+    if iovcnt > IOV_MAX {
+        return Err(-EINVAL);
+    }
+    (0..iovcnt)
+        .map(|i| {
+            let iov = UserPtr::<IoVec>::new(iov.addr() + i * core::mem::size_of::<IoVec>());
+            iov.read().map(|iov| UserSlice::new(iov.base, iov.len))
+        })
+        .collect()
+}
+
+/// write the `iovcnt` buffers of `iov` in order, stops at the first short
+/// write
+pub fn sys_writev(fd: usize, iov: UserPtr<IoVec>, iovcnt: usize) -> isize {
+    let bufs = match read_iovecs(iov, iovcnt) {
+        Ok(bufs) => bufs,
+        Err(errno) => return errno,
+    };
+    let mut written = 0;
+    for buf in bufs {
+        match sys_write(fd, buf) {
+            n if n < 0 && written == 0 => return n,
+            n if n < 0 => break,
+            n => {
+                written += n;
+                if (n as usize) < buf.len() {
+                    break;
+                }
+            }
         }
-        _ => {
-            panic!("Unsupported fd {fd} in sys_write!");
+    }
+    written
+}
+
+/// read into the `iovcnt` buffers of `iov` in order, with a single read of
+/// the file so it does not block once something has been read
+pub fn sys_readv(fd: usize, iov: UserPtr<IoVec>, iovcnt: usize) -> isize {
+    let bufs = match read_iovecs(iov, iovcnt) {
+        Ok(bufs) => bufs,
+        Err(errno) => return errno,
+    };
+    if let Some(errno) = bufs.iter().find_map(|buf| buf.check_writable().err()) {
+        return errno;
+    }
+
+    match TASK_MANAGER.get_current_fd(fd) {
+        Some(file) if file.readable() => {
+            let len: usize = bufs.iter().map(UserSlice::len).sum();
+            let mut kernel_buf = vec![0u8; len.min(MAX_READ_LEN)];
+            let n = match file.read(&mut kernel_buf) {
+                Ok(n) => n,
+                Err(errno) => return errno,
+            };
+            let mut copied = 0;
+            for buf in bufs {
+                match buf.copy_to_user(&kernel_buf[copied..n]) {
+                    Ok(len) => copied += len,
+                    Err(errno) => return errno,
+                }
+            }
+            copied as isize
         }
+        _ => -EBADF,
+    }
+}
+
+/// get the size of a terminal into a `struct winsize`
+pub const TIOCGWINSZ: usize = 0x5413;
+
+/// `struct winsize`
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct WinSize {
+    pub row: u16,
+    pub col: u16,
+    pub xpixel: u16,
+    pub ypixel: u16,
+}
+
+/// the console cannot tell its size, assume the classic one
+const CONSOLE_SIZE: WinSize = WinSize {
+    row: 24,
+    col: 80,
+    xpixel: 0,
+    ypixel: 0,
+};
+
+/// device specific `cmd` on `fd`, only `TIOCGWINSZ` on the console exists
+pub fn sys_ioctl(fd: usize, cmd: usize, arg: usize) -> isize {
+    match TASK_MANAGER.get_current_fd(fd) {
+        None => -EBADF,
+        Some(file) if !file.is_tty() => -ENOTTY,
+        Some(_) => match cmd {
+            TIOCGWINSZ => match UserPtr::<WinSize>::new(arg).write(CONSOLE_SIZE) {
+                Ok(()) => 0,
+                Err(errno) => errno,
+            },
+            _ => -ENOTTY,
+        },
     }
 }
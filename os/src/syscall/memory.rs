@@ -0,0 +1,63 @@
+//! Syscalls that change the memory of a process, see [`crate::mm::UserMemory`]
+
+use super::errno::{EINVAL, ENOMEM};
+use crate::task::TASK_MANAGER;
+
+/// Set the program break to `addr`, 0 only queries it. Like Linux, returns
+/// the break, which stays where it was if `addr` does not fit.
+pub fn sys_brk(addr: usize) -> isize {
+    TASK_MANAGER.with_current_memory(|memory| {
+        if addr != 0 {
+            let _ = memory.set_brk(addr);
+        }
+        memory.brk() as isize
+    })
+}
+
+/// Move the program break by `increment` bytes, returns the old break.
+pub fn sys_sbrk(increment: isize) -> isize {
+    TASK_MANAGER.with_current_memory(|memory| {
+        let old = memory.brk();
+        let new = match old.checked_add_signed(increment) {
+            Some(new) => new,
+            None => return -ENOMEM,
+        };
+        match memory.set_brk(new) {
+            Ok(_) => old as isize,
+            Err(errno) => errno,
+        }
+    })
+}
+
+/// Map anonymous memory, there are no files to map so `fd` is ignored and
+/// `offset` must be 0. Returns the start of the mapping.
+pub fn sys_mmap(
+    addr: usize,
+    len: usize,
+    prot: usize,
+    flags: usize,
+    _fd: usize,
+    offset: usize,
+) -> isize {
+    if offset != 0 {
+        return -EINVAL;
+    }
+    match TASK_MANAGER.with_current_memory(|memory| memory.mmap(addr, len, prot, flags)) {
+        Ok(start) => start as isize,
+        Err(errno) => errno,
+    }
+}
+
+pub fn sys_munmap(addr: usize, len: usize) -> isize {
+    match TASK_MANAGER.with_current_memory(|memory| memory.munmap(addr, len)) {
+        Ok(()) => 0,
+        Err(errno) => errno,
+    }
+}
+
+pub fn sys_mprotect(addr: usize, len: usize, prot: usize) -> isize {
+    match TASK_MANAGER.with_current_memory(|memory| memory.mprotect(addr, len, prot)) {
+        Ok(()) => 0,
+        Err(errno) => errno,
+    }
+}
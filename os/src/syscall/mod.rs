@@ -9,27 +9,112 @@
 //! For clarity, each single syscall is implemented as its own function, named
 //! `sys_` then the name of the syscall. You can find functions like this in
 //! submodules, and you should also implement syscalls this way.
+//!
+//! Numbers, arguments, struct layouts and error numbers follow Linux on
+//! riscv64 where Linux has the syscall, the ones of our own start at 1000.
+//! Unknown syscalls fail with `-ENOSYS`, which is what libcs probe for.
+//! Programs are still built along with the kernel and their ELFs are not
+//! relocated, see [`crate::loader`], so Linux binaries linked elsewhere do
+//! not run as they are.
 
-pub const MAX_SYSCALL_NUM: usize = 8;
+/// how many different syscalls a process keeps counts of, the ones it makes
+/// after that are not counted
+pub const MAX_SYSCALL_NUM: usize = 64;
 
+pub mod errno;
 mod fs;
+mod memory;
 mod process;
+mod sched;
+mod sync;
+mod syslog;
+mod time;
+pub mod trace;
 
-use core::ops::Range;
+use core::time::Duration;
 
 use fs::*;
-use log::info;
+use memory::*;
 use process::*;
+use sched::*;
+use sync::*;
+use syslog::*;
+use time::*;
 
-use crate::task::TASK_MANAGER;
+use self::errno::ENOSYS;
+use crate::{
+    mm::{UserPtr, UserSlice},
+    timer::timer_now,
+};
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum SyscallId {
+    Ioctl = 29,
+    OpenAt = 56,
+    Close = 57,
+    Pipe = 59,
+    Read = 63,
     Write = 64,
+    ReadV = 65,
+    WriteV = 66,
     Exit = 93,
-    Yield = 124,
-    GetTime = 169,
+    ExitGroup = 94,
+    SetTidAddress = 96,
+    Futex = 98,
+    NanoSleep = 101,
+    GetITimer = 102,
+    SetITimer = 103,
+    ClockGetTime = 113,
+    Syslog = 116,
+    SchedSetParam = 118,
+    SchedSetScheduler = 119,
+    SchedGetScheduler = 120,
+    SchedGetParam = 121,
+    SchedYield = 124,
+    SchedGetPriorityMax = 125,
+    SchedGetPriorityMin = 126,
+    SchedRrGetInterval = 127,
+    Kill = 129,
+    SigAction = 134,
+    SigProcMask = 135,
+    SigReturn = 139,
+    Uname = 160,
+    GetTimeOfDay = 169,
+    SetTimeOfDay = 170,
+    GetPid = 172,
+    GetUid = 174,
+    GetEuid = 175,
+    GetGid = 176,
+    GetEgid = 177,
+    GetTid = 178,
+    Brk = 214,
+    Munmap = 215,
+    Exec = 221,
+    Mmap = 222,
+    Mprotect = 226,
     GetTaskInfo = 233,
+    SchedSetAttr = 274,
+    SchedGetAttr = 275,
+    PidfdOpen = 434,
+    PidfdGetfd = 438,
+    EnableDeadlockDetect = 469,
+    ThreadCreate = 1000,
+    WaitTid = 1002,
+    MutexCreate = 1010,
+    MutexLock = 1011,
+    MutexUnlock = 1012,
+    SemaphoreCreate = 1020,
+    SemaphoreUp = 1021,
+    SemaphoreDown = 1022,
+    CondvarCreate = 1030,
+    CondvarSignal = 1031,
+    CondvarWait = 1032,
+    GetPidTaskInfo = 1100,
+    SetLogFilter = 1101,
+    Trace = 1102,
+    Profile = 1103,
+    Sbrk = 1104,
+    Alarm = 1105,
     Unsupported,
 }
 
@@ -38,45 +123,177 @@ use SyscallId::*;
 impl From<usize> for SyscallId {
     fn from(v: usize) -> Self {
         match v {
+            x if x == Ioctl as usize => Ioctl,
+            x if x == OpenAt as usize => OpenAt,
+            x if x == Close as usize => Close,
+            x if x == Pipe as usize => Pipe,
+            x if x == Read as usize => Read,
             x if x == Write as usize => Write,
+            x if x == ReadV as usize => ReadV,
+            x if x == WriteV as usize => WriteV,
             x if x == Exit as usize => Exit,
-            x if x == Yield as usize => Yield,
-            x if x == GetTime as usize => GetTime,
+            x if x == ExitGroup as usize => ExitGroup,
+            x if x == SetTidAddress as usize => SetTidAddress,
+            x if x == Futex as usize => Futex,
+            x if x == NanoSleep as usize => NanoSleep,
+            x if x == GetITimer as usize => GetITimer,
+            x if x == SetITimer as usize => SetITimer,
+            x if x == ClockGetTime as usize => ClockGetTime,
+            x if x == Syslog as usize => Syslog,
+            x if x == SchedSetParam as usize => SchedSetParam,
+            x if x == SchedSetScheduler as usize => SchedSetScheduler,
+            x if x == SchedGetScheduler as usize => SchedGetScheduler,
+            x if x == SchedGetParam as usize => SchedGetParam,
+            x if x == SchedYield as usize => SchedYield,
+            x if x == SchedGetPriorityMax as usize => SchedGetPriorityMax,
+            x if x == SchedGetPriorityMin as usize => SchedGetPriorityMin,
+            x if x == SchedRrGetInterval as usize => SchedRrGetInterval,
+            x if x == Kill as usize => Kill,
+            x if x == SigAction as usize => SigAction,
+            x if x == SigProcMask as usize => SigProcMask,
+            x if x == SigReturn as usize => SigReturn,
+            x if x == Uname as usize => Uname,
+            x if x == GetTimeOfDay as usize => GetTimeOfDay,
+            x if x == SetTimeOfDay as usize => SetTimeOfDay,
+            x if x == GetPid as usize => GetPid,
+            x if x == GetUid as usize => GetUid,
+            x if x == GetEuid as usize => GetEuid,
+            x if x == GetGid as usize => GetGid,
+            x if x == GetEgid as usize => GetEgid,
+            x if x == GetTid as usize => GetTid,
+            x if x == Brk as usize => Brk,
+            x if x == Munmap as usize => Munmap,
+            x if x == Exec as usize => Exec,
+            x if x == Mmap as usize => Mmap,
+            x if x == Mprotect as usize => Mprotect,
             x if x == GetTaskInfo as usize => GetTaskInfo,
+            x if x == SchedSetAttr as usize => SchedSetAttr,
+            x if x == SchedGetAttr as usize => SchedGetAttr,
+            x if x == PidfdOpen as usize => PidfdOpen,
+            x if x == PidfdGetfd as usize => PidfdGetfd,
+            x if x == EnableDeadlockDetect as usize => EnableDeadlockDetect,
+            x if x == ThreadCreate as usize => ThreadCreate,
+            x if x == WaitTid as usize => WaitTid,
+            x if x == MutexCreate as usize => MutexCreate,
+            x if x == MutexLock as usize => MutexLock,
+            x if x == MutexUnlock as usize => MutexUnlock,
+            x if x == SemaphoreCreate as usize => SemaphoreCreate,
+            x if x == SemaphoreUp as usize => SemaphoreUp,
+            x if x == SemaphoreDown as usize => SemaphoreDown,
+            x if x == CondvarCreate as usize => CondvarCreate,
+            x if x == CondvarSignal as usize => CondvarSignal,
+            x if x == CondvarWait as usize => CondvarWait,
+            x if x == GetPidTaskInfo as usize => GetPidTaskInfo,
+            x if x == SetLogFilter as usize => SetLogFilter,
+            x if x == Trace as usize => Trace,
+            x if x == Profile as usize => Profile,
+            x if x == Sbrk as usize => Sbrk,
+            x if x == Alarm as usize => Alarm,
             _ => Unsupported,
         }
     }
 }
 
 /// handle syscall exception with `syscall_id` and other arguments
-pub fn syscall(syscall_id_raw: usize, args: [usize; 3]) -> isize {
+pub fn syscall(syscall_id_raw: usize, args: [usize; 6]) -> isize {
     let syscall_id = SyscallId::from(syscall_id_raw);
-    match syscall_id {
-        Write => sys_write(args[0], args[1] as *const u8, args[2]),
-        Exit => sys_exit(args[0] as i32),
-        Yield => sys_yield(),
-        GetTime => sys_get_time(),
-        GetTaskInfo => sys_get_taskinfo(args[0] as *mut u8, args[1]),
-        Unsupported => panic!("Unsupported syscall_id: {}", syscall_id_raw),
+    if !trace::is_traced(syscall_id) {
+        return dispatch(syscall_id, syscall_id_raw, args);
+    }
+    // the arguments of a successful exec are gone once it returns
+    if syscall_id == Exit || syscall_id == ExitGroup || syscall_id == Exec {
+        trace::log_syscall(syscall_id, args, None, Duration::ZERO);
+    }
+    let start = timer_now();
+    let ret = dispatch(syscall_id, syscall_id_raw, args);
+    if syscall_id != Exec || ret < 0 {
+        trace::log_syscall(syscall_id, args, Some(ret), timer_now() - start);
     }
+    ret
 }
 
-fn check_buf(buf: *const u8, len: usize) -> bool {
-    let user_stack_range = TASK_MANAGER
-        .get_current_task_stack()
-        .get_stack()
-        .as_ptr_range();
-    let task_range = TASK_MANAGER.get_current_task_data_section().as_ptr_range();
-    let buf_range = (buf as *const u8)..((buf as usize + len) as *const u8);
-
-    //debug!("{user_stack_range:?} {current_app_range:?} {buf_range:?}");
-    let in_range =
-        |a: &Range<*const u8>, b: &Range<*const u8>| (b.start <= a.start) & (b.end >= a.end);
-
-    if !in_range(&buf_range, &user_stack_range) & !in_range(&buf_range, &task_range) {
-        info!("Task access out of bounds, excepted in user stack {user_stack_range:?} or in data section {task_range:?}, but given {buf_range:?}");
-        false
-    } else {
-        true
+fn dispatch(syscall_id: SyscallId, syscall_id_raw: usize, args: [usize; 6]) -> isize {
+    match syscall_id {
+        Ioctl => sys_ioctl(args[0], args[1], args[2]),
+        OpenAt => sys_openat(args[0] as isize, UserPtr::new(args[1]), args[2]),
+        Close => sys_close(args[0]),
+        Pipe => sys_pipe(UserPtr::new(args[0]), args[1]),
+        Read => sys_read(args[0], UserSlice::new(args[1], args[2])),
+        Write => sys_write(args[0], UserSlice::new(args[1], args[2])),
+        ReadV => sys_readv(args[0], UserPtr::new(args[1]), args[2]),
+        WriteV => sys_writev(args[0], UserPtr::new(args[1]), args[2]),
+        Exit => sys_exit(args[0] as i32),
+        ExitGroup => sys_exit_group(args[0] as i32),
+        SetTidAddress => sys_set_tid_address(args[0]),
+        Futex => sys_futex(UserPtr::new(args[0]), args[1], args[2]),
+        NanoSleep => sys_nanosleep(UserPtr::new(args[0]), UserPtr::new(args[1])),
+        GetITimer => sys_getitimer(args[0], UserPtr::new(args[1])),
+        SetITimer => sys_setitimer(args[0], UserPtr::new(args[1]), UserPtr::new(args[2])),
+        ClockGetTime => sys_clock_gettime(args[0], UserPtr::new(args[1])),
+        Syslog => sys_syslog(args[0], UserSlice::new(args[1], args[2])),
+        SchedSetParam => sys_sched_setparam(args[0], UserPtr::new(args[1])),
+        SchedSetScheduler => sys_sched_setscheduler(args[0], args[1], UserPtr::new(args[2])),
+        SchedGetScheduler => sys_sched_getscheduler(args[0]),
+        SchedGetParam => sys_sched_getparam(args[0], UserPtr::new(args[1])),
+        SchedYield => sys_sched_yield(),
+        SchedGetPriorityMax => sys_sched_get_priority_max(args[0]),
+        SchedGetPriorityMin => sys_sched_get_priority_min(args[0]),
+        SchedRrGetInterval => sys_sched_rr_get_interval(args[0], UserPtr::new(args[1])),
+        Kill => sys_kill(args[0], args[1]),
+        SigAction => sys_sigaction(
+            args[0],
+            UserPtr::new(args[1]),
+            UserPtr::new(args[2]),
+            args[3],
+        ),
+        SigProcMask => sys_sigprocmask(
+            args[0],
+            UserPtr::new(args[1]),
+            UserPtr::new(args[2]),
+            args[3],
+        ),
+        SigReturn => sys_sigreturn(),
+        Uname => sys_uname(UserPtr::new(args[0])),
+        GetTimeOfDay => sys_gettimeofday(UserPtr::new(args[0]), UserPtr::new(args[1])),
+        SetTimeOfDay => sys_settimeofday(UserPtr::new(args[0]), UserPtr::new(args[1])),
+        GetPid => sys_getpid(),
+        GetUid | GetEuid | GetGid | GetEgid => sys_getuid(),
+        GetTid => sys_gettid(),
+        Brk => sys_brk(args[0]),
+        Munmap => sys_munmap(args[0], args[1]),
+        Exec => sys_exec(
+            UserPtr::new(args[0]),
+            UserPtr::new(args[1]),
+            UserPtr::new(args[2]),
+        ),
+        Mmap => sys_mmap(args[0], args[1], args[2], args[3], args[4], args[5]),
+        Mprotect => sys_mprotect(args[0], args[1], args[2]),
+        GetTaskInfo => sys_get_taskinfo(UserPtr::new(args[0])),
+        SchedSetAttr => sys_sched_setattr(args[0], UserPtr::new(args[1]), args[2]),
+        SchedGetAttr => sys_sched_getattr(args[0], UserPtr::new(args[1]), args[2], args[3]),
+        PidfdOpen => sys_pidfd_open(args[0], args[1]),
+        PidfdGetfd => sys_pidfd_getfd(args[0], args[1], args[2]),
+        EnableDeadlockDetect => sys_enable_deadlock_detect(args[0]),
+        ThreadCreate => sys_thread_create(args[0], args[1]),
+        WaitTid => sys_waittid(args[0]),
+        MutexCreate => sys_mutex_create(),
+        MutexLock => sys_mutex_lock(args[0]),
+        MutexUnlock => sys_mutex_unlock(args[0]),
+        SemaphoreCreate => sys_semaphore_create(args[0]),
+        SemaphoreUp => sys_semaphore_up(args[0]),
+        SemaphoreDown => sys_semaphore_down(args[0]),
+        CondvarCreate => sys_condvar_create(),
+        CondvarSignal => sys_condvar_signal(args[0]),
+        CondvarWait => sys_condvar_wait(args[0], args[1]),
+        GetPidTaskInfo => sys_get_pid_taskinfo(args[0], UserPtr::new(args[1])),
+        SetLogFilter => sys_set_log_filter(UserPtr::new(args[0])),
+        Trace => sys_trace(args[0], args[1]),
+        Profile => sys_profile(args[0], UserSlice::new(args[1], args[2])),
+        Sbrk => sys_sbrk(args[0] as isize),
+        Alarm => sys_alarm(args[0]),
+        Unsupported => {
+            log::warn!("unsupported syscall {}", syscall_id_raw);
+            -ENOSYS
+        }
     }
 }
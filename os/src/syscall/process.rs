@@ -1,43 +1,367 @@
 //! App management syscalls
 
-use super::check_buf;
+use alloc::{string::String, sync::Arc, vec::Vec};
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use super::errno::{E2BIG, EDEADLK, EINVAL, ENAMETOOLONG, ENOENT, ENOEXEC, ESRCH};
 use crate::{
-    task::{exit_current_and_run_next, suspend_current_and_run_next, TASK_MANAGER},
-    timer::get_time_ms,
+    fs::PidFd,
+    loader::find_app,
+    mm::{read_cstr, UserPtr, UserSlice},
+    profile::{self, ProfileFormat},
+    sync::futex_wake,
+    task::{
+        block_current_and_run_next, exit_current_and_run_next, exit_current_process_and_run_next,
+        signal::{
+            SignalAction, MAX_SIG, SIGKILL, SIGSET_SIZE, SIGSTOP, SIG_BLOCK, SIG_SETMASK,
+            SIG_UNBLOCK,
+        },
+        JoinResult, UserTaskInfo, TASK_INFO_SIZE_VER0, TASK_MANAGER,
+    },
 };
 
-/// task exits and submit an exit code
+/// thread exits and submit an exit code, the process exits along with its
+/// main thread
 pub fn sys_exit(exit_code: i32) -> ! {
-    println!("[kernel] Application exited with code {}", exit_code);
-    exit_current_and_run_next();
+    clear_child_tid();
+    let tid = TASK_MANAGER.get_current_task();
+    if tid == TASK_MANAGER.get_current_pid() {
+        println!("[kernel] Application exited with code {}", exit_code);
+    } else {
+        println!("[kernel] Thread {} exited with code {}", tid, exit_code);
+    }
+    exit_current_and_run_next(exit_code);
     panic!("Unreachable in sys_exit!");
 }
 
-pub fn sys_yield() -> isize {
-    suspend_current_and_run_next();
+/// exit all threads of current process
+pub fn sys_exit_group(exit_code: i32) -> ! {
+    clear_child_tid();
+    println!("[kernel] Application exited with code {}", exit_code);
+    exit_current_process_and_run_next(exit_code);
+    panic!("Unreachable in sys_exit_group!");
+}
+
+/// zero the address given to `sys_set_tid_address` and wake a waiter on it,
+/// that is how a thread library learns that a thread is gone
+fn clear_child_tid() {
+    let addr = TASK_MANAGER.take_current_clear_child_tid();
+    if addr == 0 {
+        return;
+    }
+    if let Ok(futex) = UserPtr::<AtomicU32>::new(addr).as_ref() {
+        futex.store(0, Ordering::Release);
+        futex_wake(futex, 1);
+    }
+}
+
+/// have current thread clear `tidptr` when it exits, returns its tid
+pub fn sys_set_tid_address(tidptr: usize) -> isize {
+    TASK_MANAGER.set_current_clear_child_tid(tidptr) as isize
+}
+
+const MAX_PATH_LEN: usize = 256;
+/// how many strings `argv` and `envp` may each hold
+const MAX_ARGS: usize = 32;
+const MAX_ARG_LEN: usize = 256;
+
+/// the NULL-terminated array of strings at `array`, null is an empty array
+fn read_str_array(array: UserPtr<usize>) -> Result<Vec<String>, isize> {
+    let mut strings = Vec::new();
+    if array.is_null() {
+        return Ok(strings);
+    }
+    for i in 0.. {
+        let ptr = UserPtr::<usize>::new(array.addr() + i * core::mem::size_of::<usize>()).read()?;
+        if ptr == 0 {
+            break;
+        }
+        if i == MAX_ARGS {
+            return Err(-E2BIG);
+        }
+        match read_cstr(ptr, MAX_ARG_LEN) {
+            Ok(s) => strings.push(s),
+            Err(errno) if errno == -ENAMETOOLONG => return Err(-E2BIG),
+            Err(errno) => return Err(errno),
+        }
+    }
+    Ok(strings)
+}
+
+/// Start app `path` over in current process with the strings of `argv` and
+/// `envp`. An image is linked for the slot of its app and there is no address
+/// translation to put it elsewhere, so a process can only exec the app it
+/// already is, `-ENOEXEC` otherwise; see "暂不支持" in the README. Only the
+/// main thread may call it. Does not return on success, the new program
+/// finds argc in `a0`.
+pub fn sys_exec(path: UserPtr<u8>, argv: UserPtr<usize>, envp: UserPtr<usize>) -> isize {
+    let path = match read_cstr(path.addr(), MAX_PATH_LEN) {
+        Ok(path) => path,
+        Err(errno) => return errno,
+    };
+    match find_app(&path) {
+        Some(app) if app == TASK_MANAGER.get_current_pid() => {}
+        Some(_) => return -ENOEXEC,
+        None => return -ENOENT,
+    }
+    // copy everything out before the old program is gone
+    let (args, envs) = match (read_str_array(argv), read_str_array(envp)) {
+        (Ok(args), Ok(envs)) => (args, envs),
+        (Err(errno), _) | (_, Err(errno)) => return errno,
+    };
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let envs: Vec<&str> = envs.iter().map(String::as_str).collect();
+    match TASK_MANAGER.exec_current(&args, &envs) {
+        Ok(argc) => argc as isize,
+        Err(errno) => errno,
+    }
+}
+
+/// fill `info` with the accounting of current process
+pub fn sys_get_taskinfo(info: UserPtr<UserTaskInfo>) -> isize {
+    sys_get_pid_taskinfo(TASK_MANAGER.get_current_pid(), info)
+}
+
+/// Fill `info` with the accounting of process `pid`, as much of it as the
+/// `size` the caller set holds. `-ESRCH` if there is no such process.
+pub fn sys_get_pid_taskinfo(pid: usize, info: UserPtr<UserTaskInfo>) -> isize {
+    let size = match UserPtr::<usize>::new(info.addr()).read() {
+        Ok(size) if size < TASK_INFO_SIZE_VER0 => return -EINVAL,
+        Ok(size) => size.min(core::mem::size_of::<UserTaskInfo>()),
+        Err(errno) => return errno,
+    };
+    match TASK_MANAGER.get_task_info(pid) {
+        Some(task_info) => {
+            let info_val = UserTaskInfo {
+                size,
+                ..UserTaskInfo::from(&task_info)
+            };
+            match info.write_prefix(info_val, size) {
+                Ok(()) => 0,
+                Err(errno) => errno,
+            }
+        }
+        None => -ESRCH,
+    }
+}
+
+pub fn sys_getpid() -> isize {
+    TASK_MANAGER.get_current_pid() as isize
+}
+
+/// a file referring to process `pid`, for `sys_pidfd_getfd`
+pub fn sys_pidfd_open(pid: usize, flags: usize) -> isize {
+    if flags != 0 {
+        return -EINVAL;
+    }
+    if !TASK_MANAGER.is_live_process(pid) {
+        return -ESRCH;
+    }
+    match TASK_MANAGER.alloc_current_fd(Arc::new(PidFd { pid })) {
+        Ok(fd) => fd as isize,
+        Err(errno) => errno,
+    }
+}
+
+pub fn sys_gettid() -> isize {
+    TASK_MANAGER.get_current_task() as isize
+}
+
+/// there are no users, everything runs as root, for all the ids
+pub fn sys_getuid() -> isize {
     0
 }
 
-/// get time in milliseconds
-pub fn sys_get_time() -> isize {
-    get_time_ms() as isize
+const UTS_LEN: usize = 65;
+
+/// `struct utsname`
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct UtsName {
+    pub sysname: [u8; UTS_LEN],
+    pub nodename: [u8; UTS_LEN],
+    pub release: [u8; UTS_LEN],
+    pub version: [u8; UTS_LEN],
+    pub machine: [u8; UTS_LEN],
+    pub domainname: [u8; UTS_LEN],
 }
 
-pub fn sys_get_taskinfo(buf: *mut u8, len: usize) -> isize {
-    if !check_buf(buf, len) {
-        return -1;
+fn uts_field(s: &str) -> [u8; UTS_LEN] {
+    let mut field = [0; UTS_LEN];
+    field[..s.len()].copy_from_slice(s.as_bytes());
+    field
+}
+
+/// name and version of the kernel
+pub fn sys_uname(buf: UserPtr<UtsName>) -> isize {
+    let uts = UtsName {
+        sysname: uts_field("toyos"),
+        nodename: uts_field("toyos"),
+        release: uts_field(env!("CARGO_PKG_VERSION")),
+        version: uts_field("#1"),
+        machine: uts_field("riscv64"),
+        domainname: uts_field("(none)"),
+    };
+    match buf.write(uts) {
+        Ok(()) => 0,
+        Err(errno) => errno,
     }
-    let task_id = TASK_MANAGER.get_current_task();
-    let name = TASK_MANAGER.get_current_task_name();
+}
 
-    let dst = unsafe { core::slice::from_raw_parts_mut(buf, len) };
-    let src = name.as_bytes();
+/// create a thread in current process, it starts at `entry` with `arg` in
+/// `a0` and must end with `sys_exit`. `-EAGAIN` past the thread limits,
+/// `-ENOMEM` if there is no room for its stacks.
+pub fn sys_thread_create(entry: usize, arg: usize) -> isize {
+    match TASK_MANAGER.create_thread(entry, arg) {
+        Ok(tid) => tid as isize,
+        Err(errno) => errno,
+    }
+}
 
-    if src.len() > dst.len() {
-        return -1;
+/// wait for thread `tid` of current process to exit, returns its exit code.
+/// `-ESRCH` if there is no such thread, `-EDEADLK` for current thread.
+pub fn sys_waittid(tid: usize) -> isize {
+    loop {
+        match TASK_MANAGER.try_join(tid) {
+            JoinResult::Exited(exit_code) => return exit_code as isize,
+            JoinResult::Running => block_current_and_run_next(),
+            JoinResult::NotFound => return -ESRCH,
+            JoinResult::Current => return -EDEADLK,
+        }
     }
+}
 
-    dst[0..src.len()].copy_from_slice(src);
+/// send signal `signum` to process `pid`, 0 only checks that it exists
+pub fn sys_kill(pid: usize, signum: usize) -> isize {
+    if signum > MAX_SIG {
+        return -EINVAL;
+    }
+    if signum == 0 {
+        return match TASK_MANAGER.is_live_process(pid) {
+            true => 0,
+            false => -ESRCH,
+        };
+    }
+    match TASK_MANAGER.send_signal(pid, signum) {
+        Some(()) => 0,
+        None => -ESRCH,
+    }
+}
 
-    task_id as isize
+/// install `action` for `signum`, the previous one is stored into
+/// `old_action`; either pointer may be null
+pub fn sys_sigaction(
+    signum: usize,
+    action: UserPtr<SignalAction>,
+    old_action: UserPtr<SignalAction>,
+    sigsetsize: usize,
+) -> isize {
+    if signum == 0 || signum > MAX_SIG || signum == SIGKILL || signum == SIGSTOP {
+        return -EINVAL;
+    }
+    if sigsetsize != SIGSET_SIZE {
+        return -EINVAL;
+    }
+    // copy in and out before changing anything, so a bad pointer has no
+    // effect
+    let new = match action.is_null() {
+        true => None,
+        false => match action.read() {
+            Ok(action) => Some(action),
+            Err(errno) => return errno,
+        },
+    };
+    if !old_action.is_null() {
+        if let Err(errno) = old_action.write(TASK_MANAGER.get_current_sigaction(signum)) {
+            return errno;
+        }
+    }
+    if let Some(new) = new {
+        TASK_MANAGER.set_current_sigaction(signum, new);
+    }
+    0
+}
+
+/// examine and change blocked signals, `how` is one of `SIG_BLOCK`,
+/// `SIG_UNBLOCK` and `SIG_SETMASK`; either pointer may be null
+pub fn sys_sigprocmask(
+    how: usize,
+    set: UserPtr<u64>,
+    old_set: UserPtr<u64>,
+    sigsetsize: usize,
+) -> isize {
+    if sigsetsize != SIGSET_SIZE {
+        return -EINVAL;
+    }
+    let set = match set.is_null() {
+        true => None,
+        false => match set.read() {
+            Ok(set) => Some(set),
+            Err(errno) => return errno,
+        },
+    };
+
+    let old = TASK_MANAGER.get_current_sigmask();
+    let mask = match (set, how) {
+        (None, _) => old,
+        (Some(set), SIG_BLOCK) => old | set,
+        (Some(set), SIG_UNBLOCK) => old & !set,
+        (Some(set), SIG_SETMASK) => set,
+        _ => return -EINVAL,
+    };
+    if !old_set.is_null() {
+        if let Err(errno) = old_set.write(old) {
+            return errno;
+        }
+    }
+    TASK_MANAGER.set_current_sigmask(mask);
+    0
+}
+
+/// return from a signal handler to where the signal interrupted the task, the
+/// return value is overwritten by the restored `a0`. `-EINVAL` if no handler
+/// runs on current thread.
+pub fn sys_sigreturn() -> isize {
+    match TASK_MANAGER.current_sigreturn() {
+        Some(()) => 0,
+        None => -EINVAL,
+    }
+}
+
+/// Trace the syscall classes in `mask` (see [`super::trace`]) made by
+/// process `pid`, 0 turns tracing off. Returns the old mask.
+pub fn sys_trace(pid: usize, mask: usize) -> isize {
+    match TASK_MANAGER.set_trace_mask(pid, mask) {
+        Some(old) => old as isize,
+        None => -EINVAL,
+    }
+}
+
+pub const PROFILE_STOP: usize = 0;
+/// drop the samples so far and start sampling
+pub const PROFILE_START: usize = 1;
+pub const PROFILE_DUMP_TEXT: usize = 2;
+pub const PROFILE_DUMP_FOLDED: usize = 3;
+
+/// Control the sampling profiler. The dump commands copy as much of the
+/// profile as fits into `buf` and return the number of bytes copied.
+pub fn sys_profile(cmd: usize, buf: UserSlice) -> isize {
+    let format = match cmd {
+        PROFILE_STOP => {
+            profile::stop();
+            return 0;
+        }
+        PROFILE_START => {
+            profile::start();
+            return 0;
+        }
+        PROFILE_DUMP_TEXT => ProfileFormat::Text,
+        PROFILE_DUMP_FOLDED => ProfileFormat::Folded,
+        _ => return -EINVAL,
+    };
+    let dump = profile::dump(format);
+    match buf.copy_to_user(dump.as_bytes()) {
+        Ok(len) => len as isize,
+        Err(errno) => errno,
+    }
 }
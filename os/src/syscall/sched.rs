@@ -0,0 +1,203 @@
+//! Scheduling syscalls, `pid` is a tid and 0 means the calling thread
+
+use core::time::Duration;
+
+use super::errno::{EINVAL, ESRCH};
+use crate::{
+    mm::UserPtr,
+    task::{
+        deadline::DeadlineParams,
+        run_next_task,
+        sched::{
+            SchedPolicy, MAX_RT_PRIO, MIN_RT_PRIO, SCHED_DEADLINE, SCHED_FIFO, SCHED_OTHER,
+            SCHED_RR,
+        },
+        suspend_current_and_run_next, TASK_MANAGER,
+    },
+    timer::{timer_now, TimeSpec},
+};
+
+/// `struct sched_param`
+#[repr(C)]
+#[derive(Copy, Clone, Default)]
+pub struct SchedParam {
+    pub priority: i32,
+}
+
+/// `priority` of `param`, `-EINVAL` if it is negative
+fn read_priority(param: UserPtr<SchedParam>) -> Result<usize, isize> {
+    if param.is_null() {
+        return Err(-EINVAL);
+    }
+    usize::try_from(param.read()?.priority).map_err(|_| -EINVAL)
+}
+
+fn set_policy(pid: usize, policy: usize, param: UserPtr<SchedParam>) -> isize {
+    let priority = match read_priority(param) {
+        Ok(priority) => priority,
+        Err(errno) => return errno,
+    };
+    let sched = match SchedPolicy::new(policy, priority) {
+        Some(sched) => sched,
+        None => return -EINVAL,
+    };
+    match TASK_MANAGER.set_sched_policy(pid, sched) {
+        Some(()) => 0,
+        None => -ESRCH,
+    }
+}
+
+/// schedule thread `pid` by `policy` at the priority in `param`
+pub fn sys_sched_setscheduler(pid: usize, policy: usize, param: UserPtr<SchedParam>) -> isize {
+    set_policy(pid, policy, param)
+}
+
+/// change only the priority of thread `pid`
+pub fn sys_sched_setparam(pid: usize, param: UserPtr<SchedParam>) -> isize {
+    match TASK_MANAGER.get_sched_policy(pid) {
+        Some(sched) => set_policy(pid, sched.policy, param),
+        None => -ESRCH,
+    }
+}
+
+pub fn sys_sched_getscheduler(pid: usize) -> isize {
+    match TASK_MANAGER.get_sched_policy(pid) {
+        Some(sched) => sched.policy as isize,
+        None => -ESRCH,
+    }
+}
+
+pub fn sys_sched_getparam(pid: usize, param: UserPtr<SchedParam>) -> isize {
+    let sched = match TASK_MANAGER.get_sched_policy(pid) {
+        Some(sched) => sched,
+        None => return -ESRCH,
+    };
+    let param_val = SchedParam {
+        priority: sched.priority as i32,
+    };
+    match param.write(param_val) {
+        Ok(()) => 0,
+        Err(errno) => errno,
+    }
+}
+
+/// Give up the CPU to the next ready thread of the same or a higher
+/// priority, if there is none current one goes on. A deadline thread is done
+/// with its job and waits for the next period.
+pub fn sys_sched_yield() -> isize {
+    if TASK_MANAGER.end_current_job(timer_now()) {
+        run_next_task();
+    } else {
+        suspend_current_and_run_next();
+    }
+    0
+}
+
+/// `struct sched_attr`, times in nanoseconds
+#[repr(C)]
+#[derive(Copy, Clone, Default)]
+pub struct SchedAttr {
+    pub size: u32,
+    pub policy: u32,
+    pub flags: u64,
+    pub nice: i32,
+    pub priority: u32,
+    pub runtime: u64,
+    pub deadline: u64,
+    pub period: u64,
+}
+
+/// the size of the first version of `struct sched_attr`, the only one
+const SCHED_ATTR_SIZE_VER0: u32 = 48;
+
+/// Schedule thread `pid` by `attr`, the only way to `SCHED_DEADLINE`,
+/// `-EBUSY` if admission control turns it down. No `flags` are supported.
+pub fn sys_sched_setattr(pid: usize, attr: UserPtr<SchedAttr>, flags: usize) -> isize {
+    if attr.is_null() || flags != 0 {
+        return -EINVAL;
+    }
+    let attr = match attr.read() {
+        Ok(attr) => attr,
+        Err(errno) => return errno,
+    };
+    if (attr.size != 0 && attr.size < SCHED_ATTR_SIZE_VER0) || attr.flags != 0 {
+        return -EINVAL;
+    }
+    if attr.policy as usize != SCHED_DEADLINE {
+        let sched = match SchedPolicy::new(attr.policy as usize, attr.priority as usize) {
+            Some(sched) => sched,
+            None => return -EINVAL,
+        };
+        return match TASK_MANAGER.set_sched_policy(pid, sched) {
+            Some(()) => 0,
+            None => -ESRCH,
+        };
+    }
+    let params = DeadlineParams::new(
+        Duration::from_nanos(attr.runtime),
+        Duration::from_nanos(attr.deadline),
+        Duration::from_nanos(attr.period),
+    );
+    let params = match (attr.priority, params) {
+        (0, Some(params)) => params,
+        _ => return -EINVAL,
+    };
+    match TASK_MANAGER.set_deadline(pid, params, timer_now()) {
+        Ok(()) => 0,
+        Err(errno) => errno,
+    }
+}
+
+/// how thread `pid` is scheduled into `attr`, which holds `size` bytes
+pub fn sys_sched_getattr(pid: usize, attr: UserPtr<SchedAttr>, size: usize, flags: usize) -> isize {
+    if size < SCHED_ATTR_SIZE_VER0 as usize || flags != 0 {
+        return -EINVAL;
+    }
+    let sched = match TASK_MANAGER.get_sched_policy(pid) {
+        Some(sched) => sched,
+        None => return -ESRCH,
+    };
+    let mut attr_val = SchedAttr {
+        size: SCHED_ATTR_SIZE_VER0,
+        policy: sched.policy as u32,
+        priority: sched.priority as u32,
+        ..SchedAttr::default()
+    };
+    if let Some(params) = TASK_MANAGER.get_deadline(pid) {
+        attr_val.runtime = params.runtime.as_nanos() as u64;
+        attr_val.deadline = params.deadline.as_nanos() as u64;
+        attr_val.period = params.period.as_nanos() as u64;
+    }
+    match attr.write(attr_val) {
+        Ok(()) => 0,
+        Err(errno) => errno,
+    }
+}
+
+pub fn sys_sched_get_priority_max(policy: usize) -> isize {
+    match policy {
+        SCHED_OTHER | SCHED_DEADLINE => 0,
+        SCHED_FIFO | SCHED_RR => MAX_RT_PRIO as isize,
+        _ => -EINVAL,
+    }
+}
+
+pub fn sys_sched_get_priority_min(policy: usize) -> isize {
+    match policy {
+        SCHED_OTHER | SCHED_DEADLINE => 0,
+        SCHED_FIFO | SCHED_RR => MIN_RT_PRIO as isize,
+        _ => -EINVAL,
+    }
+}
+
+/// the time slice of thread `pid` into `tp`, zero for `SCHED_FIFO`
+pub fn sys_sched_rr_get_interval(pid: usize, tp: UserPtr<TimeSpec>) -> isize {
+    let slice = match TASK_MANAGER.get_time_slice(pid) {
+        Some(slice) => slice.unwrap_or_default(),
+        None => return -ESRCH,
+    };
+    match tp.write(TimeSpec::from(slice)) {
+        Ok(()) => 0,
+        Err(errno) => errno,
+    }
+}
@@ -0,0 +1,145 @@
+//! Synchronization syscalls
+
+use alloc::sync::Arc;
+use core::sync::atomic::AtomicU32;
+
+use super::errno::{EAGAIN, EDEADLK, EINVAL, EPERM};
+use crate::{
+    mm::UserPtr,
+    sync::{futex_wait, futex_wake, Condvar, Mutex, Resource, Semaphore},
+    task::TASK_MANAGER,
+};
+
+pub const FUTEX_WAIT: usize = 0;
+pub const FUTEX_WAKE: usize = 1;
+
+/// `FUTEX_WAIT`: block while `*uaddr == val`
+/// `FUTEX_WAKE`: wake at most `val` tasks blocked on `uaddr`
+pub fn sys_futex(uaddr: UserPtr<AtomicU32>, op: usize, val: usize) -> isize {
+    if uaddr.addr() % core::mem::align_of::<AtomicU32>() != 0 {
+        return -EINVAL;
+    }
+    let futex = match uaddr.as_ref() {
+        Ok(futex) => futex,
+        Err(errno) => return errno,
+    };
+
+    match op {
+        FUTEX_WAIT => {
+            if futex_wait(futex, val as u32) {
+                0
+            } else {
+                -EAGAIN
+            }
+        }
+        FUTEX_WAKE => futex_wake(futex, val) as isize,
+        _ => -EINVAL,
+    }
+}
+
+pub fn sys_mutex_create() -> isize {
+    TASK_MANAGER.add_current_mutex(Arc::new(Mutex::new())) as isize
+}
+
+/// `-EDEADLK` if deadlock detection is on and waiting could deadlock
+pub fn sys_mutex_lock(mutex_id: usize) -> isize {
+    let mutex = match TASK_MANAGER.get_current_mutex(mutex_id) {
+        Some(mutex) => mutex,
+        None => return -EINVAL,
+    };
+    let resource = Resource::Mutex(mutex_id);
+    if !TASK_MANAGER.request_current_resource(resource) {
+        return -EDEADLK;
+    }
+    mutex.lock();
+    TASK_MANAGER.grant_current_resource(resource);
+    0
+}
+
+pub fn sys_mutex_unlock(mutex_id: usize) -> isize {
+    let mutex = match TASK_MANAGER.get_current_mutex(mutex_id) {
+        Some(mutex) => mutex,
+        None => return -EINVAL,
+    };
+    if mutex.unlock().is_none() {
+        return -EPERM;
+    }
+    TASK_MANAGER.release_current_resource(Resource::Mutex(mutex_id));
+    0
+}
+
+pub fn sys_semaphore_create(count: usize) -> isize {
+    TASK_MANAGER.add_current_semaphore(Arc::new(Semaphore::new(count)), count) as isize
+}
+
+pub fn sys_semaphore_up(sem_id: usize) -> isize {
+    let semaphore = match TASK_MANAGER.get_current_semaphore(sem_id) {
+        Some(semaphore) => semaphore,
+        None => return -EINVAL,
+    };
+    TASK_MANAGER.release_current_resource(Resource::Semaphore(sem_id));
+    semaphore.up();
+    0
+}
+
+/// `-EDEADLK` if deadlock detection is on and waiting could deadlock
+pub fn sys_semaphore_down(sem_id: usize) -> isize {
+    let semaphore = match TASK_MANAGER.get_current_semaphore(sem_id) {
+        Some(semaphore) => semaphore,
+        None => return -EINVAL,
+    };
+    let resource = Resource::Semaphore(sem_id);
+    if !TASK_MANAGER.request_current_resource(resource) {
+        return -EDEADLK;
+    }
+    semaphore.down();
+    TASK_MANAGER.grant_current_resource(resource);
+    0
+}
+
+pub fn sys_condvar_create() -> isize {
+    TASK_MANAGER.add_current_condvar(Arc::new(Condvar::new())) as isize
+}
+
+pub fn sys_condvar_signal(condvar_id: usize) -> isize {
+    match TASK_MANAGER.get_current_condvar(condvar_id) {
+        Some(condvar) => {
+            condvar.signal();
+            0
+        }
+        None => -EINVAL,
+    }
+}
+
+/// release mutex `mutex_id` while waiting on condvar `condvar_id`
+pub fn sys_condvar_wait(condvar_id: usize, mutex_id: usize) -> isize {
+    let (condvar, mutex) = match (
+        TASK_MANAGER.get_current_condvar(condvar_id),
+        TASK_MANAGER.get_current_mutex(mutex_id),
+    ) {
+        (Some(condvar), Some(mutex)) => (condvar, mutex),
+        _ => return -EINVAL,
+    };
+    if !mutex.is_held() {
+        return -EPERM;
+    }
+    // the mutex is given up and taken back inside `wait`, keep the detector
+    // in sync with that
+    let resource = Resource::Mutex(mutex_id);
+    TASK_MANAGER.release_current_resource(resource);
+    condvar.wait(&mutex);
+    TASK_MANAGER.request_current_resource(resource);
+    TASK_MANAGER.grant_current_resource(resource);
+    0
+}
+
+/// turn deadlock detection for current process on (`enabled == 1`) or off
+pub fn sys_enable_deadlock_detect(enabled: usize) -> isize {
+    match enabled {
+        0 | 1 => {
+            TASK_MANAGER.set_current_deadlock_detect(enabled == 1);
+            0
+        }
+        _ => -EINVAL,
+    }
+}
@@ -0,0 +1,101 @@
+//! `syslog(2)` over the kernel log ring buffer, and control of the log filter
+
+use alloc::vec;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use super::errno::EINVAL;
+use crate::{
+    logging::{
+        console_loglevel, set_console_loglevel, set_filter, LogFilter, DEFAULT_CONSOLE_LOGLEVEL,
+        KMSG, KMSG_BUFFER_SIZE, MINIMUM_CONSOLE_LOGLEVEL,
+    },
+    mm::{read_cstr, UserPtr, UserSlice},
+};
+
+const MAX_FILTER_LEN: usize = 256;
+
+pub const SYSLOG_ACTION_CLOSE: usize = 0;
+pub const SYSLOG_ACTION_OPEN: usize = 1;
+/// consume the unread records, returns 0 instead of blocking if there is none
+pub const SYSLOG_ACTION_READ: usize = 2;
+/// the newest records, without consuming them
+pub const SYSLOG_ACTION_READ_ALL: usize = 3;
+pub const SYSLOG_ACTION_READ_CLEAR: usize = 4;
+pub const SYSLOG_ACTION_CLEAR: usize = 5;
+pub const SYSLOG_ACTION_CONSOLE_OFF: usize = 6;
+pub const SYSLOG_ACTION_CONSOLE_ON: usize = 7;
+/// `len` is the new console log level, 1..=8
+pub const SYSLOG_ACTION_CONSOLE_LEVEL: usize = 8;
+pub const SYSLOG_ACTION_SIZE_UNREAD: usize = 9;
+pub const SYSLOG_ACTION_SIZE_BUFFER: usize = 10;
+
+/// the console level `SYSLOG_ACTION_CONSOLE_ON` goes back to
+static SAVED_CONSOLE_LOGLEVEL: AtomicUsize = AtomicUsize::new(DEFAULT_CONSOLE_LOGLEVEL);
+
+/// `len` is the length of `buf`, or the level for
+/// `SYSLOG_ACTION_CONSOLE_LEVEL`
+pub fn sys_syslog(action: usize, buf: UserSlice) -> isize {
+    let len = buf.len();
+    match action {
+        SYSLOG_ACTION_CLOSE | SYSLOG_ACTION_OPEN => 0,
+        SYSLOG_ACTION_READ | SYSLOG_ACTION_READ_ALL | SYSLOG_ACTION_READ_CLEAR => {
+            // a read consumes records, check first
+            if let Err(errno) = buf.check_writable() {
+                return errno;
+            }
+            let mut kernel_buf = vec![0u8; len.min(KMSG_BUFFER_SIZE)];
+            let mut kmsg = KMSG.lock();
+            let written = match action {
+                SYSLOG_ACTION_READ => kmsg.read(&mut kernel_buf),
+                _ => kmsg.render_all(&mut kernel_buf),
+            };
+            if action == SYSLOG_ACTION_READ_CLEAR {
+                kmsg.clear();
+            }
+            drop(kmsg);
+            match buf.copy_to_user(&kernel_buf[..written]) {
+                Ok(written) => written as isize,
+                Err(errno) => errno,
+            }
+        }
+        SYSLOG_ACTION_CLEAR => {
+            KMSG.lock().clear();
+            0
+        }
+        SYSLOG_ACTION_CONSOLE_OFF => {
+            SAVED_CONSOLE_LOGLEVEL.store(console_loglevel(), Ordering::Relaxed);
+            set_console_loglevel(MINIMUM_CONSOLE_LOGLEVEL);
+            0
+        }
+        SYSLOG_ACTION_CONSOLE_ON => {
+            set_console_loglevel(SAVED_CONSOLE_LOGLEVEL.load(Ordering::Relaxed));
+            0
+        }
+        SYSLOG_ACTION_CONSOLE_LEVEL => {
+            if !(MINIMUM_CONSOLE_LOGLEVEL..=DEFAULT_CONSOLE_LOGLEVEL).contains(&len) {
+                return -EINVAL;
+            }
+            set_console_loglevel(len);
+            0
+        }
+        SYSLOG_ACTION_SIZE_UNREAD => KMSG.lock().unread_size() as isize,
+        SYSLOG_ACTION_SIZE_BUFFER => KMSG_BUFFER_SIZE as isize,
+        _ => -EINVAL,
+    }
+}
+
+/// Replace the kernel log filter with `spec`, in the syntax of `log=` on the
+/// command line, e.g. `info,toyos::task=trace`.
+pub fn sys_set_log_filter(spec: UserPtr<u8>) -> isize {
+    let spec = match read_cstr(spec.addr(), MAX_FILTER_LEN) {
+        Ok(spec) => spec,
+        Err(errno) => return errno,
+    };
+    match LogFilter::parse(&spec) {
+        Some(filter) => {
+            set_filter(filter);
+            0
+        }
+        None => -EINVAL,
+    }
+}
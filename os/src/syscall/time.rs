@@ -0,0 +1,167 @@
+//! Clock syscalls
+
+use core::time::Duration;
+
+use super::errno::EINVAL;
+use crate::{
+    mm::UserPtr,
+    task::{
+        block_current_and_run_next,
+        itimer::{ITimer, ITIMER_PROF, ITIMER_REAL},
+        TASK_MANAGER,
+    },
+    timer::{
+        add_timer, cancel_timer, realtime, set_realtime, timer_now, TimeSpec, TimeVal, TimerEvent,
+    },
+};
+
+/// wall-clock time
+pub const CLOCK_REALTIME: usize = 0;
+/// time since boot, never jumps
+pub const CLOCK_MONOTONIC: usize = 1;
+/// CPU time of current process
+pub const CLOCK_PROCESS_CPUTIME_ID: usize = 2;
+/// CPU time of current thread
+pub const CLOCK_THREAD_CPUTIME_ID: usize = 3;
+
+/// read clock `clock_id` into `tp`
+pub fn sys_clock_gettime(clock_id: usize, tp: UserPtr<TimeSpec>) -> isize {
+    let time = match clock_id {
+        CLOCK_REALTIME => realtime(),
+        CLOCK_MONOTONIC => timer_now(),
+        CLOCK_PROCESS_CPUTIME_ID => TASK_MANAGER.get_current_process_cpu_time(),
+        CLOCK_THREAD_CPUTIME_ID => TASK_MANAGER.get_current_thread_cpu_time(),
+        _ => return -EINVAL,
+    };
+    match tp.write(TimeSpec::from(time)) {
+        Ok(()) => 0,
+        Err(errno) => errno,
+    }
+}
+
+/// Sleep for `req` on a kernel timer. Signals do not wake a blocked thread,
+/// so the sleep is never cut short and `rem` is left alone.
+pub fn sys_nanosleep(req: UserPtr<TimeSpec>, _rem: UserPtr<TimeSpec>) -> isize {
+    let req = match req.read() {
+        Ok(req) => req.to_duration(),
+        Err(errno) => return errno,
+    };
+    let deadline = match req {
+        Some(req) => timer_now() + req,
+        None => return -EINVAL,
+    };
+    let tid = TASK_MANAGER.get_current_task();
+    // woken by someone else, e.g. a futex, sleep on
+    while timer_now() < deadline {
+        add_timer(deadline, TimerEvent::Wakeup(tid));
+        block_current_and_run_next();
+        cancel_timer(TimerEvent::Wakeup(tid));
+    }
+    0
+}
+
+/// `struct timezone`, always UTC
+#[repr(C)]
+#[derive(Copy, Clone, Default)]
+pub struct TimeZone {
+    pub minuteswest: i32,
+    pub dsttime: i32,
+}
+
+/// the wall-clock time into `tv`, either pointer may be null
+pub fn sys_gettimeofday(tv: UserPtr<TimeVal>, tz: UserPtr<TimeZone>) -> isize {
+    if !tv.is_null() {
+        if let Err(errno) = tv.write(TimeVal::from(realtime())) {
+            return errno;
+        }
+    }
+    if !tz.is_null() {
+        if let Err(errno) = tz.write(TimeZone::default()) {
+            return errno;
+        }
+    }
+    0
+}
+
+/// Set the wall-clock time to `tv`. Only the offset of `CLOCK_REALTIME` from
+/// the time since boot changes, the RTC is left alone. `tz` is obsolete and
+/// ignored.
+pub fn sys_settimeofday(tv: UserPtr<TimeVal>, _tz: UserPtr<TimeZone>) -> isize {
+    if tv.is_null() {
+        return 0;
+    }
+    let now = match tv.read() {
+        Ok(tv) => tv.to_duration(),
+        Err(errno) => return errno,
+    };
+    match now.and_then(set_realtime) {
+        Some(()) => 0,
+        None => -EINVAL,
+    }
+}
+
+/// `struct itimerval`
+#[repr(C)]
+#[derive(Copy, Clone, Default)]
+pub struct ITimerVal {
+    pub interval: TimeVal,
+    pub value: TimeVal,
+}
+
+impl From<ITimer> for ITimerVal {
+    fn from(timer: ITimer) -> Self {
+        ITimerVal {
+            interval: timer.interval.into(),
+            value: timer.value.into(),
+        }
+    }
+}
+
+/// the time left on interval timer `which` into `curr`
+pub fn sys_getitimer(which: usize, curr: UserPtr<ITimerVal>) -> isize {
+    if which > ITIMER_PROF {
+        return -EINVAL;
+    }
+    match curr.write(TASK_MANAGER.get_current_itimer(which).into()) {
+        Ok(()) => 0,
+        Err(errno) => errno,
+    }
+}
+
+/// Arm interval timer `which` with `new`, a zero value disarms it. The
+/// timer it replaces goes into `old`, which may be null.
+pub fn sys_setitimer(which: usize, new: UserPtr<ITimerVal>, old: UserPtr<ITimerVal>) -> isize {
+    if which > ITIMER_PROF {
+        return -EINVAL;
+    }
+    let new = match new.read() {
+        Ok(new) => (new.value.to_duration(), new.interval.to_duration()),
+        Err(errno) => return errno,
+    };
+    let new = match new {
+        (Some(value), Some(interval)) => ITimer { value, interval },
+        _ => return -EINVAL,
+    };
+    // check before anything changes
+    if !old.is_null() {
+        if let Err(errno) = old.write(TASK_MANAGER.get_current_itimer(which).into()) {
+            return errno;
+        }
+    }
+    TASK_MANAGER.set_current_itimer(which, new);
+    0
+}
+
+/// `SIGALRM` in `seconds`, 0 cancels it. Returns the seconds that were left
+/// on the previous alarm, rounded, but at least 1 if there was one.
+pub fn sys_alarm(seconds: usize) -> isize {
+    let new = ITimer {
+        value: Duration::from_secs(seconds as u64),
+        interval: Duration::ZERO,
+    };
+    let old = TASK_MANAGER.set_current_itimer(ITIMER_REAL, new).value;
+    match old.is_zero() {
+        true => 0,
+        false => ((old + Duration::from_millis(500)).as_secs() as isize).max(1),
+    }
+}
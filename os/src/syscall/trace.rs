@@ -0,0 +1,204 @@
+//! Syscall tracing, like a tiny in-kernel `strace`
+//!
+//! Every process has a trace mask of syscall classes. A syscall whose class
+//! is in the mask of the calling process is logged, once it returns, with
+//! its decoded arguments, return value and how long it took, blocking
+//! included. Masks are set with `sys_trace` or with `strace=` on the command
+//! line, e.g. `strace=3` traces everything process 3 does and
+//! `strace=3:0x1,4:0x6` picks classes per process.
+
+use alloc::string::String;
+use core::{fmt::Write, time::Duration};
+
+use log::info;
+
+use super::{SyscallId, SyscallId::*};
+use crate::{
+    bootargs,
+    mm::{read_cstr, UserSlice},
+    task::TASK_MANAGER,
+};
+
+pub const TRACE_FS: usize = 1 << 0;
+pub const TRACE_PROCESS: usize = 1 << 1;
+pub const TRACE_SIGNAL: usize = 1 << 2;
+pub const TRACE_SYNC: usize = 1 << 3;
+pub const TRACE_INFO: usize = 1 << 4;
+pub const TRACE_MEMORY: usize = 1 << 5;
+pub const TRACE_ALL: usize =
+    TRACE_FS | TRACE_PROCESS | TRACE_SIGNAL | TRACE_SYNC | TRACE_INFO | TRACE_MEMORY;
+
+/// how many bytes of a buffer are shown
+const SHOWN_BYTES: usize = 32;
+
+fn class_of(id: SyscallId) -> usize {
+    match id {
+        OpenAt | Close | Pipe | Read | Write | ReadV | WriteV | Ioctl | PidfdGetfd => TRACE_FS,
+        Exit | ExitGroup | Exec | GetPid | GetTid | SetTidAddress | ThreadCreate | WaitTid
+        | SchedSetParam | SchedSetScheduler | SchedGetScheduler | SchedGetParam | SchedYield
+        | SchedGetPriorityMax | SchedGetPriorityMin | SchedRrGetInterval | SchedSetAttr
+        | SchedGetAttr | PidfdOpen => TRACE_PROCESS,
+        Kill | SigAction | SigProcMask | SigReturn | GetITimer | SetITimer | Alarm => TRACE_SIGNAL,
+        Futex | EnableDeadlockDetect | MutexCreate | MutexLock | MutexUnlock | SemaphoreCreate
+        | SemaphoreUp | SemaphoreDown | CondvarCreate | CondvarSignal | CondvarWait => TRACE_SYNC,
+        Brk | Sbrk | Mmap | Munmap | Mprotect => TRACE_MEMORY,
+        _ => TRACE_INFO,
+    }
+}
+
+/// Apply `strace=` of the command line.
+pub fn init() {
+    let spec = match bootargs::get("strace") {
+        Some(spec) => spec,
+        None => return,
+    };
+    for item in spec.split(',') {
+        let (pid, mask) = match item.split_once(':') {
+            Some((pid, mask)) => (pid, parse_mask(mask)),
+            None => (item, Some(TRACE_ALL)),
+        };
+        match (pid.parse(), mask) {
+            (Ok(pid), Some(mask)) if TASK_MANAGER.set_trace_mask(pid, mask).is_some() => {}
+            _ => log::warn!("invalid strace= item {:?}", item),
+        }
+    }
+}
+
+fn parse_mask(mask: &str) -> Option<usize> {
+    match mask.strip_prefix("0x") {
+        Some(hex) => usize::from_str_radix(hex, 16).ok(),
+        None => mask.parse().ok(),
+    }
+}
+
+/// whether the current process traces `id`
+pub fn is_traced(id: SyscallId) -> bool {
+    TASK_MANAGER.get_current_trace_mask() & class_of(id) != 0
+}
+
+/// the user buffer at `ptr`, as far as the syscall got with it
+fn format_buf(out: &mut String, ptr: usize, len: usize) {
+    let mut bytes = [0u8; SHOWN_BYTES];
+    let shown = match UserSlice::new(ptr, len).copy_from_user(&mut bytes) {
+        Ok(shown) => shown,
+        Err(_) => {
+            let _ = write!(out, "{:#x}", ptr);
+            return;
+        }
+    };
+    let _ = write!(out, "\"{}\"", bytes[..shown].escape_ascii());
+    if shown < len {
+        out.push_str("...");
+    }
+}
+
+fn format_str(out: &mut String, ptr: usize) {
+    let _ = match read_cstr(ptr, SHOWN_BYTES) {
+        Ok(s) => write!(out, "{:?}", s),
+        Err(_) => write!(out, "{:#x}", ptr),
+    };
+}
+
+/// Log one syscall. `ret` is `None` for a syscall that does not return.
+pub fn log_syscall(id: SyscallId, args: [usize; 6], ret: Option<isize>, duration: Duration) {
+    let mut line = String::new();
+    let _ = write!(line, "{:?}(", id);
+    let ok = ret.map_or(false, |ret| ret >= 0);
+    let _ = match id {
+        Read if ok => {
+            let _ = write!(line, "{}, ", args[0]);
+            format_buf(&mut line, args[1], ret.unwrap() as usize);
+            write!(line, ", {}", args[2])
+        }
+        Write if ok => {
+            let _ = write!(line, "{}, ", args[0]);
+            format_buf(&mut line, args[1], args[2]);
+            write!(line, ", {}", args[2])
+        }
+        OpenAt => {
+            let _ = write!(line, "{}, ", args[0] as isize);
+            format_str(&mut line, args[1]);
+            write!(line, ", {:#x}", args[2])
+        }
+        Exec => {
+            format_str(&mut line, args[0]);
+            write!(line, ", {:#x}, {:#x}", args[1], args[2])
+        }
+        SetLogFilter => {
+            format_str(&mut line, args[0]);
+            Ok(())
+        }
+        Close | Exit | ExitGroup | MutexLock | MutexUnlock | SemaphoreCreate | SemaphoreUp
+        | SemaphoreDown | CondvarSignal | WaitTid | EnableDeadlockDetect => {
+            write!(line, "{}", args[0] as isize)
+        }
+        Kill => write!(
+            line,
+            "{}, {}",
+            args[0],
+            crate::task::signal::signal_name(args[1])
+        ),
+        SigAction => write!(
+            line,
+            "{}, {:#x}, {:#x}, {}",
+            crate::task::signal::signal_name(args[0]),
+            args[1],
+            args[2],
+            args[3]
+        ),
+        SigProcMask => write!(
+            line,
+            "{}, {:#x}, {:#x}, {}",
+            args[0], args[1], args[2], args[3]
+        ),
+        ReadV | WriteV => write!(line, "{}, {:#x}, {}", args[0], args[1], args[2]),
+        Ioctl => write!(line, "{}, {:#x}, {:#x}", args[0], args[1], args[2]),
+        PidfdOpen => write!(line, "{}, {:#x}", args[0], args[1]),
+        PidfdGetfd => write!(line, "{}, {}, {:#x}", args[0], args[1], args[2]),
+        SetTidAddress => write!(line, "{:#x}", args[0]),
+        Futex => write!(line, "{:#x}, {}, {}", args[0], args[1], args[2]),
+        Brk => write!(line, "{:#x}", args[0]),
+        GetITimer => write!(line, "{}, {:#x}", args[0], args[1]),
+        SetITimer => write!(line, "{}, {:#x}, {:#x}", args[0], args[1], args[2]),
+        Alarm => write!(line, "{}", args[0]),
+        Sbrk => write!(line, "{}", args[0] as isize),
+        Munmap => write!(line, "{:#x}, {}", args[0], args[1]),
+        Mprotect => write!(line, "{:#x}, {}, {:#x}", args[0], args[1], args[2]),
+        Mmap => write!(
+            line,
+            "{:#x}, {}, {:#x}, {:#x}, {}, {}",
+            args[0], args[1], args[2], args[3], args[4] as isize, args[5]
+        ),
+        ThreadCreate => write!(line, "{:#x}, {}", args[0], args[1]),
+        CondvarWait | GetPidTaskInfo => write!(line, "{}, {:#x}", args[0], args[1]),
+        ClockGetTime => write!(line, "{}, {:#x}", args[0], args[1]),
+        NanoSleep => write!(line, "{:#x}, {:#x}", args[0], args[1]),
+        SchedSetScheduler => write!(line, "{}, {}, {:#x}", args[0], args[1], args[2]),
+        SchedSetAttr => write!(line, "{}, {:#x}, {:#x}", args[0], args[1], args[2]),
+        SchedGetAttr => write!(
+            line,
+            "{}, {:#x}, {}, {:#x}",
+            args[0], args[1], args[2], args[3]
+        ),
+        SchedSetParam | SchedGetParam | SchedRrGetInterval => {
+            write!(line, "{}, {:#x}", args[0], args[1])
+        }
+        SchedGetScheduler | SchedGetPriorityMax | SchedGetPriorityMin => {
+            write!(line, "{}", args[0])
+        }
+        GetTimeOfDay | SetTimeOfDay => write!(line, "{:#x}, {:#x}", args[0], args[1]),
+        SchedYield | GetPid | GetTid | GetUid | GetEuid | GetGid | GetEgid | SigReturn
+        | MutexCreate | CondvarCreate => Ok(()),
+        _ => write!(line, "{:#x}, {:#x}, {:#x}", args[0], args[1], args[2]),
+    };
+    let _ = match ret {
+        Some(ret) => write!(line, ") = {} <{}us>", ret, duration.as_micros()),
+        None => write!(line, ") = ?"),
+    };
+    info!(
+        "[pid {} tid {}] {}",
+        TASK_MANAGER.get_current_pid(),
+        TASK_MANAGER.get_current_task(),
+        line
+    );
+}
@@ -0,0 +1,253 @@
+//! `SCHED_DEADLINE`, earliest deadline first on a CPU reservation
+//!
+//! A thread asks for `runtime` of CPU within `deadline` of the start of every
+//! `period`. Deadline threads come before all others, the one whose job is
+//! due first runs. The CPU it gets is charged to the budget of the period;
+//! once that is used up, or the thread calls `sched_yield` to end its job
+//! early, it is throttled: blocked until the next period refills it.
+//!
+//! Admission control keeps the summed `runtime / period` of all deadline
+//! threads at most 1. A job that has not had its runtime by its deadline is a
+//! deadline miss, counted in the [`super::TaskInfo`] of the process. Without
+//! `tickless` the tick is rearmed to fire early at the end of the budget, so
+//! budgets are enforced on time either way.
+
+use core::time::Duration;
+
+use super::{
+    sched::{SchedPolicy, SCHED_DEADLINE},
+    task::TaskStatus,
+    TaskManager, TaskManagerInner,
+};
+use crate::{
+    syscall::errno::{EBUSY, ESRCH},
+    timer::{add_timer, cancel_timer, TimerEvent},
+};
+
+/// the shortest runtime, as Linux
+const MIN_RUNTIME: Duration = Duration::from_nanos(1 << 10);
+
+/// fixed point of bandwidths, `1 << BW_SHIFT` is the whole CPU
+const BW_SHIFT: u32 = 20;
+
+/// What a deadline thread asks for
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DeadlineParams {
+    pub runtime: Duration,
+    /// relative to the start of the period
+    pub deadline: Duration,
+    pub period: Duration,
+}
+
+impl DeadlineParams {
+    /// `None` unless `runtime <= deadline <= period`, a zero `period` is
+    /// `deadline`.
+    pub fn new(runtime: Duration, deadline: Duration, period: Duration) -> Option<Self> {
+        let period = match period.is_zero() {
+            true => deadline,
+            false => period,
+        };
+        (MIN_RUNTIME <= runtime && runtime <= deadline && deadline <= period).then_some(
+            DeadlineParams {
+                runtime,
+                deadline,
+                period,
+            },
+        )
+    }
+
+    /// `runtime / period`
+    fn bandwidth(&self) -> u64 {
+        ((self.runtime.as_nanos() << BW_SHIFT) / self.period.as_nanos()) as u64
+    }
+}
+
+/// The job of a deadline thread in the current period
+#[derive(Copy, Clone, Debug)]
+pub struct DeadlineState {
+    pub params: DeadlineParams,
+    /// runtime left in this period
+    budget: Duration,
+    /// when this period started
+    release: Duration,
+    /// when the job is due, since boot
+    pub abs_deadline: Duration,
+    /// out of budget or done until the next period
+    throttled: bool,
+    /// blocked for being throttled, the next period makes it ready
+    parked: bool,
+}
+
+impl DeadlineState {
+    fn new(params: DeadlineParams, now: Duration) -> Self {
+        DeadlineState {
+            params,
+            budget: params.runtime,
+            release: now,
+            abs_deadline: now + params.deadline,
+            throttled: false,
+            parked: false,
+        }
+    }
+}
+
+impl TaskManagerInner {
+    /// Charge current thread for the CPU it had since it was last charged.
+    /// Once throttled it is parked as soon as it is runnable, returns
+    /// whether it is.
+    pub(super) fn charge_budget(&mut self, now: Duration) -> bool {
+        let since = core::mem::replace(&mut self.budget_since, now);
+        let current = self.current_task;
+        let dl = match &mut self.tasks[current].dl {
+            Some(dl) => dl,
+            None => return false,
+        };
+        if !dl.throttled {
+            dl.budget = dl.budget.saturating_sub(now.saturating_sub(since));
+            if dl.budget.is_zero() {
+                self.end_job(current, now);
+            }
+        }
+        self.park_if_throttled(current)
+    }
+
+    /// the job of deadline thread `tid` is over for this period
+    fn end_job(&mut self, tid: usize, now: Duration) {
+        let pid = self.tasks[tid].pid;
+        let dl = self.tasks[tid].dl.as_mut().unwrap();
+        dl.throttled = true;
+        if now > dl.abs_deadline {
+            self.infos[pid].deadline_misses += 1;
+        }
+    }
+
+    /// Block deadline thread `tid` until the next period if it is throttled
+    /// but runnable, returns whether it is parked.
+    fn park_if_throttled(&mut self, tid: usize) -> bool {
+        let status = self.tasks[tid].task_status;
+        let dl = self.tasks[tid].dl.as_mut().unwrap();
+        if dl.throttled && matches!(status, TaskStatus::Ready | TaskStatus::Running) {
+            dl.parked = true;
+            self.set_task_status(tid, TaskStatus::Blocked);
+        }
+        self.tasks[tid].dl.unwrap().parked
+    }
+
+    /// thread `tid` is no longer a deadline one, if it was
+    pub(super) fn drop_deadline(&mut self, tid: usize) {
+        if self.tasks[tid].dl.take().is_some() {
+            cancel_timer(TimerEvent::Replenish(tid));
+        }
+    }
+
+    /// the summed bandwidth of the live deadline threads other than `except`
+    fn deadline_bandwidth(&self, except: usize) -> u64 {
+        self.tasks
+            .iter()
+            .enumerate()
+            .filter(|(tid, task)| {
+                *tid != except
+                    && !matches!(task.task_status, TaskStatus::Uninit | TaskStatus::Exited)
+            })
+            .filter_map(|(_, task)| task.dl.as_ref())
+            .map(|dl| dl.params.bandwidth())
+            .sum()
+    }
+}
+
+impl TaskManager {
+    /// Make thread `tid`, 0 for current one, a deadline thread asking for
+    /// `params`, its first period starts at `now`. `Err` holds `-ESRCH` if
+    /// there is no such thread, or `-EBUSY` if the CPU cannot take it.
+    pub fn set_deadline(
+        &self,
+        tid: usize,
+        params: DeadlineParams,
+        now: Duration,
+    ) -> Result<(), isize> {
+        let tid = self.live_tid(tid).ok_or(-ESRCH)?;
+        let mut inner = self.inner.exclusive_access();
+        if inner.deadline_bandwidth(tid) + params.bandwidth() > 1 << BW_SHIFT {
+            return Err(-EBUSY);
+        }
+        inner.drop_deadline(tid);
+        let task = &mut inner.tasks[tid];
+        task.sched = SchedPolicy {
+            policy: SCHED_DEADLINE,
+            priority: 0,
+        };
+        task.dl = Some(DeadlineState::new(params, now));
+        add_timer(now + params.period, TimerEvent::Replenish(tid));
+        if tid == inner.current_task {
+            crate::timer::start_slice(None);
+        }
+        Ok(())
+    }
+
+    /// what deadline thread `tid` asks for, `None` if it is not one
+    pub fn get_deadline(&self, tid: usize) -> Option<DeadlineParams> {
+        let tid = self.live_tid(tid)?;
+        let inner = self.inner.exclusive_access();
+        inner.tasks[tid].dl.map(|dl| dl.params)
+    }
+
+    /// Charge current thread if it is a deadline one. Returns whether it
+    /// is out of budget and parked, the caller has to switch away.
+    pub fn charge_current_budget(&self, now: Duration) -> bool {
+        self.inner.exclusive_access().charge_budget(now)
+    }
+
+    /// Current thread is done with the job of this period. Returns false
+    /// if it is not a deadline thread, otherwise it is parked and the caller
+    /// has to switch away.
+    pub fn end_current_job(&self, now: Duration) -> bool {
+        let mut inner = self.inner.exclusive_access();
+        inner.charge_budget(now);
+        let current = inner.current_task;
+        match inner.tasks[current].dl {
+            Some(dl) if !dl.throttled => inner.end_job(current, now),
+            Some(_) => {}
+            None => return false,
+        }
+        inner.park_if_throttled(current)
+    }
+
+    /// the budget left to current thread if it is a deadline one
+    pub fn current_budget(&self) -> Option<Duration> {
+        let inner = self.inner.exclusive_access();
+        let dl = inner.tasks[inner.current_task].dl?;
+        (!dl.throttled).then_some(dl.budget)
+    }
+
+    /// The next period of deadline thread `tid` is due at `now`, a job
+    /// still runnable with budget left has missed its deadline.
+    pub fn replenish(&self, tid: usize, now: Duration) {
+        let mut inner = self.inner.exclusive_access();
+        let status = inner.tasks[tid].task_status;
+        let dl = match &mut inner.tasks[tid].dl {
+            Some(dl) => dl,
+            None => return,
+        };
+        let missed = !dl.throttled && matches!(status, TaskStatus::Ready | TaskStatus::Running);
+        let parked = dl.parked;
+        dl.throttled = false;
+        dl.parked = false;
+        // keep to the period, unless a whole one was missed
+        let next = dl.release + dl.params.period;
+        dl.release = if next + dl.params.period > now {
+            next
+        } else {
+            now
+        };
+        dl.abs_deadline = dl.release + dl.params.deadline;
+        dl.budget = dl.params.runtime;
+        add_timer(dl.release + dl.params.period, TimerEvent::Replenish(tid));
+        let pid = inner.tasks[tid].pid;
+        if missed {
+            inner.infos[pid].deadline_misses += 1;
+        }
+        if parked {
+            inner.set_task_status(tid, TaskStatus::Ready);
+        }
+    }
+}
@@ -0,0 +1,141 @@
+//! Interval timers of a process, for `setitimer` and `alarm`
+
+use core::time::Duration;
+
+use super::{
+    signal::{sigmask, SIGALRM, SIGPROF, SIGVTALRM},
+    TaskManager, TaskManagerInner,
+};
+use crate::timer::{add_timer, cancel_timer, timer_now, TimerEvent};
+
+/// counts down in real time, then `SIGALRM`
+pub const ITIMER_REAL: usize = 0;
+/// counts down while the process runs in user mode, then `SIGVTALRM`
+pub const ITIMER_VIRTUAL: usize = 1;
+/// counts down while the process runs, then `SIGPROF`
+pub const ITIMER_PROF: usize = 2;
+
+/// the signal timer `which` raises
+pub fn itimer_signal(which: usize) -> usize {
+    [SIGALRM, SIGVTALRM, SIGPROF][which]
+}
+
+/// One timer. A zero `value` is disarmed, a zero `interval` fires once.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ITimer {
+    /// time left, but the deadline since boot for `ITIMER_REAL`, whose
+    /// expiry is left to the kernel timer queue
+    pub value: Duration,
+    pub interval: Duration,
+}
+
+impl ITimer {
+    /// Count `time` down, returns whether the timer expired, in which case
+    /// it is reloaded from `interval`. Overruns are not made up for, like
+    /// signals they collapse into one.
+    pub fn consume(&mut self, time: Duration) -> bool {
+        if self.value.is_zero() {
+            return false;
+        }
+        if time < self.value {
+            self.value -= time;
+            return false;
+        }
+        self.value = self.interval;
+        true
+    }
+}
+
+/// `timer` as user space sees it, with the time left also for `ITIMER_REAL`
+fn relative(which: usize, timer: ITimer, now: Duration) -> ITimer {
+    if which != ITIMER_REAL || timer.value.is_zero() {
+        return timer;
+    }
+    // due but not yet seen by the timer interrupt, still armed
+    let left = timer
+        .value
+        .saturating_sub(now)
+        .max(Duration::from_micros(1));
+    ITimer {
+        value: left,
+        ..timer
+    }
+}
+
+impl TaskManagerInner {
+    /// count `time` spent running down the timers of process `pid`, only
+    /// `ITIMER_PROF` for kernel time
+    pub(super) fn charge_itimers(&mut self, pid: usize, time: Duration, user: bool) {
+        let process = &mut self.processes[pid];
+        let which: &[usize] = match user {
+            true => &[ITIMER_VIRTUAL, ITIMER_PROF],
+            false => &[ITIMER_PROF],
+        };
+        for which in which {
+            if process.itimers[*which].consume(time) {
+                process.signal.pending |= sigmask(itimer_signal(*which));
+            }
+        }
+    }
+}
+
+impl TaskManager {
+    /// Arm timer `which` of current process with `new`, or disarm it if its
+    /// value is zero. Returns the timer it replaces.
+    pub fn set_current_itimer(&self, which: usize, new: ITimer) -> ITimer {
+        let now = timer_now();
+        let mut inner = self.inner.exclusive_access();
+        let pid = inner.current_pid();
+        let timer = &mut inner.processes[pid].itimers[which];
+        let old = relative(which, *timer, now);
+        *timer = new;
+        if which == ITIMER_REAL {
+            cancel_timer(TimerEvent::ITimerReal(pid));
+            if !new.value.is_zero() {
+                timer.value = now + new.value;
+                add_timer(timer.value, TimerEvent::ITimerReal(pid));
+            }
+        }
+        old
+    }
+
+    pub fn get_current_itimer(&self, which: usize) -> ITimer {
+        let inner = self.inner.exclusive_access();
+        let timer = inner.processes[inner.current_pid()].itimers[which];
+        relative(which, timer, timer_now())
+    }
+
+    /// the least time left on the CPU-time timers of current process
+    pub fn next_current_cpu_itimer(&self) -> Option<Duration> {
+        let inner = self.inner.exclusive_access();
+        let itimers = &inner.processes[inner.current_pid()].itimers;
+        [ITIMER_VIRTUAL, ITIMER_PROF]
+            .iter()
+            .map(|which| itimers[*which].value)
+            .filter(|value| !value.is_zero())
+            .min()
+    }
+
+    /// `ITIMER_REAL` of process `pid` is due at `now`
+    pub fn itimer_real_expired(&self, pid: usize, now: Duration) {
+        let mut inner = self.inner.exclusive_access();
+        let process = &mut inner.processes[pid];
+        if process.exited {
+            return;
+        }
+        process.signal.pending |= sigmask(SIGALRM);
+        let timer = &mut process.itimers[ITIMER_REAL];
+        if timer.interval.is_zero() {
+            timer.value = Duration::ZERO;
+            return;
+        }
+        // keep to the period, unless a whole one was missed
+        let next = timer.value + timer.interval;
+        timer.value = if next > now {
+            next
+        } else {
+            now + timer.interval
+        };
+        add_timer(timer.value, TimerEvent::ITimerReal(pid));
+    }
+}
@@ -1,47 +1,160 @@
 mod context;
+pub mod deadline;
+pub mod itimer;
+mod process;
+pub mod sched;
+pub mod signal;
 mod task;
 
-use core::time::Duration;
+use alloc::{boxed::Box, sync::Arc, vec, vec::Vec};
+use core::{arch::asm, ops::Range, time::Duration};
 
 pub use context::TaskContext;
 use log::{info, trace};
-pub use task::TaskInfo;
+pub use process::ProcessStat;
+pub use task::{TaskInfo, TaskStatus, UserTaskInfo, TASK_INFO_SIZE_VER0};
 
 use self::{
     context::switch,
-    task::{TaskControlBlock, TaskStatus},
+    process::ProcessControlBlock,
+    sched::{SchedPolicy, DEADLINE_RANK},
+    scheduler::Scheduler,
+    task::TaskControlBlock,
 };
 use crate::{
-    config::{APP_BASE_ADDRESS, APP_SIZE_LIMIT},
+    config::APP_SIZE_LIMIT,
+    fs::File,
     link_app::{APP_NAME, APP_NUM},
-    loader::{init_app_cx, UserStack, USER_STACK},
-    sync::UPSafeCell,
-    syscall::SyscallId,
-    timer::timer_now,
+    loader::{
+        app_base, app_context, app_mem_len, init_app_cx, load_app, KernelStack, UserStack,
+        KERNEL_STACK,
+    },
+    mm::{UserMemory, PROT_READ, PROT_WRITE},
+    sync::{futex_cancel, Condvar, DeadlockDetector, Mutex, Resource, Semaphore, UPSafeCell},
+    syscall::{
+        errno::{EAGAIN, EBADF, EINVAL, EMFILE, ENOMEM, ESRCH},
+        SyscallId,
+    },
+    timer::{
+        cancel_timer, expired_timers, next_timer_deadline, set_trigger_at, start_slice, timer_now,
+        TimerEvent,
+    },
+    trap::context::TrapContext,
+    vvar,
 };
 
+/// threads a process can have, the main one included
+pub const MAX_PROCESS_THREADS: usize = 8;
+/// threads besides the main ones, their stacks take up the kernel heap
+pub const MAX_THREADS: usize = 24;
+
 pub struct TaskManager {
     inner: UPSafeCell<TaskManagerInner>,
 }
 
+/// Apps are processes, `pid` is the app id. Threads live in `tasks` and are
+/// indexed by `tid`; the main thread of process `pid` has `tid == pid`.
 pub struct TaskManagerInner {
     infos: [TaskInfo; APP_NUM],
-    tasks: [TaskControlBlock; APP_NUM],
+    processes: [ProcessControlBlock; APP_NUM],
+    tasks: Vec<TaskControlBlock>,
     current_task: usize,
     timestamp: Duration,
+    scheduler: Box<dyn Scheduler>,
+    /// current thread is charged for its budget from here on
+    budget_since: Duration,
 }
 
 lazy_static::lazy_static! {
     pub static ref TASK_MANAGER: TaskManager = TaskManager::init();
 }
 
+/// Result of [`TaskManager::try_join`]
+pub enum JoinResult {
+    /// the thread has exited and was reaped, this is its exit code
+    Exited(i32),
+    /// the thread is still running, the caller has been queued on it
+    Running,
+    /// no such thread in current process
+    NotFound,
+    /// it is the caller itself, which would wait forever
+    Current,
+}
+
+impl TaskManagerInner {
+    /// whether thread slot `tid` can be reused: it was reaped, or its thread
+    /// was running when its whole process exited
+    fn is_free_slot(&self, tid: usize) -> bool {
+        let task = &self.tasks[tid];
+        task.task_status == TaskStatus::Uninit
+            || (task.task_status == TaskStatus::Exited && self.processes[task.pid].exited)
+    }
+
+    /// Thread `tid` of process `pid` is gone without waking up, drop it from
+    /// every queue it may wait in, so the thread that gets its tid next is
+    /// not woken in its place. Pipes re-check after a wakeup, so a stale
+    /// waiter there only costs a spurious one.
+    fn forget_waits(&mut self, pid: usize, tid: usize) {
+        cancel_timer(TimerEvent::Wakeup(tid));
+        futex_cancel(tid);
+        let process = &self.processes[pid];
+        process.mutex_list.iter().for_each(|m| m.cancel_wait(tid));
+        process
+            .semaphore_list
+            .iter()
+            .for_each(|s| s.cancel_wait(tid));
+        process.condvar_list.iter().for_each(|c| c.cancel_wait(tid));
+    }
+
+    fn current_pid(&self) -> usize {
+        self.tasks[self.current_task].pid
+    }
+
+    /// `status` of the process, derived from the status of its threads
+    fn update_process_status(&mut self, pid: usize) {
+        let tasks = &self.tasks;
+        let status = |status: TaskStatus| {
+            self.processes[pid]
+                .threads
+                .iter()
+                .any(|tid| tasks[*tid].task_status == status)
+        };
+        self.infos[pid].status = if self.processes[pid].exited {
+            TaskStatus::Exited
+        } else if status(TaskStatus::Running) {
+            TaskStatus::Running
+        } else if status(TaskStatus::Ready) {
+            TaskStatus::Ready
+        } else {
+            TaskStatus::Blocked
+        };
+    }
+
+    fn set_task_status(&mut self, tid: usize, status: TaskStatus) {
+        self.tasks[tid].task_status = status;
+        let pid = self.tasks[tid].pid;
+        if status == TaskStatus::Running && self.infos[pid].start_time.is_none() {
+            self.infos[pid].start_time = Some(timer_now());
+        }
+        self.update_process_status(pid);
+    }
+}
+
 impl TaskManager {
     fn init() -> Self {
-        let mut tasks = [TaskControlBlock::zero_init(); APP_NUM];
+        let mut tasks: Vec<_> = (0..APP_NUM)
+            .map(|_| TaskControlBlock::zero_init())
+            .collect();
 
         let mut infos = [(); APP_NUM].map(|_| TaskInfo::zero_init());
+        let mut pid = 0;
+        let processes = [(); APP_NUM].map(|_| {
+            pid += 1;
+            ProcessControlBlock::new(pid - 1)
+        });
 
         for (app_id, (task, info)) in tasks.iter_mut().zip(infos.iter_mut()).enumerate() {
+            task.pid = app_id;
             task.task_cx = TaskContext::goto_restore(init_app_cx(app_id));
             task.task_status = TaskStatus::Ready;
 
@@ -52,44 +165,51 @@ impl TaskManager {
 
         let inner = TaskManagerInner {
             tasks,
+            processes,
             infos,
             timestamp: Duration::default(),
             current_task: 0,
+            scheduler: scheduler::from_bootargs(),
+            budget_since: Duration::ZERO,
         };
         TaskManager {
             inner: unsafe { UPSafeCell::new(inner) },
         }
     }
+    /// tid of current thread
     pub fn get_current_task(&self) -> usize {
         let inner = self.inner.exclusive_access();
         inner.current_task
     }
+    pub fn get_current_pid(&self) -> usize {
+        let inner = self.inner.exclusive_access();
+        inner.current_pid()
+    }
     pub fn get_current_task_name(&self) -> &'static str {
         let inner = self.inner.exclusive_access();
-        APP_NAME[inner.current_task]
+        APP_NAME[inner.current_pid()]
     }
     pub fn get_current_task_status(&self) -> TaskStatus {
         let inner = self.inner.exclusive_access();
         inner.tasks[inner.current_task].task_status
     }
-    pub fn get_current_task_stack(&self) -> &UserStack {
-        let inner = self.inner.exclusive_access();
-        &USER_STACK[inner.current_task]
-    }
-    pub fn get_current_task_data_section(&self) -> &[u8] {
+    /// user stack of current thread
+    pub fn get_current_task_stack(&self) -> Range<*const u8> {
         let inner = self.inner.exclusive_access();
-        let get_base_i = |app_id| APP_BASE_ADDRESS + app_id * APP_SIZE_LIMIT;
-        let app_id = inner.current_task;
-        unsafe { core::slice::from_raw_parts(get_base_i(app_id) as *const u8, APP_SIZE_LIMIT) }
+        inner.tasks[inner.current_task]
+            .get_user_stack()
+            .as_ptr_range()
     }
     pub fn run_first_task(&self) -> ! {
         let mut inner = self.inner.exclusive_access();
-        let task0 = &mut inner.tasks[0];
-        task0.task_status = TaskStatus::Running;
-        let next_task_cx_ptr = &task0.task_cx as *const TaskContext;
+        inner.set_task_status(0, TaskStatus::Running);
+        let next_task_cx_ptr = &inner.tasks[0].task_cx as *const TaskContext;
+        let slice = inner.time_slice(0);
+        inner.budget_since = timer_now();
         let mut _unused = TaskContext::zero_init();
         drop(inner);
         TASK_MANAGER.set_timestamp(timer_now());
+        start_slice(slice);
 
         unsafe { switch(&mut _unused, next_task_cx_ptr) }
         panic!("unreachable in run_first_task!");
@@ -98,73 +218,612 @@ impl TaskManager {
     fn mark_current_suspended(&self) {
         let mut inner = self.inner.exclusive_access();
         let current = inner.current_task;
-        inner.tasks[current].task_status = TaskStatus::Ready;
-        inner.infos[current].status = TaskStatus::Ready;
+        inner.set_task_status(current, TaskStatus::Ready);
     }
 
-    /// Change the status of current `Running` task into `Exited`.
-    fn mark_current_exited(&self) {
+    /// Change the status of current `Running` task into `Blocked`.
+    fn mark_current_blocked(&self) {
         let mut inner = self.inner.exclusive_access();
         let current = inner.current_task;
-        inner.tasks[current].task_status = TaskStatus::Exited;
-        inner.infos[current].status = TaskStatus::Exited;
+        inner.set_task_status(current, TaskStatus::Blocked);
     }
 
-    pub fn find_next_task(&self) -> Option<usize> {
+    /// Like [`Self::mark_current_blocked`], but the wait is given up if every
+    /// task ends up blocked with no timer pending.
+    fn mark_current_blocked_interruptible(&self) {
+        let mut inner = self.inner.exclusive_access();
+        let current = inner.current_task;
+        inner.tasks[current].interruptible = true;
+        inner.set_task_status(current, TaskStatus::Blocked);
+    }
+
+    /// Nothing can run and no timer is pending: wake the interruptible
+    /// waits, which are the only ones that can give up. Returns false if
+    /// there is none.
+    fn interrupt_stuck_waits(&self) -> bool {
+        let mut inner = self.inner.exclusive_access();
+        let stuck: Vec<_> = (0..inner.tasks.len())
+            .filter(|&tid| {
+                let task = &inner.tasks[tid];
+                task.interruptible && task.task_status == TaskStatus::Blocked
+            })
+            .collect();
+        for &tid in &stuck {
+            inner.tasks[tid].interrupted = true;
+            inner.set_task_status(tid, TaskStatus::Ready);
+        }
+        !stuck.is_empty()
+    }
+
+    /// whether the interruptible wait current thread returns from was given
+    /// up, resets it for the next one
+    fn take_current_interrupted(&self) -> bool {
+        let mut inner = self.inner.exclusive_access();
+        let current = inner.current_task;
+        let task = &mut inner.tasks[current];
+        task.interruptible = false;
+        core::mem::take(&mut task.interrupted)
+    }
+
+    /// Current process and all of its threads exit.
+    fn mark_current_process_exited(&self, exit_code: i32) {
+        let pid = self.get_current_pid();
+        self.mark_exited(pid, exit_code);
+    }
+
+    /// Current thread exits. If it is the main thread, the whole process
+    /// exits with it.
+    fn mark_current_exited(&self, exit_code: i32) {
+        let inner = self.inner.exclusive_access();
+        let current = inner.current_task;
+        let pid = inner.current_pid();
+        drop(inner);
+        if current == pid {
+            self.mark_exited(pid, exit_code);
+        } else {
+            self.mark_thread_exited(current, exit_code);
+        }
+    }
+
+    /// Thread `tid` exits and waits to be reaped by `sys_waittid`. Its kernel
+    /// stack is still in use until we switch away, so it is freed on reaping.
+    fn mark_thread_exited(&self, tid: usize, exit_code: i32) {
+        let mut inner = self.inner.exclusive_access();
+        inner.drop_deadline(tid);
+        inner.drop_signal_frame(tid);
+        inner.tasks[tid].exit_code = exit_code;
+        inner.tasks[tid].ustack = None;
+        inner.set_task_status(tid, TaskStatus::Exited);
+        let join_waiters = core::mem::take(&mut inner.tasks[tid].join_waiters);
+        for waiter in join_waiters {
+            if inner.tasks[waiter].task_status == TaskStatus::Blocked {
+                inner.set_task_status(waiter, TaskStatus::Ready);
+            }
+        }
+    }
+
+    /// Process `pid` and all of its threads exit.
+    fn mark_exited(&self, pid: usize, exit_code: i32) {
+        let mut inner = self.inner.exclusive_access();
+        let current = inner.current_task;
+        let process = &mut inner.processes[pid];
+        process.exited = true;
+        process.exit_code = exit_code;
+        let threads = core::mem::take(&mut process.threads);
+        // closing a pipe end may wake other tasks up, so drop the files
+        // only after the borrow is released
+        let fd_table = core::mem::take(&mut process.fd_table);
+        for tid in threads {
+            inner.forget_waits(pid, tid);
+            inner.drop_deadline(tid);
+            inner.drop_signal_frame(tid);
+            let task = &mut inner.tasks[tid];
+            task.task_status = TaskStatus::Exited;
+            task.exit_code = exit_code;
+            task.join_waiters.clear();
+            task.ustack = None;
+            // every thread but the running one can be reaped right away, the
+            // main thread keeps its slot since `tid == pid` for it
+            if tid != current {
+                task.kstack = None;
+                if tid >= APP_NUM {
+                    task.task_status = TaskStatus::Uninit;
+                }
+            }
+        }
+        inner.update_process_status(pid);
+        drop(inner);
+        drop(fd_table);
+    }
+
+    /// Replace the program of current process by a fresh copy of its app
+    /// started with `args` and `envs`. The other threads are gone, memory,
+    /// locks and signal handlers start over, open files stay. Only the main
+    /// thread may do this, as its stacks are the ones the program starts on.
+    /// Returns argc, which the new program gets in `a0`.
+    pub fn exec_current(&self, args: &[&str], envs: &[&str]) -> Result<usize, isize> {
+        let mut inner = self.inner.exclusive_access();
+        let pid = inner.current_pid();
+        if inner.current_task != pid {
+            return Err(-EINVAL);
+        }
+        let cx = app_context(pid, args, envs)?;
+
+        let others: Vec<usize> = inner.processes[pid]
+            .threads
+            .iter()
+            .copied()
+            .filter(|tid| *tid != pid)
+            .collect();
+        for &tid in &others {
+            inner.forget_waits(pid, tid);
+            inner.drop_deadline(tid);
+            inner.tasks[tid].signal_frame = None;
+            let task = &mut inner.tasks[tid];
+            // nobody is left to join them, reap right away
+            task.task_status = TaskStatus::Uninit;
+            task.join_waiters.clear();
+            task.kstack = None;
+            task.ustack = None;
+        }
+
+        let process = &mut inner.processes[pid];
+        process.threads = vec![pid];
+        process.memory = UserMemory::new(
+            app_base(pid)..app_base(pid) + APP_SIZE_LIMIT,
+            app_mem_len(pid),
+        );
+        process.mutex_list.clear();
+        process.semaphore_list.clear();
+        process.condvar_list.clear();
+        process.deadlock_detector = DeadlockDetector::default();
+        process.signal.reset_for_exec();
+        inner.tasks[pid].signal_frame = None;
+        inner.tasks[pid].clear_child_tid = 0;
+        // only the other threads could have been joining it
+        inner.tasks[pid].join_waiters.clear();
+        inner.scheduler.reset(pid);
+        inner.update_process_status(pid);
+        drop(inner);
+
+        load_app(pid);
+        // the trap context of the main thread is always at the top of its
+        // kernel stack, on the way out of this syscall it starts the program
+        KERNEL_STACK[pid].push_context(cx);
+        Ok(args.len())
+    }
+
+    /// Change the status of a `Blocked` task into `Ready`, returns whether
+    /// it was blocked.
+    pub fn wakeup_task(&self, task_id: usize) -> bool {
+        let mut inner = self.inner.exclusive_access();
+        let blocked = inner.tasks[task_id].task_status == TaskStatus::Blocked;
+        if blocked {
+            inner.set_task_status(task_id, TaskStatus::Ready);
+        }
+        blocked
+    }
+
+    /// Create a thread in current process which starts at `entry` with `arg`
+    /// in `a0`, returns its tid. `-EAGAIN` if there are too many threads
+    /// already, `-ENOMEM` if their stacks do not fit on the heap.
+    pub fn create_thread(&self, entry: usize, arg: usize) -> Result<usize, isize> {
         let inner = self.inner.exclusive_access();
+        let threads = (APP_NUM..inner.tasks.len())
+            .filter(|tid| !inner.is_free_slot(*tid))
+            .count();
+        if inner.processes[inner.current_pid()].threads.len() >= MAX_PROCESS_THREADS
+            || threads >= MAX_THREADS
+        {
+            return Err(-EAGAIN);
+        }
+        drop(inner);
+        let kstack = KernelStack::new_boxed().ok_or(-ENOMEM)?;
+        let ustack = UserStack::new_boxed().ok_or(-ENOMEM)?;
+        let mut cx = TrapContext::init_app_context(entry, ustack.get_sp());
+        cx.x10 = arg;
+        let task_cx = TaskContext::goto_restore(kstack.push_context(cx));
+
+        let mut inner = self.inner.exclusive_access();
+        let pid = inner.current_pid();
+        let task = TaskControlBlock {
+            pid,
+            task_status: TaskStatus::Uninit,
+            task_cx,
+            kstack: Some(kstack),
+            ustack: Some(ustack),
+            exit_code: 0,
+            join_waiters: Vec::new(),
+            clear_child_tid: 0,
+            cpu_time: Duration::ZERO,
+            // inherited as on Linux, but a reservation is not shared
+            sched: match inner.tasks[inner.current_task].dl {
+                Some(_) => SchedPolicy::default(),
+                None => inner.tasks[inner.current_task].sched,
+            },
+            dl: None,
+            interruptible: false,
+            interrupted: false,
+            signal_frame: None,
+        };
+        let free_slot = (APP_NUM..inner.tasks.len()).find(|tid| inner.is_free_slot(*tid));
+        let tid = match free_slot {
+            Some(tid) => {
+                inner.tasks[tid] = task;
+                tid
+            }
+            None => {
+                inner.tasks.push(task);
+                inner.tasks.len() - 1
+            }
+        };
+        inner.processes[pid].threads.push(tid);
+        inner.scheduler.reset(tid);
+        inner.set_task_status(tid, TaskStatus::Ready);
+        Ok(tid)
+    }
+
+    /// Reap thread `tid` of current process if it has exited, otherwise queue
+    /// current thread to be woken up when it does.
+    pub fn try_join(&self, tid: usize) -> JoinResult {
+        let mut inner = self.inner.exclusive_access();
+        let current = inner.current_task;
+        let pid = inner.current_pid();
+        if tid == current {
+            return JoinResult::Current;
+        }
+        if !inner.processes[pid].threads.contains(&tid) {
+            return JoinResult::NotFound;
+        }
+        let task = &mut inner.tasks[tid];
+        if task.task_status != TaskStatus::Exited {
+            task.join_waiters.push(current);
+            return JoinResult::Running;
+        }
+        let exit_code = task.exit_code;
+        task.kstack = None;
+        if tid >= APP_NUM {
+            task.task_status = TaskStatus::Uninit;
+        }
+        inner.processes[pid].threads.retain(|t| *t != tid);
+        JoinResult::Exited(exit_code)
+    }
+
+    /// a ready task of the highest rank, the scheduler picks among
+    /// `SCHED_OTHER` ones
+    pub fn find_next_task(&self) -> Option<usize> {
+        let mut inner = self.inner.exclusive_access();
+        let inner = &mut *inner;
         let current = inner.current_task;
+        let tasks = &inner.tasks;
+        let len = tasks.len();
+
+        let ready = (current + 1..current + len + 1)
+            .map(|id| id % len)
+            .filter(|id| tasks[*id].task_status == TaskStatus::Ready);
+        let rank = ready.clone().map(|id| tasks[id].sched.rank()).max()?;
+        let mut ready = ready.filter(|id| tasks[*id].sched.rank() == rank);
+        match rank {
+            0 => inner.scheduler.pick(&mut ready, timer_now()),
+            // earliest deadline first
+            DEADLINE_RANK => ready.min_by_key(|id| tasks[*id].dl.map(|dl| dl.abs_deadline)),
+            _ => ready.next(),
+        }
+    }
+
+    fn any_blocked(&self) -> bool {
+        let inner = self.inner.exclusive_access();
+        inner
+            .tasks
+            .iter()
+            .any(|task| task.task_status == TaskStatus::Blocked)
+    }
 
-        (current + 1..current + APP_NUM + 1)
-            .map(|id| id % APP_NUM)
-            .find(|id| inner.tasks[*id].task_status == TaskStatus::Ready)
+    /// whether a task is waiting for the CPU, besides the running one
+    pub fn has_ready_task(&self) -> bool {
+        self.find_next_task().is_some()
     }
 
     pub fn run_next_task(&self) {
-        let next = self.find_next_task();
+        self.inner.exclusive_access().charge_budget(timer_now());
+        let next = loop {
+            match self.find_next_task() {
+                Some(next) => break Some(next),
+                // only a timer can make progress now
+                None if self.any_blocked() && wait_for_timer() => {}
+                // a deadlock, unless someone gives up waiting
+                None if self.interrupt_stuck_waits() => {}
+                None => break None,
+            }
+        };
         if let Some(next) = next {
             let mut inner = self.inner.exclusive_access();
             let current = inner.current_task;
 
-            let current_name = APP_NAME[current];
-            let next_name = APP_NAME[next];
+            let current_name = APP_NAME[inner.tasks[current].pid];
+            let next_name = APP_NAME[inner.tasks[next].pid];
             trace!("run next task! current:{current} {current_name} next:{next} {next_name}");
 
             inner.current_task = next;
-            inner.tasks[next].task_status = TaskStatus::Running;
+            inner.set_task_status(next, TaskStatus::Running);
 
             let current_task_cx_ptr = &mut inner.tasks[current].task_cx as *mut TaskContext;
             let next_task_cx_ptr = &inner.tasks[next].task_cx as *const TaskContext;
+            let slice = inner.time_slice(next);
+            inner.budget_since = timer_now();
             drop(inner);
+            start_slice(slice);
+            #[cfg(not(feature = "tickless"))]
+            arm_budget_timer();
 
             unsafe { switch(current_task_cx_ptr, next_task_cx_ptr) }
         } else {
-            info!("{:#?}", self.inner.exclusive_access().infos);
+            let inner = self.inner.exclusive_access();
+            info!("{:#?}", inner.infos);
+            if inner
+                .tasks
+                .iter()
+                .any(|task| task.task_status == TaskStatus::Blocked)
+            {
+                panic!("All remaining applications are blocked!")
+            }
+            drop(inner);
+            crate::profile::dump_at_shutdown();
             panic!("All applications completed!")
         }
     }
 
+    /// set the address current thread clears when it exits, returns its tid
+    pub fn set_current_clear_child_tid(&self, addr: usize) -> usize {
+        let mut inner = self.inner.exclusive_access();
+        let current = inner.current_task;
+        inner.tasks[current].clear_child_tid = addr;
+        current
+    }
+
+    pub fn take_current_clear_child_tid(&self) -> usize {
+        let mut inner = self.inner.exclusive_access();
+        let current = inner.current_task;
+        core::mem::take(&mut inner.tasks[current].clear_child_tid)
+    }
+
+    pub fn get_current_fd(&self, fd: usize) -> Option<Arc<dyn File>> {
+        let inner = self.inner.exclusive_access();
+        let fd_table = &inner.processes[inner.current_pid()].fd_table;
+        fd_table.get(fd).and_then(|file| file.clone())
+    }
+
+    /// the file `fd` of process `pid`, for `sys_pidfd_getfd`
+    pub fn get_fd(&self, pid: usize, fd: usize) -> Result<Arc<dyn File>, isize> {
+        if !self.is_live_process(pid) {
+            return Err(-ESRCH);
+        }
+        let inner = self.inner.exclusive_access();
+        let fd_table = &inner.processes[pid].fd_table;
+        fd_table.get(fd).and_then(|file| file.clone()).ok_or(-EBADF)
+    }
+
+    /// whether `pid` is a process that has not exited
+    pub fn is_live_process(&self, pid: usize) -> bool {
+        pid < APP_NUM && !self.inner.exclusive_access().processes[pid].exited
+    }
+
+    /// `-EMFILE` if current process has `MAX_FDS` files open already
+    pub fn alloc_current_fd(&self, file: Arc<dyn File>) -> Result<usize, isize> {
+        let mut inner = self.inner.exclusive_access();
+        let pid = inner.current_pid();
+        let process = &mut inner.processes[pid];
+        let fd = process.alloc_fd().ok_or(-EMFILE)?;
+        process.fd_table[fd] = Some(file);
+        Ok(fd)
+    }
+
+    /// remove `fd` from the fd table of current task, the caller should drop
+    /// the returned file without holding any borrow of the task manager
+    pub fn take_current_fd(&self, fd: usize) -> Option<Arc<dyn File>> {
+        let mut inner = self.inner.exclusive_access();
+        let pid = inner.current_pid();
+        inner.processes[pid]
+            .fd_table
+            .get_mut(fd)
+            .and_then(|file| file.take())
+    }
+
+    pub fn get_current_mutex(&self, id: usize) -> Option<Arc<Mutex>> {
+        let inner = self.inner.exclusive_access();
+        let mutex_list = &inner.processes[inner.current_pid()].mutex_list;
+        mutex_list.get(id).cloned()
+    }
+
+    pub fn add_current_mutex(&self, mutex: Arc<Mutex>) -> usize {
+        let mut inner = self.inner.exclusive_access();
+        let pid = inner.current_pid();
+        let process = &mut inner.processes[pid];
+        let id = process.mutex_list.len();
+        process.mutex_list.push(mutex);
+        process
+            .deadlock_detector
+            .add_resource(Resource::Mutex(id), 1);
+        id
+    }
+
+    pub fn get_current_semaphore(&self, id: usize) -> Option<Arc<Semaphore>> {
+        let inner = self.inner.exclusive_access();
+        let semaphore_list = &inner.processes[inner.current_pid()].semaphore_list;
+        semaphore_list.get(id).cloned()
+    }
+
+    pub fn add_current_semaphore(&self, semaphore: Arc<Semaphore>, count: usize) -> usize {
+        let mut inner = self.inner.exclusive_access();
+        let pid = inner.current_pid();
+        let process = &mut inner.processes[pid];
+        let id = process.semaphore_list.len();
+        process.semaphore_list.push(semaphore);
+        process
+            .deadlock_detector
+            .add_resource(Resource::Semaphore(id), count);
+        id
+    }
+
+    pub fn get_current_condvar(&self, id: usize) -> Option<Arc<Condvar>> {
+        let inner = self.inner.exclusive_access();
+        let condvar_list = &inner.processes[inner.current_pid()].condvar_list;
+        condvar_list.get(id).cloned()
+    }
+
+    pub fn add_current_condvar(&self, condvar: Arc<Condvar>) -> usize {
+        let mut inner = self.inner.exclusive_access();
+        let pid = inner.current_pid();
+        let condvar_list = &mut inner.processes[pid].condvar_list;
+        condvar_list.push(condvar);
+        condvar_list.len() - 1
+    }
+
+    pub fn set_current_deadlock_detect(&self, enabled: bool) {
+        let mut inner = self.inner.exclusive_access();
+        let pid = inner.current_pid();
+        inner.processes[pid].deadlock_detector.enabled = enabled;
+    }
+
+    /// Current task is about to wait for `resource`, returns `false` if that
+    /// is refused by deadlock detection.
+    pub fn request_current_resource(&self, resource: Resource) -> bool {
+        let mut inner = self.inner.exclusive_access();
+        let (pid, tid) = (inner.current_pid(), inner.current_task);
+        inner.processes[pid]
+            .deadlock_detector
+            .request(tid, resource)
+    }
+
+    pub fn grant_current_resource(&self, resource: Resource) {
+        let mut inner = self.inner.exclusive_access();
+        let (pid, tid) = (inner.current_pid(), inner.current_task);
+        inner.processes[pid].deadlock_detector.grant(tid, resource);
+    }
+
+    pub fn release_current_resource(&self, resource: Resource) {
+        let mut inner = self.inner.exclusive_access();
+        let (pid, tid) = (inner.current_pid(), inner.current_task);
+        inner.processes[pid]
+            .deadlock_detector
+            .release(tid, resource);
+    }
+
+    /// Whether the current process may access `range` with `prot`: the
+    /// stacks of its threads are read-write, the vvar page is read-only, the
+    /// rest of its memory is as [`UserMemory::prot_of`] says.
+    pub fn current_access_ok(&self, range: Range<usize>, prot: usize) -> bool {
+        let vvar = vvar::range();
+        if vvar.start <= range.start && range.end <= vvar.end {
+            return prot & !PROT_READ == 0;
+        }
+        let inner = self.inner.exclusive_access();
+        let process = &inner.processes[inner.current_pid()];
+        let in_stack = process.threads.iter().any(|tid| {
+            let ustack = inner.tasks[*tid].get_user_stack().as_ptr_range();
+            ustack.start as usize <= range.start && range.end <= ustack.end as usize
+        });
+        if in_stack {
+            return prot & !(PROT_READ | PROT_WRITE) == 0;
+        }
+        process
+            .memory
+            .prot_of(range)
+            .map_or(false, |allowed| prot & !allowed == 0)
+    }
+
+    /// run `f` on the memory of the current process
+    pub fn with_current_memory<T>(&self, f: impl FnOnce(&mut UserMemory) -> T) -> T {
+        let mut inner = self.inner.exclusive_access();
+        let pid = inner.current_pid();
+        f(&mut inner.processes[pid].memory)
+    }
+
     pub fn get_current_task_info(&self) -> TaskInfo {
         let inner = self.inner.exclusive_access();
-        inner.infos[inner.current_task]
+        inner.infos[inner.current_pid()]
+    }
+
+    /// `None` if there is no process `pid`
+    pub fn get_task_info(&self, pid: usize) -> Option<TaskInfo> {
+        let inner = self.inner.exclusive_access();
+        inner.infos.get(pid).copied()
+    }
+
+    pub fn get_current_trace_mask(&self) -> usize {
+        let inner = self.inner.exclusive_access();
+        inner.processes[inner.current_pid()].trace_mask
     }
 
-    pub fn add_current_task_info_call_times(&self, syscall_id: SyscallId) -> Option<()> {
+    /// Returns the old mask, `None` if there is no process `pid`.
+    pub fn set_trace_mask(&self, pid: usize, mask: usize) -> Option<usize> {
         let mut inner = self.inner.exclusive_access();
-        let current = inner.current_task;
-        let call = &mut inner.infos[current].call;
-        call.add(syscall_id)
+        let process = inner.processes.get_mut(pid)?;
+        Some(core::mem::replace(&mut process.trace_mask, mask))
+    }
+
+    /// `None` if there is no process `pid`
+    pub fn get_process_stat(&self, pid: usize) -> Option<ProcessStat> {
+        let inner = self.inner.exclusive_access();
+        let process = inner.processes.get(pid)?;
+        let threads = process
+            .threads
+            .iter()
+            .map(|tid| {
+                let task = &inner.tasks[*tid];
+                let ustack = task.get_user_stack().as_ptr_range();
+                (
+                    *tid,
+                    task.task_status,
+                    ustack.start as usize..ustack.end as usize,
+                )
+            })
+            .collect();
+        Some(ProcessStat {
+            threads,
+            regions: process.memory.regions().collect(),
+            open_fds: process.fd_table.iter().flatten().count(),
+            signal_pending: process.signal.pending,
+            signal_mask: process.signal.mask,
+            stopped: process.signal.frozen,
+            exit_code: process.exited.then(|| process.exit_code),
+        })
+    }
+
+    pub fn add_current_task_info_call_times(&self, syscall_id: SyscallId) {
+        let mut inner = self.inner.exclusive_access();
+        let pid = inner.current_pid();
+        inner.infos[pid].call.add(syscall_id);
     }
 
     pub fn add_current_task_user_time(&self, time: Duration) {
         let mut inner = self.inner.exclusive_access();
         let current = inner.current_task;
-        inner.infos[current].add_user_time(time);
+        let pid = inner.current_pid();
+        inner.infos[pid].add_user_time(time);
+        inner.tasks[current].cpu_time += time;
+        inner.charge_itimers(pid, time, true);
     }
 
     pub fn add_current_task_kernel_time(&self, time: Duration) {
         let mut inner = self.inner.exclusive_access();
         let current = inner.current_task;
-        inner.infos[current].add_kernel_time(time);
+        let pid = inner.current_pid();
+        inner.infos[pid].add_kernel_time(time);
+        inner.tasks[current].cpu_time += time;
+        inner.charge_itimers(pid, time, false);
+    }
+
+    /// user and kernel time of current process, all its threads together
+    pub fn get_current_process_cpu_time(&self) -> Duration {
+        let inner = self.inner.exclusive_access();
+        let info = &inner.infos[inner.current_pid()];
+        info.user_time + info.kernel_time
+    }
+
+    pub fn get_current_thread_cpu_time(&self) -> Duration {
+        let inner = self.inner.exclusive_access();
+        inner.tasks[inner.current_task].cpu_time
     }
 
     pub fn get_timestamp(&self) -> Duration {
@@ -188,12 +847,18 @@ fn mark_current_suspended() {
     TASK_MANAGER.mark_current_suspended();
 }
 
-fn mark_current_exited() {
-    TASK_MANAGER.mark_current_exited();
+fn mark_current_exited(exit_code: i32) {
+    TASK_MANAGER.mark_current_exited(exit_code);
+}
+
+pub fn exit_current_and_run_next(exit_code: i32) {
+    mark_current_exited(exit_code);
+    run_next_task();
 }
 
-pub fn exit_current_and_run_next() {
-    mark_current_exited();
+/// exit the whole process current thread belongs to
+pub fn exit_current_process_and_run_next(exit_code: i32) {
+    TASK_MANAGER.mark_current_process_exited(exit_code);
     run_next_task();
 }
 
@@ -201,3 +866,83 @@ pub fn suspend_current_and_run_next() {
     mark_current_suspended();
     run_next_task();
 }
+
+/// block current task until someone calls [`wakeup_task`] on it
+pub fn block_current_and_run_next() {
+    TASK_MANAGER.mark_current_blocked();
+    run_next_task();
+}
+
+/// Block current task like [`block_current_and_run_next`]. Returns true if
+/// the wait was given up because every task was blocked with no timer
+/// pending, in which case the caller should dequeue itself and fail.
+pub fn block_current_interruptible_and_run_next() -> bool {
+    TASK_MANAGER.mark_current_blocked_interruptible();
+    run_next_task();
+    TASK_MANAGER.take_current_interrupted()
+}
+
+pub fn wakeup_task(task_id: usize) -> bool {
+    TASK_MANAGER.wakeup_task(task_id)
+}
+
+/// act on a kernel timer that expired
+pub fn handle_timer_event(event: TimerEvent) {
+    match event {
+        TimerEvent::ITimerReal(pid) => TASK_MANAGER.itimer_real_expired(pid, timer_now()),
+        TimerEvent::Wakeup(tid) => {
+            wakeup_task(tid);
+        }
+        TimerEvent::Replenish(tid) => TASK_MANAGER.replenish(tid, timer_now()),
+    }
+}
+
+/// Nothing is ready: wait for the earliest kernel timer and act on what
+/// expired. Returns false if there is no timer to wait for.
+fn wait_for_timer() -> bool {
+    let deadline = match next_timer_deadline() {
+        Some(deadline) => deadline,
+        None => return false,
+    };
+    set_trigger_at(Some(deadline));
+    // `sie.STIE` is set, so `wfi` wakes up on the timer even though
+    // interrupts are off in the kernel
+    while timer_now() < deadline {
+        unsafe { asm!("wfi") };
+    }
+    expired_timers(timer_now())
+        .into_iter()
+        .for_each(handle_timer_event);
+    true
+}
+
+/// Ticking: interrupt at the end of the budget of current thread too, if it
+/// is a deadline one, so it does not overrun its budget until the tick.
+#[cfg(not(feature = "tickless"))]
+pub fn arm_budget_timer() {
+    let budget = TASK_MANAGER.current_budget().map(|left| timer_now() + left);
+    crate::timer::set_trigger_before_tick(budget);
+}
+
+/// Tickless: interrupt at the earliest of the end of the slice, if another
+/// task wants the CPU, the next sample of the profiler, the kernel timers,
+/// the CPU-time itimers of current process, and the end of its budget if it
+/// is a deadline thread.
+#[cfg(feature = "tickless")]
+pub fn set_next_event() {
+    let now = timer_now();
+    let slice = TASK_MANAGER
+        .has_ready_task()
+        .then(crate::timer::slice_end)
+        .flatten();
+    let sample = crate::profile::is_profiling().then(|| now + crate::timer::TIME_SLICE);
+    let itimer = TASK_MANAGER
+        .next_current_cpu_itimer()
+        .map(|left| now + left);
+    let budget = TASK_MANAGER.current_budget().map(|left| now + left);
+    let deadline = [slice, sample, itimer, budget, next_timer_deadline()]
+        .into_iter()
+        .flatten()
+        .min();
+    set_trigger_at(deadline);
+}
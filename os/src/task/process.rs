@@ -0,0 +1,88 @@
+use alloc::{sync::Arc, vec, vec::Vec};
+use core::ops::Range;
+
+use super::{itimer::ITimer, signal::SignalState, task::TaskStatus};
+use crate::{
+    config::{APP_BASE_ADDRESS, APP_SIZE_LIMIT},
+    fs::{File, Stdin, Stdout},
+    loader::app_mem_len,
+    mm::UserMemory,
+    sync::{Condvar, DeadlockDetector, Mutex, Semaphore},
+};
+
+/// how many files a process can have open, its `RLIMIT_NOFILE`
+pub const MAX_FDS: usize = 64;
+
+/// Everything the threads of one app share
+pub struct ProcessControlBlock {
+    /// at most `MAX_FDS` long
+    pub fd_table: Vec<Option<Arc<dyn File>>>,
+    /// program break and mappings in the app slot
+    pub memory: UserMemory,
+    pub signal: SignalState,
+    /// indexed by `ITIMER_*`, they survive exec
+    pub itimers: [ITimer; 3],
+    pub mutex_list: Vec<Arc<Mutex>>,
+    pub semaphore_list: Vec<Arc<Semaphore>>,
+    pub condvar_list: Vec<Arc<Condvar>>,
+    pub deadlock_detector: DeadlockDetector,
+    /// syscall classes to trace, see [`crate::syscall::trace`]
+    pub trace_mask: usize,
+    pub exit_code: i32,
+    pub exited: bool,
+    /// tids of the live (not yet reaped) threads, the main thread first
+    pub threads: Vec<usize>,
+}
+
+impl ProcessControlBlock {
+    pub fn new(pid: usize) -> Self {
+        let base = APP_BASE_ADDRESS + pid * APP_SIZE_LIMIT;
+        ProcessControlBlock {
+            fd_table: vec![
+                // 0 -> stdin
+                Some(Arc::new(Stdin)),
+                // 1 -> stdout
+                Some(Arc::new(Stdout)),
+                // 2 -> stderr
+                Some(Arc::new(Stdout)),
+            ],
+            memory: UserMemory::new(base..base + APP_SIZE_LIMIT, app_mem_len(pid)),
+            signal: SignalState::new(),
+            itimers: [ITimer::default(); 3],
+            mutex_list: Vec::new(),
+            semaphore_list: Vec::new(),
+            condvar_list: Vec::new(),
+            deadlock_detector: DeadlockDetector::default(),
+            trace_mask: 0,
+            exit_code: 0,
+            exited: false,
+            threads: vec![pid],
+        }
+    }
+
+    /// find the lowest free fd, grow the table if there is none, `None` once
+    /// `MAX_FDS` are open
+    pub fn alloc_fd(&mut self) -> Option<usize> {
+        if let Some(fd) = (0..self.fd_table.len()).find(|fd| self.fd_table[*fd].is_none()) {
+            Some(fd)
+        } else if self.fd_table.len() < MAX_FDS {
+            self.fd_table.push(None);
+            Some(self.fd_table.len() - 1)
+        } else {
+            None
+        }
+    }
+}
+
+/// What `/proc/<pid>` shows of a process besides its [`super::TaskInfo`]
+pub struct ProcessStat {
+    /// (tid, status, user stack) of each live thread
+    pub threads: Vec<(usize, TaskStatus, Range<usize>)>,
+    /// (range, prot, name) of the image, heap and mappings
+    pub regions: Vec<(Range<usize>, usize, &'static str)>,
+    pub open_fds: usize,
+    pub signal_pending: u64,
+    pub signal_mask: u64,
+    pub stopped: bool,
+    pub exit_code: Option<i32>,
+}
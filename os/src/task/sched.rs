@@ -0,0 +1,148 @@
+//! Scheduling policies of threads, for `sched_setscheduler`
+//!
+//! The ready thread of the highest rank runs: deadline threads, then
+//! real-time ones by priority, then `SCHED_OTHER` ones at priority 0. The
+//! earliest deadline goes first among deadline threads, real-time threads of
+//! the same priority take turns in tid order, the [`super::scheduler`]
+//! decides among the `SCHED_OTHER` ones.
+
+use core::{cmp::Ordering, time::Duration};
+
+use super::{task::TaskStatus, TaskManager, TaskManagerInner};
+use crate::timer::TIME_SLICE;
+
+/// the default, time-shared
+pub const SCHED_OTHER: usize = 0;
+/// real-time, runs until it blocks, yields or a higher priority is ready
+pub const SCHED_FIFO: usize = 1;
+/// real-time, like `SCHED_FIFO` but takes turns by `RR_TIME_SLICE`
+pub const SCHED_RR: usize = 2;
+/// earliest deadline first, see [`super::deadline`]
+pub const SCHED_DEADLINE: usize = 6;
+
+/// priorities of the real-time policies
+pub const MIN_RT_PRIO: usize = 1;
+pub const MAX_RT_PRIO: usize = 99;
+
+/// the rank of deadline threads, above every real-time priority
+pub const DEADLINE_RANK: usize = MAX_RT_PRIO + 1;
+
+/// the slice of `SCHED_RR`, longer than the one of `SCHED_OTHER` as Linux does
+pub const RR_TIME_SLICE: Duration = TIME_SLICE.saturating_mul(10);
+
+/// how a thread is scheduled
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SchedPolicy {
+    pub policy: usize,
+    pub priority: usize,
+}
+
+impl SchedPolicy {
+    pub const fn default() -> Self {
+        SchedPolicy {
+            policy: SCHED_OTHER,
+            priority: 0,
+        }
+    }
+
+    /// `None` if `priority` is out of the range of `policy`
+    pub fn new(policy: usize, priority: usize) -> Option<Self> {
+        let valid = match policy {
+            SCHED_OTHER => priority == 0,
+            SCHED_FIFO | SCHED_RR => (MIN_RT_PRIO..=MAX_RT_PRIO).contains(&priority),
+            _ => false,
+        };
+        valid.then_some(SchedPolicy { policy, priority })
+    }
+
+    /// Who runs first, the higher the earlier. Deadline threads come first,
+    /// then real-time ones by priority, `SCHED_OTHER` ones are 0.
+    pub fn rank(&self) -> usize {
+        match self.policy {
+            SCHED_DEADLINE => DEADLINE_RANK,
+            _ => self.priority,
+        }
+    }
+}
+
+impl TaskManagerInner {
+    /// How long thread `tid` runs before others of its priority get the CPU,
+    /// `None` for as long as it wants. The scheduler has a say for
+    /// `SCHED_OTHER`.
+    pub(super) fn time_slice(&self, tid: usize) -> Option<Duration> {
+        match self.tasks[tid].sched.policy {
+            SCHED_FIFO | SCHED_DEADLINE => None,
+            SCHED_RR => Some(RR_TIME_SLICE),
+            _ => Some(self.scheduler.time_slice(tid)),
+        }
+    }
+}
+
+impl TaskManager {
+    /// Thread `tid`, 0 for current one, `None` if there is no such thread.
+    pub(super) fn live_tid(&self, tid: usize) -> Option<usize> {
+        let inner = self.inner.exclusive_access();
+        if tid == 0 {
+            return Some(inner.current_task);
+        }
+        let task = inner.tasks.get(tid)?;
+        match task.task_status {
+            TaskStatus::Uninit | TaskStatus::Exited => None,
+            _ => Some(tid),
+        }
+    }
+
+    pub fn get_sched_policy(&self, tid: usize) -> Option<SchedPolicy> {
+        let tid = self.live_tid(tid)?;
+        Some(self.inner.exclusive_access().tasks[tid].sched)
+    }
+
+    /// Schedule thread `tid` by `sched`, `None` if there is no such thread.
+    /// Current thread starts over on a slice of the new policy; whether it
+    /// still deserves the CPU is up to [`TaskManager::is_preempted`].
+    pub fn set_sched_policy(&self, tid: usize, sched: SchedPolicy) -> Option<()> {
+        let tid = self.live_tid(tid)?;
+        let mut inner = self.inner.exclusive_access();
+        inner.drop_deadline(tid);
+        inner.tasks[tid].sched = sched;
+        if tid == inner.current_task {
+            crate::timer::start_slice(inner.time_slice(tid));
+        }
+        Some(())
+    }
+
+    /// the time slice of thread `tid`, `None` if there is no such thread
+    pub fn get_time_slice(&self, tid: usize) -> Option<Option<Duration>> {
+        let tid = self.live_tid(tid)?;
+        Some(self.inner.exclusive_access().time_slice(tid))
+    }
+
+    /// whether a ready thread outranks current one
+    pub fn is_preempted(&self) -> bool {
+        let inner = self.inner.exclusive_access();
+        let current = inner.current_task;
+        let running = &inner.tasks[current];
+        let rank = running.sched.rank();
+        inner.tasks.iter().enumerate().any(|(tid, task)| {
+            task.task_status == TaskStatus::Ready
+                && match task.sched.rank().cmp(&rank) {
+                    Ordering::Greater => true,
+                    Ordering::Less => false,
+                    Ordering::Equal if rank == DEADLINE_RANK => {
+                        task.dl.map(|dl| dl.abs_deadline) < running.dl.map(|dl| dl.abs_deadline)
+                    }
+                    Ordering::Equal if rank == 0 => inner.scheduler.preempts(tid, current),
+                    Ordering::Equal => false,
+                }
+        })
+    }
+
+    /// current thread ran through its whole time slice
+    pub fn current_slice_expired(&self) {
+        let mut inner = self.inner.exclusive_access();
+        let current = inner.current_task;
+        if inner.tasks[current].sched.policy == SCHED_OTHER {
+            inner.scheduler.slice_expired(current);
+        }
+    }
+}
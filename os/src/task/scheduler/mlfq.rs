@@ -0,0 +1,126 @@
+//! Multi-level feedback queue
+//!
+//! Threads start on level 0, the top. The ready thread of the topmost level
+//! runs and takes the CPU from threads below at once. One that uses up the
+//! quantum of its level moves a level down, one that blocks or yields before
+//! stays, so interactive threads stay on top of CPU hogs. Every boost period
+//! all threads go back to the top, so the ones at the bottom do not starve.
+//!
+//! Configured by `mlfq.quanta=10,20,40`, the quantum of each level in
+//! milliseconds, or by `mlfq.levels=3` to double `TIME_SLICE` level by
+//! level, and by `mlfq.boost=1000`, the boost period in milliseconds.
+
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use log::warn;
+
+use super::Scheduler;
+use crate::{bootargs, timer::TIME_SLICE};
+
+const DEFAULT_LEVELS: usize = 3;
+const MAX_LEVELS: usize = 8;
+const DEFAULT_BOOST_PERIOD: Duration = Duration::from_secs(1);
+
+pub struct Mlfq {
+    /// the quantum of each level, top first
+    quanta: Vec<Duration>,
+    boost_period: Duration,
+    next_boost: Duration,
+    /// level by tid, the threads past the end are on top
+    levels: Vec<usize>,
+}
+
+/// a positive number of milliseconds
+fn parse_ms(ms: &str) -> Option<Duration> {
+    ms.parse()
+        .ok()
+        .filter(|ms| *ms > 0)
+        .map(Duration::from_millis)
+}
+
+/// the value of `key` parsed by `parse`, warns if it does not parse
+fn get_arg<T>(key: &str, parse: impl FnOnce(&str) -> Option<T>) -> Option<T> {
+    let value = bootargs::get(key)?;
+    let parsed = parse(value);
+    if parsed.is_none() {
+        warn!("invalid {}={:?}", key, value);
+    }
+    parsed
+}
+
+impl Mlfq {
+    /// `quanta` holds at least one level
+    pub fn new(quanta: Vec<Duration>, boost_period: Duration) -> Self {
+        assert!(!quanta.is_empty());
+        Mlfq {
+            quanta,
+            boost_period,
+            next_boost: boost_period,
+            levels: Vec::new(),
+        }
+    }
+
+    pub fn from_bootargs() -> Self {
+        let quanta = get_arg("mlfq.quanta", |quanta| {
+            let quanta: Option<Vec<_>> = quanta.split(',').map(parse_ms).collect();
+            quanta.filter(|quanta| (1..=MAX_LEVELS).contains(&quanta.len()))
+        });
+        let quanta = quanta.unwrap_or_else(|| {
+            let levels = get_arg("mlfq.levels", |levels| {
+                levels
+                    .parse()
+                    .ok()
+                    .filter(|levels| (1..=MAX_LEVELS).contains(levels))
+            });
+            (0..levels.unwrap_or(DEFAULT_LEVELS))
+                .map(|level| TIME_SLICE * (1 << level))
+                .collect()
+        });
+        let boost_period = get_arg("mlfq.boost", parse_ms).unwrap_or(DEFAULT_BOOST_PERIOD);
+        Mlfq::new(quanta, boost_period)
+    }
+
+    fn level(&self, tid: usize) -> usize {
+        self.levels.get(tid).copied().unwrap_or(0)
+    }
+
+    fn set_level(&mut self, tid: usize, level: usize) {
+        if tid >= self.levels.len() {
+            self.levels.resize(tid + 1, 0);
+        }
+        self.levels[tid] = level;
+    }
+}
+
+impl Scheduler for Mlfq {
+    fn name(&self) -> &'static str {
+        "mlfq"
+    }
+
+    fn pick(&mut self, ready: &mut dyn Iterator<Item = usize>, now: Duration) -> Option<usize> {
+        if now >= self.next_boost {
+            self.levels.clear();
+            self.next_boost = now + self.boost_period;
+        }
+        // the first of the level, so threads of a level take turns
+        ready.min_by_key(|tid| self.level(*tid))
+    }
+
+    fn preempts(&self, tid: usize, current: usize) -> bool {
+        self.level(tid) < self.level(current)
+    }
+
+    fn time_slice(&self, tid: usize) -> Duration {
+        self.quanta[self.level(tid)]
+    }
+
+    fn slice_expired(&mut self, tid: usize) {
+        let level = (self.level(tid) + 1).min(self.quanta.len() - 1);
+        self.set_level(tid, level);
+    }
+
+    fn reset(&mut self, tid: usize) {
+        self.set_level(tid, 0);
+    }
+}
@@ -0,0 +1,51 @@
+//! How `SCHED_OTHER` threads share the CPU
+//!
+//! Real-time threads always come first, see [`super::sched`]. Among the
+//! others a [`Scheduler`] decides, picked at boot by `sched=rr` (the
+//! default) or `sched=mlfq`.
+
+mod mlfq;
+mod rr;
+
+use alloc::boxed::Box;
+use core::time::Duration;
+
+use log::{info, warn};
+
+pub use self::{mlfq::Mlfq, rr::RoundRobin};
+use crate::bootargs;
+
+pub trait Scheduler {
+    fn name(&self) -> &'static str;
+
+    /// The thread to run at `now` among `ready`, which come in turn order
+    /// after current thread, ending with it if it is ready.
+    fn pick(&mut self, ready: &mut dyn Iterator<Item = usize>, now: Duration) -> Option<usize>;
+
+    /// whether ready thread `tid` should take the CPU from `current` at once
+    fn preempts(&self, _tid: usize, _current: usize) -> bool {
+        false
+    }
+
+    fn time_slice(&self, tid: usize) -> Duration;
+
+    /// `tid` ran through its whole slice
+    fn slice_expired(&mut self, _tid: usize) {}
+
+    /// `tid` is a new thread or starts a new program
+    fn reset(&mut self, _tid: usize) {}
+}
+
+/// the scheduler of the command line
+pub fn from_bootargs() -> Box<dyn Scheduler> {
+    let scheduler: Box<dyn Scheduler> = match bootargs::get("sched") {
+        None | Some("rr") => Box::new(RoundRobin),
+        Some("mlfq") => Box::new(Mlfq::from_bootargs()),
+        Some(other) => {
+            warn!("unknown sched={:?}, using round-robin", other);
+            Box::new(RoundRobin)
+        }
+    };
+    info!("scheduler: {}", scheduler.name());
+    scheduler
+}
@@ -0,0 +1,21 @@
+use core::time::Duration;
+
+use super::Scheduler;
+use crate::timer::TIME_SLICE;
+
+/// Every thread takes its turn for `TIME_SLICE`.
+pub struct RoundRobin;
+
+impl Scheduler for RoundRobin {
+    fn name(&self) -> &'static str {
+        "round-robin"
+    }
+
+    fn pick(&mut self, ready: &mut dyn Iterator<Item = usize>, _now: Duration) -> Option<usize> {
+        ready.next()
+    }
+
+    fn time_slice(&self, _tid: usize) -> Duration {
+        TIME_SLICE
+    }
+}
@@ -0,0 +1,404 @@
+//! POSIX-style signals
+//!
+//! Every process keeps a pending set, a blocked mask and a table of actions,
+//! shared by its threads. Sets are 64-bit with signal `n` at bit `n - 1`, as
+//! the `sigset_t` of Linux.
+//! Signals are only delivered on the way back to user mode, at the end of
+//! [`crate::trap::trap_handler`]. A user handler runs on the interrupted user
+//! stack with the interrupted [`TrapContext`] saved aside in the
+//! [`SignalFrame`] of the thread it runs on; `sys_sigreturn` from that thread
+//! asks for it to be put back on the same way out. Only one user handler of a
+//! process runs at a time, with the mask of its action added to the blocked
+//! mask.
+//!
+//! A fault of the task itself, like SIGSEGV, is raised again by the faulting
+//! instruction until a handler moves on, so if it cannot be handled right
+//! away it kills the process, as on Linux.
+
+use core::arch::asm;
+
+use super::{
+    exit_current_process_and_run_next, suspend_current_and_run_next, TaskManager, TaskManagerInner,
+    TASK_MANAGER,
+};
+use crate::{link_app::APP_NUM, syscall::SyscallId, trap::context::TrapContext};
+
+pub const MAX_SIG: usize = 31;
+
+pub const SIGHUP: usize = 1;
+pub const SIGINT: usize = 2;
+pub const SIGQUIT: usize = 3;
+pub const SIGILL: usize = 4;
+pub const SIGTRAP: usize = 5;
+pub const SIGABRT: usize = 6;
+pub const SIGBUS: usize = 7;
+pub const SIGFPE: usize = 8;
+pub const SIGKILL: usize = 9;
+pub const SIGUSR1: usize = 10;
+pub const SIGSEGV: usize = 11;
+pub const SIGUSR2: usize = 12;
+pub const SIGPIPE: usize = 13;
+pub const SIGALRM: usize = 14;
+pub const SIGTERM: usize = 15;
+pub const SIGSTKFLT: usize = 16;
+pub const SIGCHLD: usize = 17;
+pub const SIGCONT: usize = 18;
+pub const SIGSTOP: usize = 19;
+pub const SIGTSTP: usize = 20;
+pub const SIGTTIN: usize = 21;
+pub const SIGTTOU: usize = 22;
+pub const SIGURG: usize = 23;
+pub const SIGXCPU: usize = 24;
+pub const SIGXFSZ: usize = 25;
+pub const SIGVTALRM: usize = 26;
+pub const SIGPROF: usize = 27;
+pub const SIGWINCH: usize = 28;
+pub const SIGIO: usize = 29;
+pub const SIGPWR: usize = 30;
+pub const SIGSYS: usize = 31;
+
+/// `handler` value of the default action
+pub const SIG_DFL: usize = 0;
+/// `handler` value that ignores the signal
+pub const SIG_IGN: usize = 1;
+
+/// `how` of `sys_sigprocmask`
+pub const SIG_BLOCK: usize = 0;
+pub const SIG_UNBLOCK: usize = 1;
+pub const SIG_SETMASK: usize = 2;
+
+/// `sa_flags`: do not block the signal while its handler runs
+pub const SA_NODEFER: usize = 0x4000_0000;
+
+/// the size of a signal set, what `sigsetsize` must be
+pub const SIGSET_SIZE: usize = core::mem::size_of::<u64>();
+
+static SIGNAL_NAME: [&str; MAX_SIG + 1] = [
+    "SIG0",
+    "SIGHUP",
+    "SIGINT",
+    "SIGQUIT",
+    "SIGILL",
+    "SIGTRAP",
+    "SIGABRT",
+    "SIGBUS",
+    "SIGFPE",
+    "SIGKILL",
+    "SIGUSR1",
+    "SIGSEGV",
+    "SIGUSR2",
+    "SIGPIPE",
+    "SIGALRM",
+    "SIGTERM",
+    "SIGSTKFLT",
+    "SIGCHLD",
+    "SIGCONT",
+    "SIGSTOP",
+    "SIGTSTP",
+    "SIGTTIN",
+    "SIGTTOU",
+    "SIGURG",
+    "SIGXCPU",
+    "SIGXFSZ",
+    "SIGVTALRM",
+    "SIGPROF",
+    "SIGWINCH",
+    "SIGIO",
+    "SIGPWR",
+    "SIGSYS",
+];
+
+pub fn signal_name(signum: usize) -> &'static str {
+    SIGNAL_NAME.get(signum).copied().unwrap_or("SIG?")
+}
+
+pub const fn sigmask(signum: usize) -> u64 {
+    1 << (signum - 1)
+}
+
+/// signals that can neither be caught, ignored nor blocked
+const UNCATCHABLE: u64 = sigmask(SIGKILL) | sigmask(SIGSTOP);
+
+/// signals whose default action is to do nothing
+const DEFAULT_IGNORED: u64 =
+    sigmask(SIGCHLD) | sigmask(SIGCONT) | sigmask(SIGURG) | sigmask(SIGWINCH);
+
+/// signals whose default action is to stop the task
+const DEFAULT_STOPPED: u64 =
+    sigmask(SIGSTOP) | sigmask(SIGTSTP) | sigmask(SIGTTIN) | sigmask(SIGTTOU);
+
+/// `struct sigaction` of `sys_sigaction`, shared with `user_lib`; riscv64
+/// Linux has no `sa_restorer`, handlers return through
+/// [`sigreturn_trampoline`]
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct SignalAction {
+    /// `SIG_DFL`, `SIG_IGN` or the address of a `fn(signum: usize)`
+    pub handler: usize,
+    /// `SA_*`, the others are accepted and ignored
+    pub flags: usize,
+    /// signals blocked while the handler runs
+    pub mask: u64,
+}
+
+impl SignalAction {
+    const fn default() -> Self {
+        Self {
+            handler: SIG_DFL,
+            flags: 0,
+            mask: 0,
+        }
+    }
+}
+
+/// Where user handlers return to, like the signal trampoline of the vDSO.
+/// Without address translation user mode can run it in place.
+#[naked]
+#[repr(align(4))]
+unsafe extern "C" fn sigreturn_trampoline() -> ! {
+    asm!(
+        "
+            li a7, {sigreturn}
+            ecall
+        ",
+        sigreturn = const { SyscallId::SigReturn as usize },
+        options(noreturn)
+    )
+}
+
+/// A user handler running on a thread, kept in its
+/// [`super::TaskControlBlock`]
+#[derive(Copy, Clone)]
+pub struct SignalFrame {
+    /// where the handler interrupted the thread
+    pub trap_cx: TrapContext,
+    /// the mask to go back to when the handler returns
+    pub saved_mask: u64,
+    /// the handler called `sys_sigreturn`, restore `trap_cx`
+    pub returning: bool,
+}
+
+pub struct SignalState {
+    pub pending: u64,
+    pub mask: u64,
+    pub actions: [SignalAction; MAX_SIG + 1],
+    /// the thread whose [`SignalFrame`] is in use
+    pub handler_tid: Option<usize>,
+    /// stopped by SIGSTOP and friends, waiting for SIGCONT
+    pub frozen: bool,
+}
+
+impl SignalState {
+    pub fn new() -> Self {
+        Self {
+            pending: 0,
+            mask: 0,
+            actions: [SignalAction::default(); MAX_SIG + 1],
+            handler_tid: None,
+            frozen: false,
+        }
+    }
+
+    /// A new program starts: caught signals go back to their default action
+    /// since the handlers are gone, ignored ones stay ignored, the mask and
+    /// pending signals are kept. The caller drops the frame of the handler.
+    pub fn reset_for_exec(&mut self) {
+        for action in self.actions.iter_mut() {
+            if action.handler != SIG_IGN {
+                *action = SignalAction::default();
+            }
+        }
+        self.handler_tid = None;
+    }
+
+    /// the lowest pending signal that is not blocked right now
+    fn next_deliverable(&self) -> Option<usize> {
+        let blocked = if self.handler_tid.is_some() {
+            // the masks of two handlers would get tangled, hold back every
+            // other signal until the running handler returns
+            !(UNCATCHABLE | sigmask(SIGCONT))
+        } else {
+            self.mask & !UNCATCHABLE
+        };
+        let deliverable = self.pending & !blocked;
+        (1..=MAX_SIG).find(|signum| deliverable & sigmask(*signum) != 0)
+    }
+}
+
+impl TaskManager {
+    /// Deliver the pending signals of current task. A user handler is set up
+    /// by rewriting `cx`; the signal that should terminate the task, if any,
+    /// is returned.
+    fn deliver_current_signals(&self, cx: &mut TrapContext) -> Option<usize> {
+        let mut inner = self.inner.exclusive_access();
+        let current = inner.current_task;
+        let pid = inner.current_pid();
+        let inner = &mut *inner;
+        let signal = &mut inner.processes[pid].signal;
+        let frame = &mut inner.tasks[current].signal_frame;
+
+        if let Some(SignalFrame {
+            trap_cx,
+            saved_mask,
+            returning: true,
+        }) = *frame
+        {
+            *frame = None;
+            signal.handler_tid = None;
+            signal.mask = saved_mask;
+            *cx = trap_cx;
+        }
+
+        while let Some(signum) = signal.next_deliverable() {
+            signal.pending &= !sigmask(signum);
+            match signum {
+                SIGKILL => return Some(SIGKILL),
+                SIGSTOP => signal.frozen = true,
+                SIGCONT => signal.frozen = false,
+                _ => {
+                    let action = signal.actions[signum];
+                    match action.handler {
+                        SIG_IGN => {}
+                        SIG_DFL if DEFAULT_IGNORED & sigmask(signum) != 0 => {}
+                        SIG_DFL if DEFAULT_STOPPED & sigmask(signum) != 0 => signal.frozen = true,
+                        SIG_DFL => return Some(signum),
+                        handler => {
+                            signal.handler_tid = Some(current);
+                            *frame = Some(SignalFrame {
+                                trap_cx: *cx,
+                                saved_mask: signal.mask,
+                                returning: false,
+                            });
+                            let mut mask = action.mask;
+                            if action.flags & SA_NODEFER == 0 {
+                                mask |= sigmask(signum);
+                            }
+                            signal.mask |= mask & !UNCATCHABLE;
+                            cx.sepc = handler;
+                            cx.x10 = signum;
+                            cx.x1 = sigreturn_trampoline as usize;
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn is_current_frozen(&self) -> bool {
+        let inner = self.inner.exclusive_access();
+        inner.processes[inner.current_pid()].signal.frozen
+    }
+
+    /// Post `signum`, raised by a fault of current thread, to its process.
+    /// Returns false, posting nothing, if it would not be handled before the
+    /// fault is hit again: a handler is running, the signal is blocked or
+    /// it is ignored.
+    fn try_force_current_signal(&self, signum: usize) -> bool {
+        let mut inner = self.inner.exclusive_access();
+        let pid = inner.current_pid();
+        let signal = &mut inner.processes[pid].signal;
+        if signal.handler_tid.is_some()
+            || signal.mask & sigmask(signum) != 0
+            || signal.actions[signum].handler == SIG_IGN
+        {
+            return false;
+        }
+        signal.pending |= sigmask(signum);
+        true
+    }
+
+    /// Post `signum` to process `pid`, `None` if there is no such live
+    /// process.
+    pub fn send_signal(&self, pid: usize, signum: usize) -> Option<()> {
+        let mut inner = self.inner.exclusive_access();
+        if pid >= APP_NUM || inner.processes[pid].exited {
+            return None;
+        }
+        if signum == SIGKILL && pid != inner.current_pid() {
+            // none of its threads is running, and a blocked one would only
+            // notice the signal once back in user mode, which may never
+            // happen, so tear it down at once
+            drop(inner);
+            println!("[kernel] Application killed by {}", signal_name(signum));
+            self.mark_exited(pid, -(signum as i32));
+        } else {
+            inner.processes[pid].signal.pending |= sigmask(signum);
+        }
+        Some(())
+    }
+
+    /// Install `action` for `signum` on current task, returns the old one.
+    pub fn set_current_sigaction(&self, signum: usize, action: SignalAction) -> SignalAction {
+        let mut inner = self.inner.exclusive_access();
+        let pid = inner.current_pid();
+        let actions = &mut inner.processes[pid].signal.actions;
+        core::mem::replace(&mut actions[signum], action)
+    }
+
+    pub fn get_current_sigaction(&self, signum: usize) -> SignalAction {
+        let inner = self.inner.exclusive_access();
+        inner.processes[inner.current_pid()].signal.actions[signum]
+    }
+
+    /// Change the blocked mask of current task, returns the old mask.
+    pub fn set_current_sigmask(&self, mask: u64) -> u64 {
+        let mut inner = self.inner.exclusive_access();
+        let pid = inner.current_pid();
+        let signal = &mut inner.processes[pid].signal;
+        core::mem::replace(&mut signal.mask, mask & !UNCATCHABLE)
+    }
+
+    pub fn get_current_sigmask(&self) -> u64 {
+        let inner = self.inner.exclusive_access();
+        inner.processes[inner.current_pid()].signal.mask
+    }
+
+    /// Ask for the trap context saved when the handler running on current
+    /// thread was set up to be restored on the way back to user mode, `None`
+    /// if no handler runs on it.
+    pub fn current_sigreturn(&self) -> Option<()> {
+        let mut inner = self.inner.exclusive_access();
+        let current = inner.current_task;
+        inner.tasks[current].signal_frame.as_mut()?.returning = true;
+        Some(())
+    }
+}
+
+impl TaskManagerInner {
+    /// Thread `tid` is gone, if a handler ran on it its process may run
+    /// another one.
+    pub(super) fn drop_signal_frame(&mut self, tid: usize) {
+        if let Some(frame) = self.tasks[tid].signal_frame.take() {
+            let signal = &mut self.processes[self.tasks[tid].pid].signal;
+            signal.handler_tid = None;
+            signal.mask = frame.saved_mask;
+        }
+    }
+}
+
+/// Raise `signum` for a fault of current thread, killing its process if the
+/// signal cannot be delivered before the faulting instruction runs again.
+pub fn force_signal(signum: usize) {
+    if !TASK_MANAGER.try_force_current_signal(signum) {
+        println!("[kernel] Application killed by {}", signal_name(signum));
+        exit_current_process_and_run_next(-(signum as i32));
+    }
+}
+
+/// called right before returning to user mode
+pub fn handle_signals(cx: &mut TrapContext) {
+    loop {
+        if let Some(signum) = TASK_MANAGER.deliver_current_signals(cx) {
+            println!("[kernel] Application killed by {}", signal_name(signum));
+            exit_current_process_and_run_next(-(signum as i32));
+            return;
+        }
+        if !TASK_MANAGER.is_current_frozen() {
+            break;
+        }
+        // keep other tasks running until someone sends SIGCONT or SIGKILL
+        suspend_current_and_run_next();
+    }
+}
@@ -1,29 +1,78 @@
+use alloc::{boxed::Box, vec::Vec};
 use core::{fmt, time::Duration};
 
-use super::TaskContext;
-use crate::syscall::{SyscallId, MAX_SYSCALL_NUM};
+use super::{deadline::DeadlineState, sched::SchedPolicy, signal::SignalFrame, TaskContext};
+use crate::{
+    loader::{KernelStack, UserStack, USER_STACK},
+    syscall::{SyscallId, MAX_SYSCALL_NUM},
+};
 
-#[derive(Copy, Clone, Debug)]
+/// A thread, the unit the scheduler deals with
 pub struct TaskControlBlock {
+    /// the process this thread belongs to
+    pub pid: usize,
     pub task_status: TaskStatus,
     pub task_cx: TaskContext,
+    /// `None` for the main thread, which runs on the static stacks of its app
+    pub kstack: Option<Box<KernelStack>>,
+    pub ustack: Option<Box<UserStack>>,
+    pub exit_code: i32,
+    /// threads blocked in `sys_waittid` on this one
+    pub join_waiters: Vec<usize>,
+    /// set by `sys_set_tid_address`, zeroed and woken as a futex when the
+    /// thread exits, 0 for none
+    pub clear_child_tid: usize,
+    /// user and kernel time of this thread alone
+    pub cpu_time: Duration,
+    pub sched: SchedPolicy,
+    /// the job of a `SCHED_DEADLINE` thread
+    pub dl: Option<DeadlineState>,
+    /// blocked in a wait that is given up once nothing else could end it
+    pub interruptible: bool,
+    /// the interruptible wait was given up rather than woken
+    pub interrupted: bool,
+    /// the user handler running on this thread, if any
+    pub signal_frame: Option<SignalFrame>,
 }
 
 impl TaskControlBlock {
     pub fn zero_init() -> Self {
         TaskControlBlock {
+            pid: 0,
             task_status: TaskStatus::Uninit,
             task_cx: TaskContext::zero_init(),
+            kstack: None,
+            ustack: None,
+            exit_code: 0,
+            join_waiters: Vec::new(),
+            clear_child_tid: 0,
+            cpu_time: Duration::ZERO,
+            sched: SchedPolicy::default(),
+            dl: None,
+            interruptible: false,
+            interrupted: false,
+            signal_frame: None,
+        }
+    }
+
+    /// the user stack this thread runs on
+    pub fn get_user_stack(&self) -> &[u8] {
+        match &self.ustack {
+            Some(ustack) => ustack.get_stack(),
+            None => USER_STACK[self.pid].get_stack(),
         }
     }
 }
 
+/// part of the `sys_get_taskinfo` ABI, keep the values stable
+#[repr(usize)]
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub enum TaskStatus {
-    Uninit,
-    Ready,
-    Running,
-    Exited,
+    Uninit = 0,
+    Ready = 1,
+    Running = 2,
+    Blocked = 3,
+    Exited = 4,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -34,6 +83,10 @@ pub struct TaskInfo {
     pub call: Call,
     pub user_time: Duration,
     pub kernel_time: Duration,
+    /// when the process was first scheduled
+    pub start_time: Option<Duration>,
+    /// jobs of its deadline threads that did not get their runtime in time
+    pub deadline_misses: usize,
 }
 
 #[derive(Clone, Copy)]
@@ -50,20 +103,24 @@ impl Call {
             }; MAX_SYSCALL_NUM],
         }
     }
-    pub fn add(&mut self, syscall_id: SyscallId) -> Option<()> {
+    /// count one more `syscall_id`, dropped if the table is full
+    pub fn add(&mut self, syscall_id: SyscallId) {
         let find = |id| self.inner.iter().position(|&x| x.id == id);
 
         if let Some(pos) = find(syscall_id) {
             self.inner[pos].times += 1;
-            Some(())
         } else if let Some(pos) = find(SyscallId::Unsupported) {
             self.inner[pos].times = 1;
             self.inner[pos].id = syscall_id;
-            Some(())
-        } else {
-            None
         }
     }
+
+    /// the syscalls made so far
+    pub fn iter(&self) -> impl Iterator<Item = &SyscallInfo> {
+        self.inner
+            .iter()
+            .take_while(|x| x.id != SyscallId::Unsupported)
+    }
 }
 
 impl fmt::Debug for Call {
@@ -93,6 +150,8 @@ impl TaskInfo {
             call: Call::zero_init(),
             user_time: Duration::default(),
             kernel_time: Duration::default(),
+            start_time: None,
+            deadline_misses: 0,
         }
     }
 
@@ -103,3 +162,72 @@ impl TaskInfo {
         self.kernel_time += time;
     }
 }
+
+pub const TASK_NAME_LEN: usize = 32;
+/// room for syscall counts in [`UserTaskInfo`], fixed by the ABI whatever
+/// the kernel keeps in [`Call`]
+pub const TASK_INFO_SYSCALLS: usize = 64;
+/// the size of the first version of [`UserTaskInfo`], up to `start_time_us`
+pub const TASK_INFO_SIZE_VER0: usize = 1112;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default)]
+pub struct SyscallCount {
+    /// the syscall number
+    pub id: usize,
+    pub times: usize,
+}
+
+/// ABI of `sys_get_taskinfo`, shared with `user_lib`. Like `struct
+/// sched_attr`, fields are only ever appended, `size` tells which are there.
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct UserTaskInfo {
+    /// set by the caller to the size of its struct, the kernel fills no more
+    /// and sets it to the size it filled
+    pub size: usize,
+    pub pid: usize,
+    pub status: TaskStatus,
+    /// app name, padded with NULs
+    pub name: [u8; TASK_NAME_LEN],
+    /// the first `syscall_num` entries of `syscalls` are valid
+    pub syscall_num: usize,
+    pub syscalls: [SyscallCount; TASK_INFO_SYSCALLS],
+    pub user_time_us: u64,
+    pub kernel_time_us: u64,
+    /// since boot, 0 if the process has never run
+    pub start_time_us: u64,
+    // `TASK_INFO_SIZE_VER0` ends here, later fields are only filled for
+    // callers whose `size` covers them
+    /// jobs of deadline threads that did not get their runtime in time
+    pub deadline_misses: usize,
+}
+
+impl From<&TaskInfo> for UserTaskInfo {
+    fn from(info: &TaskInfo) -> Self {
+        let mut name = [0; TASK_NAME_LEN];
+        let len = info.name.len().min(TASK_NAME_LEN);
+        name[..len].copy_from_slice(&info.name.as_bytes()[..len]);
+
+        let mut syscalls = [SyscallCount::default(); TASK_INFO_SYSCALLS];
+        let mut syscall_num = 0;
+        for (count, syscall) in syscalls.iter_mut().zip(info.call.iter()) {
+            count.id = syscall.id as usize;
+            count.times = syscall.times;
+            syscall_num += 1;
+        }
+
+        Self {
+            size: core::mem::size_of::<Self>(),
+            pid: info.id,
+            status: info.status,
+            name,
+            syscall_num,
+            syscalls,
+            user_time_us: info.user_time.as_micros() as u64,
+            kernel_time_us: info.kernel_time.as_micros() as u64,
+            start_time_us: info.start_time.map_or(0, |time| time.as_micros() as u64),
+            deadline_misses: info.deadline_misses,
+        }
+    }
+}
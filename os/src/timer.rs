@@ -1,30 +1,264 @@
-use core::time::Duration;
+use alloc::{collections::BTreeSet, vec::Vec};
+use core::{
+    fmt,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
 
 use riscv::register::time;
+use spin::Mutex;
 
-use crate::{config::CLOCK_FREQ, sbi::set_timer};
+use crate::{fdt::Fdt, sbi::set_timer, vvar};
 
 pub fn get_cycle() -> u64 {
     time::read() as u64
 }
 
+const NSEC_PER_SEC: u64 = 1_000_000_000;
+
+/// Take the timebase frequency from `timebase-frequency` of `/cpus` in the
+/// device tree at `dtb`, one or two cells. Without one `CLOCK_FREQ` stays.
+pub fn init(dtb: usize) {
+    let freq = unsafe { Fdt::from_addr(dtb) }
+        .and_then(|fdt| fdt.property("/cpus", "timebase-frequency"))
+        .and_then(|freq| match freq.len() {
+            4 => Some(u32::from_be_bytes(freq.try_into().unwrap()) as u64),
+            8 => Some(u64::from_be_bytes(freq.try_into().unwrap())),
+            _ => None,
+        });
+    if let Some(freq) = freq.filter(|&freq| freq != 0) {
+        vvar::set_clock_freq(freq);
+    }
+}
+
+/// The only place in the kernel `time` ticks become time, they run at the
+/// timebase frequency in the vvar page, which user space converts with too.
+pub fn ticks_to_duration(ticks: u64) -> Duration {
+    let freq = vvar::clock_freq();
+    let nsec = (ticks % freq) * NSEC_PER_SEC / freq;
+    Duration::new(ticks / freq, nsec as u32)
+}
+
+/// the first tick at or after `time`
+fn duration_to_ticks(time: Duration) -> u64 {
+    let freq = vvar::clock_freq();
+    let nsec = time.subsec_nanos() as u64;
+    time.as_secs() * freq + (nsec * freq + NSEC_PER_SEC - 1) / NSEC_PER_SEC
+}
+
+/// time since boot
 pub fn timer_now() -> Duration {
-    let time = get_cycle();
-    Duration::from_nanos(time * 100)
+    ticks_to_duration(get_cycle())
 }
 
 const TICKS_PER_SEC: usize = 100;
 
+/// how long a task runs before others get the CPU
+pub const TIME_SLICE: Duration = Duration::from_nanos(NSEC_PER_SEC / TICKS_PER_SEC as u64);
+
 /// read the `mtime` register
 pub fn get_time() -> usize {
     time::read()
 }
 
+/// when the periodic tick is due, in ticks
+static NEXT_TICK: AtomicU64 = AtomicU64::new(u64::MAX);
+
+/// the periodic tick, every `TIME_SLICE`, the slice of `SCHED_OTHER`
 pub fn set_next_trigger() {
-    set_timer(get_time() + CLOCK_FREQ / TICKS_PER_SEC);
+    let next = get_time() as u64 + vvar::clock_freq() / TICKS_PER_SEC as u64;
+    NEXT_TICK.store(next, Ordering::Relaxed);
+    set_timer(next as usize);
+}
+
+/// Ticking, interrupt at `deadline` if that comes before the next tick,
+/// otherwise at the tick.
+pub fn set_trigger_before_tick(deadline: Option<Duration>) {
+    let tick = NEXT_TICK.load(Ordering::Relaxed);
+    let deadline = deadline.map_or(u64::MAX, duration_to_ticks);
+    set_timer(tick.min(deadline) as usize);
+}
+
+/// Interrupt at `deadline`, or never for `None`. Past deadlines interrupt
+/// right away.
+pub fn set_trigger_at(deadline: Option<Duration>) {
+    set_timer(deadline.map_or(usize::MAX, |deadline| duration_to_ticks(deadline) as usize));
+}
+
+/// when the slice of the running task ends, `u64::MAX` for never
+static SLICE_END: AtomicU64 = AtomicU64::new(0);
+
+/// a task was switched to, its slice of `slice` starts now
+pub fn start_slice(slice: Option<Duration>) {
+    let end = slice.map_or(u64::MAX, |slice| duration_to_ticks(timer_now() + slice));
+    SLICE_END.store(end, Ordering::Relaxed);
+}
+
+pub fn slice_end() -> Option<Duration> {
+    match SLICE_END.load(Ordering::Relaxed) {
+        u64::MAX => None,
+        end => Some(ticks_to_duration(end)),
+    }
+}
+
+/// Whether the slice of the running task is over. Ticking, a slice that
+/// ends before the next tick is over already.
+pub fn slice_expired() -> bool {
+    let tick = match cfg!(feature = "tickless") {
+        true => Duration::ZERO,
+        false => TIME_SLICE,
+    };
+    slice_end().map_or(false, |end| timer_now() + tick >= end)
+}
+
+/// what a kernel timer does when it expires
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TimerEvent {
+    /// `ITIMER_REAL` of process `pid`
+    ITimerReal(usize),
+    /// thread `tid` is done sleeping
+    Wakeup(usize),
+    /// the next period of deadline thread `tid`
+    Replenish(usize),
+}
+
+/// Kernel timers by deadline, since boot. They are checked on every timer
+/// interrupt, so without `tickless` they are as precise as the tick.
+static TIMERS: Mutex<BTreeSet<(Duration, TimerEvent)>> = Mutex::new(BTreeSet::new());
+
+pub fn add_timer(deadline: Duration, event: TimerEvent) {
+    TIMERS.lock().insert((deadline, event));
+}
+
+pub fn cancel_timer(event: TimerEvent) {
+    TIMERS.lock().retain(|(_, e)| *e != event);
+}
+
+/// when the earliest timer is due
+pub fn next_timer_deadline() -> Option<Duration> {
+    TIMERS.lock().first().map(|(deadline, _)| *deadline)
+}
+
+/// remove the timers due at `now`, returns their events earliest first
+pub fn expired_timers(now: Duration) -> Vec<TimerEvent> {
+    let mut timers = TIMERS.lock();
+    let mut expired = Vec::new();
+    while let Some((deadline, _)) = timers.first() {
+        if *deadline > now {
+            break;
+        }
+        expired.push(timers.pop_first().unwrap().1);
+    }
+    expired
+}
+
+/// `struct timespec`
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct TimeSpec {
+    pub sec: i64,
+    pub nsec: i64,
+}
+
+/// `struct timeval`
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct TimeVal {
+    pub sec: i64,
+    pub usec: i64,
+}
+
+impl From<Duration> for TimeSpec {
+    fn from(time: Duration) -> Self {
+        TimeSpec {
+            sec: time.as_secs() as i64,
+            nsec: time.subsec_nanos() as i64,
+        }
+    }
+}
+
+impl TimeSpec {
+    /// `None` if it is negative or not normalized
+    pub fn to_duration(self) -> Option<Duration> {
+        if self.sec < 0 || !(0..NSEC_PER_SEC as i64).contains(&self.nsec) {
+            return None;
+        }
+        Some(Duration::new(self.sec as u64, self.nsec as u32))
+    }
+}
+
+impl TimeVal {
+    /// `None` if it is negative or not normalized
+    pub fn to_duration(self) -> Option<Duration> {
+        if self.sec < 0 || !(0..1_000_000).contains(&self.usec) {
+            return None;
+        }
+        Some(Duration::new(self.sec as u64, self.usec as u32 * 1000))
+    }
+}
+
+impl From<Duration> for TimeVal {
+    fn from(time: Duration) -> Self {
+        TimeVal {
+            sec: time.as_secs() as i64,
+            usec: time.subsec_micros() as i64,
+        }
+    }
+}
+
+/// the wall-clock time at `uptime`
+pub fn realtime_at(uptime: Duration) -> Duration {
+    uptime + Duration::from_nanos(vvar::boot_realtime_ns())
+}
+
+/// wall-clock time, counted from the epoch at boot until something sets it
+pub fn realtime() -> Duration {
+    realtime_at(timer_now())
+}
+
+/// Make the wall-clock time `now`, only the offset from time since boot
+/// changes. `None` if `now` is before boot.
+pub fn set_realtime(now: Duration) -> Option<()> {
+    let boot = now.checked_sub(timer_now())?;
+    vvar::set_boot_realtime_ns(boot.as_nanos() as u64);
+    Some(())
+}
+
+/// Shows a wall-clock time as ISO 8601 UTC, down to the microsecond.
+pub struct Utc(pub Duration);
+
+impl fmt::Display for Utc {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let secs = self.0.as_secs();
+        let (year, month, day) = civil_from_days(secs / 86400);
+        let secs = secs % 86400;
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:06}Z",
+            year,
+            month,
+            day,
+            secs / 3600,
+            secs / 60 % 60,
+            secs % 60,
+            self.0.subsec_micros()
+        )
+    }
 }
 
-const MSEC_PER_SEC: usize = 1000;
-pub fn get_time_ms() -> usize {
-    time::read() / (CLOCK_FREQ / MSEC_PER_SEC)
+/// year, month and day of `days` since 1970-01-01, after Howard Hinnant's
+/// `civil_from_days`
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    // shift the epoch to 0000-03-01, so leap days end a year
+    let days = days + 719468;
+    let era = days / 146097;
+    let day_of_era = days % 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = era * 400 + year_of_era + (month <= 2) as u64;
+    (year, month, day)
 }
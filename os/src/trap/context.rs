@@ -6,7 +6,7 @@ use seq_macro::seq;
 
 seq!(N in 5..=31 {
 #[repr(C)]
-#[derive(Debug, ReprOffset)]
+#[derive(Debug, ReprOffset, Copy, Clone)]
 #[roff(usize_offsets)]
 pub struct TrapContext {
     pub x1: usize,
@@ -1,5 +1,7 @@
 pub mod context;
 
+use core::sync::atomic::{AtomicUsize, Ordering};
+
 use log::{info, trace};
 use riscv::register::{
     mtvec::TrapMode,
@@ -9,10 +11,30 @@ use riscv::register::{
 
 use self::context::TrapContext;
 use crate::{
+    profile,
     stack_trace::print_stack_trace,
     syscall::{syscall, SyscallId},
-    task::{exit_current_and_run_next, suspend_current_and_run_next, TASK_MANAGER},
-    timer::{set_next_trigger, timer_now},
+    task::{
+        handle_timer_event, run_next_task,
+        signal::{force_signal, handle_signals, SIGILL, SIGSEGV},
+        suspend_current_and_run_next, TASK_MANAGER,
+    },
+    timer::{expired_timers, slice_expired, timer_now},
+};
+
+/// How many traps of each kind were taken, shown in `/proc/interrupts`
+pub struct TrapStats {
+    pub timer: AtomicUsize,
+    pub syscall: AtomicUsize,
+    pub memory_fault: AtomicUsize,
+    pub illegal_instruction: AtomicUsize,
+}
+
+pub static TRAP_STATS: TrapStats = TrapStats {
+    timer: AtomicUsize::new(0),
+    syscall: AtomicUsize::new(0),
+    memory_fault: AtomicUsize::new(0),
+    illegal_instruction: AtomicUsize::new(0),
 };
 
 pub fn init() {
@@ -49,34 +71,59 @@ pub fn trap_handler(cx: &mut TrapContext) {
 
     match scause.cause() {
         Trap::Exception(Exception::UserEnvCall) => {
+            TRAP_STATS.syscall.fetch_add(1, Ordering::Relaxed);
             cx.sepc += 4; //move to next command
 
+            // exec replaces the context, take the id first
+            let syscall_id = SyscallId::from(cx.x17);
             let start = timer_now();
-            cx.x10 = syscall(cx.x17, [cx.x10, cx.x11, cx.x12]) as usize;
+            cx.x10 = syscall(cx.x17, [cx.x10, cx.x11, cx.x12, cx.x13, cx.x14, cx.x15]) as usize;
             let end = timer_now();
-            TASK_MANAGER
-                .add_current_task_info_call_times(SyscallId::from(cx.x17))
-                .expect("Unreachable!");
+            if syscall_id != SyscallId::Unsupported {
+                TASK_MANAGER.add_current_task_info_call_times(syscall_id);
+            }
             TASK_MANAGER.add_current_task_kernel_time(end - start);
         }
 
-        Trap::Exception(Exception::StoreFault) | Trap::Exception(Exception::StorePageFault) => {
-            info!("PageFault in application, kernel killed it.");
+        Trap::Exception(Exception::StoreFault)
+        | Trap::Exception(Exception::StorePageFault)
+        | Trap::Exception(Exception::LoadFault)
+        | Trap::Exception(Exception::LoadPageFault)
+        | Trap::Exception(Exception::InstructionFault)
+        | Trap::Exception(Exception::InstructionPageFault) => {
+            TRAP_STATS.memory_fault.fetch_add(1, Ordering::Relaxed);
+            info!(
+                "PageFault in application, stval = {:#x}, deliver SIGSEGV.",
+                stval
+            );
             info!("sepc = {:#x}, {:?}", cx.sepc, taskinfo);
             unsafe { print_stack_trace(cx.x8 as *const usize) }
-            exit_current_and_run_next();
+            force_signal(SIGSEGV);
         }
         Trap::Exception(Exception::IllegalInstruction) => {
-            info!("IllegalInstruction in application, kernel killed it.");
+            TRAP_STATS
+                .illegal_instruction
+                .fetch_add(1, Ordering::Relaxed);
+            info!("IllegalInstruction in application, deliver SIGILL.");
             info!("sepc = {:#x}, {:?}", cx.sepc, taskinfo);
             unsafe { print_stack_trace(cx.x8 as *const usize) }
-            exit_current_and_run_next();
+            force_signal(SIGILL);
         }
 
         Trap::Interrupt(Interrupt::SupervisorTimer) => {
-            set_next_trigger();
-            trace!("time is up, switch to next task");
-            suspend_current_and_run_next();
+            TRAP_STATS.timer.fetch_add(1, Ordering::Relaxed);
+            profile::sample(cx);
+            expired_timers(timer_now())
+                .into_iter()
+                .for_each(handle_timer_event);
+            #[cfg(not(feature = "tickless"))]
+            crate::timer::set_next_trigger();
+            // the interrupt may be for a timer rather than the end of the slice
+            if slice_expired() {
+                trace!("time is up, switch to next task");
+                TASK_MANAGER.current_slice_expired();
+                suspend_current_and_run_next();
+            }
         }
         _ => {
             panic!(
@@ -87,6 +134,22 @@ pub fn trap_handler(cx: &mut TrapContext) {
         }
     }
 
+    // a deadline thread out of budget waits for its next period
+    if TASK_MANAGER.charge_current_budget(timer_now()) {
+        run_next_task();
+    }
+    // a task woken above may outrank current one
+    if TASK_MANAGER.is_preempted() {
+        suspend_current_and_run_next();
+    }
+    handle_signals(cx);
+    // before the timer is set again, which clears a pending tick
+    profile::sample_kernel(cx);
+
+    #[cfg(feature = "tickless")]
+    crate::task::set_next_event();
+    #[cfg(not(feature = "tickless"))]
+    crate::task::arm_budget_timer();
     TASK_MANAGER.set_timestamp(timer_now());
     //panic!("trap_handler() leak!")
 }
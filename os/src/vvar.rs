@@ -0,0 +1,69 @@
+//! The vvar page, time parameters user space reads to tell the time without
+//! a syscall
+//!
+//! Every process finds its address in `AT_VVAR` of its auxiliary vector,
+//! reads the `time` CSR itself and converts with these. There is no MMU to
+//! make the page read-only for user space, so it is only read-only by
+//! agreement; syscalls treat it as read-only.
+//!
+//! Fields change under a sequence count: it is odd while the kernel writes,
+//! so a reader retries if it was odd or changed while reading.
+
+use core::{
+    arch::asm,
+    ops::Range,
+    sync::atomic::{AtomicU32, AtomicU64, Ordering},
+};
+
+use crate::config::{CLOCK_FREQ, PAGE_SIZE};
+
+/// Layout shared with user space, keep `user_lib::time::Vvar` in sync.
+#[repr(C, align(4096))]
+pub struct Vvar {
+    pub seq: AtomicU32,
+    /// `time` ticks per second, `timebase-frequency` of the device tree
+    pub clock_freq: AtomicU64,
+    /// wall-clock time at boot, in nanoseconds since the epoch
+    pub boot_realtime_ns: AtomicU64,
+}
+
+static VVAR: Vvar = Vvar {
+    seq: AtomicU32::new(0),
+    // for when the device tree does not say
+    clock_freq: AtomicU64::new(CLOCK_FREQ as u64),
+    boot_realtime_ns: AtomicU64::new(0),
+};
+
+/// not one of Linux, which has no vvar entry
+pub const AT_VVAR: usize = 0x1000;
+
+/// what the page takes in the address space of every process
+pub fn range() -> Range<usize> {
+    let start = &VVAR as *const Vvar as usize;
+    start..start + PAGE_SIZE
+}
+
+/// let user mode read the `time` CSR, `scounteren.TM`
+pub fn init() {
+    unsafe { asm!("csrs scounteren, {}", in(reg) 1 << 1) };
+}
+
+pub fn clock_freq() -> u64 {
+    VVAR.clock_freq.load(Ordering::Acquire)
+}
+
+pub fn set_clock_freq(freq: u64) {
+    VVAR.seq.fetch_add(1, Ordering::AcqRel);
+    VVAR.clock_freq.store(freq, Ordering::Release);
+    VVAR.seq.fetch_add(1, Ordering::AcqRel);
+}
+
+pub fn boot_realtime_ns() -> u64 {
+    VVAR.boot_realtime_ns.load(Ordering::Acquire)
+}
+
+pub fn set_boot_realtime_ns(ns: u64) {
+    VVAR.seq.fetch_add(1, Ordering::AcqRel);
+    VVAR.boot_realtime_ns.store(ns, Ordering::Release);
+    VVAR.seq.fetch_add(1, Ordering::AcqRel);
+}
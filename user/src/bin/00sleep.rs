@@ -7,7 +7,7 @@ extern crate user_lib;
 use user_lib::{get_time, yield_};
 
 #[no_mangle]
-fn main() -> i32 {
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
     let current_timer = get_time();
     println!("current_time_ms {}", current_timer);
     let wait_for = current_timer + 3000;
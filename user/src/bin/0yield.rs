@@ -9,7 +9,7 @@ use user_lib::yield_;
 extern crate user_lib;
 
 #[no_mangle]
-fn main() -> i32 {
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
     println!("try yield!");
     yield_();
     println!("back to this task!");
@@ -0,0 +1,28 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    sigaction,
+    signal::{sigmask, SignalAction, SIGSEGV, SIG_BLOCK},
+    sigprocmask,
+};
+
+fn on_segv(_signum: usize) {
+    panic!("a blocked SIGSEGV should not be handled");
+}
+
+#[no_mangle]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    println!("Into Test blocked_fault, we will fault with SIGSEGV blocked...");
+    println!("Kernel should kill this application!");
+    let action = SignalAction::new(on_segv, 0);
+    assert_eq!(sigaction(SIGSEGV, Some(&action), None), 0);
+    assert_eq!(sigprocmask(SIG_BLOCK, Some(sigmask(SIGSEGV)), None), 0);
+    unsafe {
+        core::ptr::null_mut::<u8>().write_volatile(0);
+    }
+    0
+}
@@ -0,0 +1,72 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::time::Duration;
+
+use user_lib::{
+    clock_gettime,
+    errno::EINVAL,
+    get_time,
+    syscall::{sys_gettimeofday, sys_settimeofday},
+    time::{
+        TimeVal, CLOCK_MONOTONIC, CLOCK_PROCESS_CPUTIME_ID, CLOCK_REALTIME, CLOCK_THREAD_CPUTIME_ID,
+    },
+};
+
+/// keep the CPU busy for `ms` milliseconds of wall time
+fn spin(ms: usize) {
+    let start = get_time();
+    while get_time() - start < ms {}
+}
+
+#[no_mangle]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    let before = clock_gettime(CLOCK_MONOTONIC).unwrap();
+    let cpu_before = clock_gettime(CLOCK_PROCESS_CPUTIME_ID).unwrap();
+    spin(50);
+    let after = clock_gettime(CLOCK_MONOTONIC).unwrap();
+    let cpu_after = clock_gettime(CLOCK_PROCESS_CPUTIME_ID).unwrap();
+    assert!(after - before >= Duration::from_millis(50));
+    // other apps may have run in between, but we ran some of the time
+    assert!(cpu_after > cpu_before);
+    assert!(cpu_after - cpu_before <= after - before);
+
+    // a single thread is the whole process
+    let thread = clock_gettime(CLOCK_THREAD_CPUTIME_ID).unwrap();
+    assert!(thread <= clock_gettime(CLOCK_PROCESS_CPUTIME_ID).unwrap());
+    assert!(thread >= cpu_after);
+
+    let mut tv = TimeVal::default();
+    assert_eq!(sys_gettimeofday(&mut tv), 0);
+    assert!((0..1_000_000).contains(&tv.usec));
+    assert!(clock_gettime(CLOCK_REALTIME).unwrap() >= Duration::from(tv));
+
+    assert_eq!(clock_gettime(42), Err(-EINVAL));
+
+    // setting the wall clock leaves time since boot alone
+    let y2k = TimeVal {
+        sec: 946684800,
+        usec: 0,
+    };
+    let monotonic = clock_gettime(CLOCK_MONOTONIC).unwrap();
+    let saved = clock_gettime(CLOCK_REALTIME).unwrap();
+    assert_eq!(sys_settimeofday(&y2k), 0);
+    let now = clock_gettime(CLOCK_REALTIME).unwrap();
+    assert!(now >= Duration::from(y2k) && now - Duration::from(y2k) < Duration::from_secs(1));
+    assert!(clock_gettime(CLOCK_MONOTONIC).unwrap() >= monotonic);
+    let bad = TimeVal {
+        sec: 0,
+        usec: 1_000_000,
+    };
+    assert_eq!(sys_settimeofday(&bad), -EINVAL);
+    let restore = TimeVal {
+        sec: saved.as_secs() as i64,
+        usec: saved.subsec_micros() as i64,
+    };
+    assert_eq!(sys_settimeofday(&restore), 0);
+    println!("Test clock OK!");
+    0
+}
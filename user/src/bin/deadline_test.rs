@@ -0,0 +1,117 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::time::Duration;
+
+use user_lib::{
+    clock_gettime,
+    errno::{EBUSY, EINVAL},
+    exit, get_taskinfo,
+    sched::{SchedAttr, SCHED_DEADLINE, SCHED_OTHER},
+    sched_getattr, sched_getscheduler, sched_setattr, semaphore_create, semaphore_down,
+    semaphore_up,
+    task::TaskInfo,
+    thread_create,
+    time::CLOCK_MONOTONIC,
+    waittid,
+};
+
+fn now() -> Duration {
+    clock_gettime(CLOCK_MONOTONIC).unwrap()
+}
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+/// Spin until `window` is over, returns how long the caller ran in it, as
+/// far as the gaps between two readings of the clock tell, and the longest
+/// gap.
+fn spin(window: Duration) -> (Duration, Duration) {
+    let end = now() + window;
+    let mut ran = Duration::ZERO;
+    let mut longest_gap = Duration::ZERO;
+    let mut last = now();
+    while last < end {
+        let time = now();
+        let delta = time - last;
+        if delta < ms(1) {
+            ran += delta;
+        } else {
+            longest_gap = longest_gap.max(delta);
+        }
+        last = time;
+    }
+    (ran, longest_gap)
+}
+
+fn deadline_misses() -> usize {
+    let mut info = TaskInfo::new();
+    assert_eq!(get_taskinfo(&mut info), 0);
+    info.deadline_misses().unwrap()
+}
+
+/// waits for the start signal, then competes for the CPU
+fn worker(sem_id: usize) -> ! {
+    semaphore_down(sem_id);
+    spin(ms(300));
+    exit(0)
+}
+
+#[no_mangle]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    assert_eq!(sched_getattr(0).unwrap().policy, SCHED_OTHER as u32);
+
+    // runtime <= deadline <= period
+    let bad = SchedAttr::deadline(ms(20), ms(10), ms(100));
+    assert_eq!(sched_setattr(0, &bad), -EINVAL);
+    let bad = SchedAttr::deadline(ms(20), ms(100), ms(50));
+    assert_eq!(sched_setattr(0, &bad), -EINVAL);
+    let bad = SchedAttr::deadline(Duration::ZERO, ms(100), ms(100));
+    assert_eq!(sched_setattr(0, &bad), -EINVAL);
+
+    // a reservation of 20ms every 100ms: the budget is enforced, but given
+    let attr = SchedAttr::deadline(ms(20), ms(100), ms(100));
+    assert_eq!(sched_setattr(0, &attr), 0);
+    assert_eq!(sched_getscheduler(0), SCHED_DEADLINE as isize);
+    assert_eq!(sched_getattr(0), Ok(attr));
+    let (ran, longest_gap) = spin(ms(300));
+    println!("ran {:?} of 300ms at 20ms per 100ms", ran);
+    assert!(ran >= ms(40) && ran <= ms(150), "ran {:?}", ran);
+    assert!(longest_gap >= ms(50), "never throttled");
+    assert_eq!(sched_setattr(0, &SchedAttr::default()), 0);
+    assert_eq!(sched_getscheduler(0), SCHED_OTHER as isize);
+
+    // together 80% of the CPU, but both want 40ms within the same 50ms, so
+    // the one whose deadline is later misses it
+    let sem_id = semaphore_create(0) as usize;
+    let tids = [(); 2].map(|_| thread_create(worker as usize, sem_id) as usize);
+    let attr = SchedAttr::deadline(ms(40), ms(50), ms(100));
+    for tid in tids {
+        assert_eq!(sched_setattr(tid, &attr), 0);
+    }
+    // admission control: 30ms more every 100ms would be 110%
+    let attr = SchedAttr::deadline(ms(30), ms(100), ms(100));
+    assert_eq!(sched_setattr(0, &attr), -EBUSY);
+    assert_eq!(sched_getscheduler(0), SCHED_OTHER as isize);
+
+    let misses = deadline_misses();
+    for _ in tids {
+        semaphore_up(sem_id);
+    }
+    for tid in tids {
+        assert_eq!(waittid(tid), 0);
+    }
+    let misses = deadline_misses() - misses;
+    println!("{} deadline misses", misses);
+    assert!(misses >= 1);
+
+    // the workers are gone, so is their bandwidth
+    assert_eq!(sched_setattr(0, &attr), 0);
+    assert_eq!(sched_setattr(0, &SchedAttr::default()), 0);
+    println!("Test deadline OK!");
+    0
+}
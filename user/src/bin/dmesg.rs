@@ -0,0 +1,32 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    syslog, syslog_read, write, SYSLOG_ACTION_READ, SYSLOG_ACTION_READ_ALL,
+    SYSLOG_ACTION_SIZE_BUFFER, SYSLOG_ACTION_SIZE_UNREAD,
+};
+
+const STDOUT: usize = 1;
+
+/// too big for the user stack
+static mut BUFFER: [u8; 0x8000] = [0; 0x8000];
+
+#[no_mangle]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    let buffer = unsafe { &mut *core::ptr::addr_of_mut!(BUFFER) };
+    assert!(syslog(SYSLOG_ACTION_SIZE_BUFFER, 0) > 0);
+
+    let len = syslog_read(SYSLOG_ACTION_READ_ALL, buffer);
+    assert!(len >= 0);
+    write(STDOUT, &buffer[..len as usize]);
+
+    // a destructive read consumes what it returns
+    let unread = syslog(SYSLOG_ACTION_SIZE_UNREAD, 0);
+    let len = syslog_read(SYSLOG_ACTION_READ, buffer);
+    assert!(len >= 0 && len <= unread);
+    println!("Test dmesg OK!");
+    0
+}
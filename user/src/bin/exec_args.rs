@@ -0,0 +1,60 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    env::{self, AT_ENTRY, AT_PAGESZ, AT_PHDR, AT_PHENT, AT_PHNUM, AT_RANDOM},
+    errno::{ENOENT, ENOEXEC},
+    exec,
+};
+
+/// whether a `PT_LOAD` segment of the program headers the kernel passed
+/// covers `addr`
+fn loaded_at(addr: usize) -> bool {
+    const PT_LOAD: u32 = 1;
+    let phdr = env::auxv(AT_PHDR).unwrap();
+    let phent = env::auxv(AT_PHENT).unwrap();
+    (0..env::auxv(AT_PHNUM).unwrap()).any(|i| {
+        let ph = phdr + i * phent;
+        let (p_type, vaddr, memsz) = unsafe {
+            (
+                *(ph as *const u32),
+                *((ph + 0x10) as *const usize),
+                *((ph + 0x28) as *const usize),
+            )
+        };
+        p_type == PT_LOAD && (vaddr..vaddr + memsz).contains(&addr)
+    })
+}
+
+#[no_mangle]
+fn main(argc: usize, argv: &[&str]) -> i32 {
+    if argc == 1 {
+        assert_eq!(argv[0], "exec_args");
+        assert_eq!(exec("no_such_app", &[], &[]), -ENOENT);
+        // images are linked for their own slot
+        assert_eq!(exec("hello_world", &[], &[]), -ENOEXEC);
+        exec(
+            "/bin/exec_args",
+            &["exec_args", "hello", "world 42"],
+            &["FOO=bar", "EMPTY="],
+        );
+        panic!("exec returned");
+    }
+    assert_eq!(argc, 3);
+    assert_eq!(argv, ["exec_args", "hello", "world 42"]);
+    assert!(env::args().eq(argv.iter().copied()));
+    assert_eq!(env::var("FOO"), Some("bar"));
+    assert_eq!(env::var("EMPTY"), Some(""));
+    assert_eq!(env::var("PATH"), None);
+    assert_eq!(env::auxv(AT_PAGESZ), Some(4096));
+    assert!(env::auxv(AT_RANDOM).is_some());
+    // started at `e_entry`, with the program headers at hand
+    assert_eq!(env::auxv(AT_ENTRY), Some(user_lib::_start as usize));
+    assert_eq!(env::auxv(AT_PHENT), Some(0x38));
+    assert!(loaded_at(main as usize));
+    println!("Test exec OK!");
+    0
+}
@@ -1,20 +1,55 @@
 #![no_std]
 #![no_main]
 
-use core::str;
-
-use user_lib::syscall::sys_get_taskinfo;
+use user_lib::{
+    errno::{EINVAL, ESRCH},
+    get_pid_taskinfo, get_taskinfo, getpid,
+    task::{TaskInfo, TaskStatus, TASK_INFO_SIZE_VER0},
+};
 
 #[macro_use]
 extern crate user_lib;
 
+const SYSCALL_WRITE: usize = 64;
+const SYSCALL_GETPID: usize = 172;
+
 #[no_mangle]
-fn main() -> i32 {
-    let mut v = [0u8; 32];
-    let taskid = sys_get_taskinfo(&mut v);
-    let name = str::from_utf8(&v).unwrap();
-    println!("task_name: {}", name);
-    println!("task_id:   {}", taskid);
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    let mut info = TaskInfo::new();
+    assert_eq!(get_taskinfo(&mut info), 0);
+    println!("task_name: {}", info.name());
+    println!("task_id:   {}", info.pid);
+    assert_eq!(info.pid, getpid() as usize);
+    assert_eq!(info.status, TaskStatus::Running);
+    assert!(info.start_time_us > 0);
+    assert_eq!(info.deadline_misses(), Some(0));
+
+    let mut again = TaskInfo::new();
+    assert_eq!(get_taskinfo(&mut again), 0);
+    // the two println! above, and one more getpid
+    assert!(again.syscall_times(SYSCALL_WRITE) >= 2);
+    assert_eq!(again.syscall_times(SYSCALL_GETPID), 1);
+    for count in again.syscalls() {
+        println!("syscall {}: {} times", count.id, count.times);
+    }
+    println!(
+        "user {}us kernel {}us",
+        again.user_time_us, again.kernel_time_us
+    );
 
+    let mut other = TaskInfo::new();
+    assert_eq!(get_pid_taskinfo(0, &mut other), 0);
+    assert_eq!(other.pid, 0);
+    println!("pid 0 is {} ({:?})", other.name(), other.status);
+    assert_eq!(get_pid_taskinfo(usize::MAX, &mut other), -ESRCH);
+    // an older, smaller struct is filled as far as it goes
+    assert_eq!(other.size, core::mem::size_of::<TaskInfo>());
+    other.size = TASK_INFO_SIZE_VER0;
+    assert_eq!(get_pid_taskinfo(0, &mut other), 0);
+    assert_eq!(other.size, TASK_INFO_SIZE_VER0);
+    assert_eq!(other.deadline_misses(), None);
+    other.size = TASK_INFO_SIZE_VER0 - 1;
+    assert_eq!(get_pid_taskinfo(0, &mut other), -EINVAL);
+    println!("Test get_taskinfo OK!");
     0
 }
@@ -0,0 +1,99 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use user_lib::{
+    alarm,
+    errno::EINVAL,
+    get_time, sigaction,
+    signal::{SignalAction, SIGALRM, SIGPROF, SIGVTALRM},
+    syscall::{sys_getitimer, sys_setitimer},
+    time::{ITimerVal, TimeVal, ITIMER_PROF, ITIMER_REAL, ITIMER_VIRTUAL},
+    yield_,
+};
+
+static ALARMS: AtomicUsize = AtomicUsize::new(0);
+static VTALARMS: AtomicUsize = AtomicUsize::new(0);
+static PROFS: AtomicUsize = AtomicUsize::new(0);
+
+fn on_signal(signum: usize) {
+    let count = match signum {
+        SIGALRM => &ALARMS,
+        SIGVTALRM => &VTALARMS,
+        SIGPROF => &PROFS,
+        _ => panic!("unexpected signal {}", signum),
+    };
+    count.fetch_add(1, Ordering::SeqCst);
+}
+
+fn itimerval(value_ms: u64, interval_ms: u64) -> ITimerVal {
+    ITimerVal {
+        interval: Duration::from_millis(interval_ms).into(),
+        value: Duration::from_millis(value_ms).into(),
+    }
+}
+
+/// busy until `count` reaches `n`, at most `timeout_ms`
+fn spin_until(count: &AtomicUsize, n: usize, timeout_ms: usize) {
+    let start = get_time();
+    while count.load(Ordering::SeqCst) < n {
+        assert!(get_time() - start < timeout_ms, "timer did not fire");
+    }
+}
+
+#[no_mangle]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    for signum in [SIGALRM, SIGVTALRM, SIGPROF] {
+        assert_eq!(
+            sigaction(signum, Some(&SignalAction::new(on_signal, 0)), None),
+            0
+        );
+    }
+
+    // a one-shot real timer, counting down while armed
+    assert_eq!(sys_setitimer(ITIMER_REAL, &itimerval(100, 0), None), 0);
+    let mut curr = ITimerVal::default();
+    assert_eq!(sys_getitimer(ITIMER_REAL, &mut curr), 0);
+    assert!(curr.value > TimeVal::default() && Duration::from(curr.value).as_millis() <= 100);
+    let start = get_time();
+    while ALARMS.load(Ordering::SeqCst) == 0 {
+        yield_();
+    }
+    assert!(get_time() - start >= 90);
+    assert_eq!(sys_getitimer(ITIMER_REAL, &mut curr), 0);
+    assert_eq!(curr, ITimerVal::default());
+
+    // periodic timers counting time spent running
+    assert_eq!(sys_setitimer(ITIMER_VIRTUAL, &itimerval(20, 20), None), 0);
+    spin_until(&VTALARMS, 3, 5000);
+    assert_eq!(sys_setitimer(ITIMER_PROF, &itimerval(20, 0), None), 0);
+    spin_until(&PROFS, 1, 5000);
+    let mut old = ITimerVal::default();
+    assert_eq!(
+        sys_setitimer(ITIMER_VIRTUAL, &itimerval(0, 0), Some(&mut old)),
+        0
+    );
+    assert_eq!(old.interval, Duration::from_millis(20).into());
+
+    // alarm reports what was left of the previous one
+    assert_eq!(alarm(5), 0);
+    let left = alarm(0);
+    assert!((4..=5).contains(&left));
+    assert_eq!(alarm(0), 0);
+
+    let bad = ITimerVal {
+        interval: TimeVal::default(),
+        value: TimeVal { sec: 0, usec: -1 },
+    };
+    assert_eq!(sys_setitimer(ITIMER_REAL, &bad, None), -EINVAL);
+    assert_eq!(sys_getitimer(3, &mut curr), -EINVAL);
+    println!("Test itimer OK!");
+    0
+}
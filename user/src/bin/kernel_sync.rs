@@ -0,0 +1,126 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use user_lib::{
+    condvar_create, condvar_signal, condvar_wait, enable_deadlock_detect, errno::EDEADLK, exit,
+    mutex_create, mutex_lock, mutex_unlock, semaphore_create, semaphore_down, semaphore_up,
+    thread_create, waittid, yield_,
+};
+
+const PER_THREAD: usize = 200;
+
+static mut COUNTER: usize = 0;
+static MUTEX: AtomicUsize = AtomicUsize::new(0);
+static SEM: AtomicUsize = AtomicUsize::new(0);
+static CONDVAR: AtomicUsize = AtomicUsize::new(0);
+static READY: AtomicBool = AtomicBool::new(false);
+
+static FIRST: AtomicUsize = AtomicUsize::new(0);
+static SECOND: AtomicUsize = AtomicUsize::new(0);
+static HOLDS_SECOND: AtomicBool = AtomicBool::new(false);
+static REFUSED: AtomicUsize = AtomicUsize::new(0);
+
+fn adder(_arg: usize) -> ! {
+    let mutex = MUTEX.load(Ordering::SeqCst);
+    for i in 0..PER_THREAD {
+        assert_eq!(mutex_lock(mutex), 0);
+        unsafe {
+            let old = COUNTER;
+            if i % 50 == 0 {
+                yield_();
+            }
+            COUNTER = old + 1;
+        }
+        assert_eq!(mutex_unlock(mutex), 0);
+    }
+    exit(0)
+}
+
+fn consumer(_arg: usize) -> ! {
+    assert_eq!(semaphore_down(SEM.load(Ordering::SeqCst)), 0);
+    exit(0)
+}
+
+fn waiter(_arg: usize) -> ! {
+    let mutex = MUTEX.load(Ordering::SeqCst);
+    assert_eq!(mutex_lock(mutex), 0);
+    while !READY.load(Ordering::SeqCst) {
+        assert_eq!(condvar_wait(CONDVAR.load(Ordering::SeqCst), mutex), 0);
+    }
+    assert_eq!(mutex_unlock(mutex), 0);
+    exit(0)
+}
+
+/// takes the locks in the opposite order of the main thread
+fn crossing(_arg: usize) -> ! {
+    let (first, second) = (FIRST.load(Ordering::SeqCst), SECOND.load(Ordering::SeqCst));
+    assert_eq!(mutex_lock(second), 0);
+    HOLDS_SECOND.store(true, Ordering::SeqCst);
+    if mutex_lock(first) == -EDEADLK {
+        REFUSED.fetch_add(1, Ordering::SeqCst);
+    } else {
+        assert_eq!(mutex_unlock(first), 0);
+    }
+    assert_eq!(mutex_unlock(second), 0);
+    exit(0)
+}
+
+#[no_mangle]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    MUTEX.store(mutex_create() as usize, Ordering::SeqCst);
+    let tids = [0, 1, 2].map(|arg| thread_create(adder as usize, arg));
+    for tid in tids {
+        assert_eq!(waittid(tid as usize), 0);
+    }
+    assert_eq!(unsafe { COUNTER }, 3 * PER_THREAD);
+    println!("mutex OK");
+
+    SEM.store(semaphore_create(0) as usize, Ordering::SeqCst);
+    let tids = [0, 1].map(|arg| thread_create(consumer as usize, arg));
+    yield_();
+    semaphore_up(SEM.load(Ordering::SeqCst));
+    semaphore_up(SEM.load(Ordering::SeqCst));
+    for tid in tids {
+        assert_eq!(waittid(tid as usize), 0);
+    }
+    println!("semaphore OK");
+
+    CONDVAR.store(condvar_create() as usize, Ordering::SeqCst);
+    let tid = thread_create(waiter as usize, 0);
+    yield_();
+    let mutex = MUTEX.load(Ordering::SeqCst);
+    assert_eq!(mutex_lock(mutex), 0);
+    READY.store(true, Ordering::SeqCst);
+    condvar_signal(CONDVAR.load(Ordering::SeqCst));
+    assert_eq!(mutex_unlock(mutex), 0);
+    assert_eq!(waittid(tid as usize), 0);
+    println!("condvar OK");
+
+    assert_eq!(enable_deadlock_detect(true), 0);
+    let (first, second) = (mutex_create() as usize, mutex_create() as usize);
+    FIRST.store(first, Ordering::SeqCst);
+    SECOND.store(second, Ordering::SeqCst);
+    assert_eq!(mutex_lock(first), 0);
+    let tid = thread_create(crossing as usize, 0);
+    while !HOLDS_SECOND.load(Ordering::SeqCst) {
+        yield_();
+    }
+    // whichever of the two asks last is refused instead of deadlocking
+    if mutex_lock(second) == -EDEADLK {
+        REFUSED.fetch_add(1, Ordering::SeqCst);
+    } else {
+        assert_eq!(mutex_unlock(second), 0);
+    }
+    assert_eq!(mutex_unlock(first), 0);
+    assert_eq!(waittid(tid as usize), 0);
+    assert_eq!(REFUSED.load(Ordering::SeqCst), 1);
+    println!("deadlock detection OK");
+
+    println!("Test kernel_sync OK!");
+    0
+}
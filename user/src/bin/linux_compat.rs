@@ -0,0 +1,134 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close,
+    errno::{EBADF, EINVAL, ENOSYS, ENOTDIR, ENOTTY, ESRCH},
+    getpid, gettid, kill, open, pipe,
+    procfs::{field, read_pid_file},
+    read,
+    signal::{SIGUSR1, SIG_BLOCK, SIG_SETMASK},
+    sigprocmask,
+    syscall::{
+        sys_exit_group, sys_getuid, sys_ioctl, sys_openat, sys_pipe, sys_readv,
+        sys_set_tid_address, sys_uname, sys_writev, syscall6, IoVec, UtsName,
+    },
+    AT_FDCWD, O_RDONLY,
+};
+
+const TIOCGWINSZ: usize = 0x5413;
+const SYSCALL_RT_SIGACTION: usize = 134;
+const SYSCALL_RT_SIGPROCMASK: usize = 135;
+const O_CLOEXEC: usize = 0x80000;
+
+fn iovec(buf: &[u8]) -> IoVec {
+    IoVec {
+        base: buf.as_ptr() as usize,
+        len: buf.len(),
+    }
+}
+
+fn iovec_mut(buf: &mut [u8]) -> IoVec {
+    IoVec {
+        base: buf.as_mut_ptr() as usize,
+        len: buf.len(),
+    }
+}
+
+#[no_mangle]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    let mut pipe_fd = [0usize; 2];
+    assert_eq!(pipe(&mut pipe_fd), 0);
+    let (rx, tx) = (pipe_fd[0], pipe_fd[1]);
+
+    // scattered writes and reads
+    assert_eq!(
+        sys_writev(tx, &[iovec(b"hello, "), iovec(b""), iovec(b"world")]),
+        12
+    );
+    let (mut a, mut b) = ([0u8; 5], [0u8; 16]);
+    assert_eq!(sys_readv(rx, &[iovec_mut(&mut a), iovec_mut(&mut b)]), 12);
+    assert_eq!(&a, b"hello");
+    assert_eq!(&b[..7], b", world");
+
+    // only the console is a terminal
+    let mut winsize = [0u16; 4];
+    assert_eq!(sys_ioctl(1, TIOCGWINSZ, winsize.as_mut_ptr() as usize), 0);
+    assert_eq!(winsize[..2], [24, 80]);
+    assert_eq!(
+        sys_ioctl(tx, TIOCGWINSZ, winsize.as_mut_ptr() as usize),
+        -ENOTTY
+    );
+    assert_eq!(
+        sys_ioctl(99, TIOCGWINSZ, winsize.as_mut_ptr() as usize),
+        -EBADF
+    );
+
+    // relative paths are looked up in `/`
+    let fd = sys_openat(AT_FDCWD, b"proc/uptime\0", O_RDONLY, 0);
+    assert!(fd > 0);
+    assert!(read(fd as usize, &mut b) > 0);
+    close(fd as usize);
+    assert!(open("/proc/meminfo", O_RDONLY) > 0);
+    assert_eq!(sys_openat(tx as isize, b"uptime\0", O_RDONLY, 0), -ENOTDIR);
+    assert_eq!(sys_openat(99, b"uptime\0", O_RDONLY, 0), -EBADF);
+
+    let mut uts = UtsName {
+        sysname: [0; 65],
+        nodename: [0; 65],
+        release: [0; 65],
+        version: [0; 65],
+        machine: [0; 65],
+        domainname: [0; 65],
+    };
+    assert_eq!(sys_uname(&mut uts), 0);
+    assert_eq!(&uts.machine[..8], b"riscv64\0");
+    assert_eq!(sys_getuid(), 0);
+
+    // `pipe2` fills an `int[2]` and supports no flags
+    let mut fds = [-1i32; 2];
+    assert_eq!(sys_pipe(&mut fds, O_CLOEXEC), -EINVAL);
+    assert_eq!(sys_pipe(&mut fds, 0), 0);
+    assert!(fds[0] > 2 && fds[1] > 2);
+    close(fds[0] as usize);
+    close(fds[1] as usize);
+
+    // signal sets are a `u64` with signal n at bit n - 1
+    let pid = getpid() as usize;
+    let mut old = 0u64;
+    assert_eq!(sigprocmask(SIG_BLOCK, Some(1 << (SIGUSR1 - 1)), None), 0);
+    assert_eq!(sigprocmask(SIG_SETMASK, Some(0), Some(&mut old)), 0);
+    assert_eq!(old, 0x200);
+    assert_eq!(sigprocmask(SIG_BLOCK, Some(old), None), 0);
+    let mut buf = [0u8; 512];
+    let status = read_pid_file(pid, "status", &mut buf).unwrap();
+    assert_eq!(field(status, "SigBlk"), Some("0000000000000200"));
+    assert_eq!(sigprocmask(SIG_SETMASK, Some(0), None), 0);
+    // and `sigsetsize` has to say so
+    let set = 0u64;
+    let set_ptr = &set as *const u64 as usize;
+    assert_eq!(
+        syscall6(SYSCALL_RT_SIGPROCMASK, [SIG_BLOCK, set_ptr, 0, 4, 0, 0]),
+        -EINVAL
+    );
+    assert_eq!(
+        syscall6(SYSCALL_RT_SIGACTION, [SIGUSR1, 0, 0, 4, 0, 0]),
+        -EINVAL
+    );
+    assert_eq!(kill(pid, 0), 0);
+    assert_eq!(kill(pid, 65), -EINVAL);
+    assert_eq!(kill(usize::MAX, 0), -ESRCH);
+
+    let mut tid = 0u32;
+    assert_eq!(sys_set_tid_address(&mut tid), gettid());
+    assert_eq!(syscall6(4242, [0; 6]), -ENOSYS);
+
+    close(rx);
+    close(tx);
+    println!("Test linux_compat OK!");
+    sys_exit_group(0);
+    unreachable!()
+}
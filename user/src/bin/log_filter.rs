@@ -0,0 +1,17 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{errno::EINVAL, set_log_filter};
+
+#[no_mangle]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    assert_eq!(set_log_filter("warn,toyos::task=trace"), 0);
+    assert_eq!(set_log_filter("info,toyos::task=loud"), -EINVAL);
+    // back to what the kernel boots with when there is no `log=`
+    assert_eq!(set_log_filter("debug"), 0);
+    println!("Test log_filter OK!");
+    0
+}
@@ -0,0 +1,130 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use user_lib::{
+    brk, close,
+    errno::{EEXIST, EINVAL, ENOMEM},
+    mmap, mprotect, munmap, pipe, read, sbrk, write, MAP_FIXED, MAP_FIXED_NOREPLACE, MAP_PRIVATE,
+    PROT_READ, PROT_WRITE,
+};
+
+const PAGE_SIZE: usize = 4096;
+
+/// zero-initialized, so in `.bss`, which the loaded image does not contain
+static mut BSS_BUF: [u8; 2 * PAGE_SIZE] = [0; 2 * PAGE_SIZE];
+
+fn test_brk() {
+    let start = brk(0);
+    assert_eq!(sbrk(PAGE_SIZE as isize), start as isize);
+    assert_eq!(brk(0), start + PAGE_SIZE);
+    let heap = unsafe { core::slice::from_raw_parts_mut(start as *mut u8, PAGE_SIZE) };
+    assert!(heap.iter().all(|b| *b == 0));
+    heap.fill(0xaa);
+    // too far, the break stays
+    assert_eq!(sbrk(0x1000_0000), -ENOMEM);
+    assert_eq!(brk(usize::MAX), start + PAGE_SIZE);
+    assert_eq!(sbrk(-(PAGE_SIZE as isize)), (start + PAGE_SIZE) as isize);
+    assert_eq!(brk(0), start);
+}
+
+/// the heap starts after `.bss`, growing it leaves `.bss` alone and the
+/// kernel takes buffers there
+fn test_bss() {
+    let bss = unsafe { &mut *core::ptr::addr_of_mut!(BSS_BUF) };
+    assert!(brk(0) >= bss.as_ptr_range().end as usize);
+    bss.fill(0x5a);
+    let start = sbrk(4 * PAGE_SIZE as isize);
+    assert!(start > 0);
+    assert!(bss.iter().all(|b| *b == 0x5a));
+
+    let mut pipe_fd = [0usize; 2];
+    assert_eq!(pipe(&mut pipe_fd), 0);
+    assert_eq!(write(pipe_fd[1], &bss[..256]), 256);
+    bss[..256].fill(0);
+    assert_eq!(read(pipe_fd[0], &mut bss[..256]), 256);
+    assert!(bss.iter().all(|b| *b == 0x5a));
+    close(pipe_fd[0]);
+    close(pipe_fd[1]);
+    assert_eq!(brk(start as usize), start as usize);
+}
+
+fn test_mmap() {
+    let rw = PROT_READ | PROT_WRITE;
+    let addr = mmap(0, 2 * PAGE_SIZE, rw, MAP_PRIVATE);
+    assert!(addr > 0);
+    let addr = addr as usize;
+    assert_eq!(addr % PAGE_SIZE, 0);
+    let area = unsafe { core::slice::from_raw_parts_mut(addr as *mut u8, 2 * PAGE_SIZE) };
+    assert!(area.iter().all(|b| *b == 0));
+    area.fill(0x55);
+
+    assert_eq!(mmap(0, 0, rw, MAP_PRIVATE), -EINVAL);
+    assert_eq!(
+        mmap(addr + 1, PAGE_SIZE, rw, MAP_PRIVATE | MAP_FIXED),
+        -EINVAL
+    );
+    assert_eq!(
+        mmap(addr, PAGE_SIZE, rw, MAP_PRIVATE | MAP_FIXED_NOREPLACE),
+        -EEXIST
+    );
+    assert_eq!(mmap(0, 0x1000_0000, rw, MAP_PRIVATE), -ENOMEM);
+    // a second mapping does not land on the first
+    let other = mmap(0, PAGE_SIZE, rw, MAP_PRIVATE) as usize;
+    assert!(other + PAGE_SIZE <= addr || other >= addr + 2 * PAGE_SIZE);
+
+    assert_eq!(mprotect(addr, PAGE_SIZE, PROT_READ), 0);
+    assert_eq!(mprotect(addr + 1, PAGE_SIZE, PROT_READ), -EINVAL);
+    assert_eq!(
+        mprotect(brk(0) + 16 * PAGE_SIZE, PAGE_SIZE, PROT_READ),
+        -ENOMEM
+    );
+
+    // unmap the second page only, then take it back
+    assert_eq!(munmap(addr + PAGE_SIZE, PAGE_SIZE), 0);
+    assert_eq!(
+        mmap(
+            addr + PAGE_SIZE,
+            PAGE_SIZE,
+            rw,
+            MAP_PRIVATE | MAP_FIXED_NOREPLACE
+        ),
+        (addr + PAGE_SIZE) as isize
+    );
+    // the new page is zeroed, the old one untouched
+    assert_eq!(area[PAGE_SIZE], 0);
+    assert_eq!(area[0], 0x55);
+    // MAP_FIXED replaces what is there
+    assert_eq!(
+        mmap(addr, PAGE_SIZE, rw, MAP_PRIVATE | MAP_FIXED),
+        addr as isize
+    );
+    assert_eq!(area[0], 0);
+
+    assert_eq!(munmap(addr, 2 * PAGE_SIZE), 0);
+    assert_eq!(munmap(other, PAGE_SIZE), 0);
+    // unmapping nothing is fine
+    assert_eq!(munmap(addr, PAGE_SIZE), 0);
+}
+
+fn test_alloc() {
+    let start = brk(0);
+    let v: Vec<usize> = (0..0x4000).collect();
+    assert!(v.iter().enumerate().all(|(i, x)| i == *x));
+    assert!(brk(0) >= start + 0x4000 * core::mem::size_of::<usize>());
+}
+
+#[no_mangle]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    test_brk();
+    test_bss();
+    test_mmap();
+    test_alloc();
+    println!("Test mmap OK!");
+    0
+}
@@ -0,0 +1,71 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close,
+    errno::EBADF,
+    pidfd_getfd, pidfd_open,
+    procfs::{field, read_pid_file},
+    read, yield_,
+};
+
+/// the fd `pipe_producer` gets for the read end of its pipe
+const READ_END: usize = 3;
+const STREAM_LEN: usize = 4096;
+
+fn pattern(i: usize) -> u8 {
+    (i * 13 % 251) as u8
+}
+
+/// the pid of the app called `name`
+fn find_app(name: &str) -> Option<usize> {
+    let mut buf = [0u8; 512];
+    // pids are app ids, the first one that does not exist ends the list
+    for pid in 0.. {
+        let status = read_pid_file(pid, "status", &mut buf).ok()?;
+        if field(status, "Name") == Some(name) {
+            return Some(pid);
+        }
+    }
+    None
+}
+
+/// Read what `pipe_producer`, another process, writes into its pipe.
+#[no_mangle]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    let pid = find_app("pipe_producer").expect("pipe_producer is not loaded");
+    let pidfd = pidfd_open(pid);
+    assert!(pidfd >= 0);
+    // the producer may not have made its pipe yet
+    let fd = loop {
+        match pidfd_getfd(pidfd as usize, READ_END) {
+            fd if fd == -EBADF => {
+                yield_();
+            }
+            fd => break fd,
+        }
+    };
+    assert!(fd >= 0);
+    close(pidfd as usize);
+
+    let mut buffer = [0u8; 256];
+    let mut total = 0;
+    loop {
+        let len = read(fd as usize, &mut buffer);
+        assert!(len >= 0);
+        if len == 0 {
+            break;
+        }
+        for &byte in &buffer[..len as usize] {
+            assert_eq!(byte, pattern(total));
+            total += 1;
+        }
+    }
+    assert_eq!(total, STREAM_LEN);
+    close(fd as usize);
+    println!("Test pipe_consumer OK!");
+    0
+}
@@ -0,0 +1,38 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, pipe, write};
+
+/// the consumer takes the read end by this fd, through `pidfd_getfd`
+const READ_END: usize = 3;
+const STREAM_LEN: usize = 4096;
+const CHUNK_LEN: usize = 1024;
+
+fn pattern(i: usize) -> u8 {
+    (i * 13 % 251) as u8
+}
+
+/// Stream bytes into a pipe that `pipe_consumer`, another process, reads.
+#[no_mangle]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    let mut pipe_fd = [0usize; 2];
+    assert_eq!(pipe(&mut pipe_fd), 0);
+    assert_eq!(pipe_fd[0], READ_END);
+
+    // blocks until the consumer has taken the read end and drains it
+    let mut chunk = [0u8; CHUNK_LEN];
+    for start in (0..STREAM_LEN).step_by(CHUNK_LEN) {
+        for (i, byte) in chunk.iter_mut().enumerate() {
+            *byte = pattern(start + i);
+        }
+        assert_eq!(write(pipe_fd[1], &chunk), CHUNK_LEN as isize);
+    }
+    // the consumer sees EOF once its copy of the read end is all that is left
+    close(pipe_fd[0]);
+    close(pipe_fd[1]);
+    println!("Test pipe_producer OK!");
+    0
+}
@@ -0,0 +1,131 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use user_lib::{
+    close,
+    errno::{EDEADLK, EMFILE, EPIPE},
+    exit, pipe, read, sigaction,
+    signal::{SignalAction, SIGPIPE},
+    thread_create, waittid, write,
+};
+
+static STR: &str = "Hello, world!";
+
+/// more than the pipe buffer holds, so both sides have to wait on each other
+const STREAM_LEN: usize = 4096;
+const CHUNK_LEN: usize = 1024;
+/// what the pipe buffer holds
+const PIPE_BUFFER_LEN: usize = 512;
+/// files a process can have open, stdin, stdout and stderr included
+const MAX_FDS: usize = 64;
+
+static PIPE_READ_END: AtomicUsize = AtomicUsize::new(0);
+static SIGPIPES: AtomicUsize = AtomicUsize::new(0);
+
+fn on_sigpipe(signum: usize) {
+    assert_eq!(signum, SIGPIPE);
+    SIGPIPES.fetch_add(1, Ordering::SeqCst);
+}
+
+fn pattern(i: usize) -> u8 {
+    (i * 7 % 251) as u8
+}
+
+/// read the stream until EOF, checking every byte
+fn reader(_arg: usize) -> ! {
+    let fd = PIPE_READ_END.load(Ordering::SeqCst);
+    let mut buffer = [0u8; 256];
+    let mut total = 0;
+    loop {
+        let len = read(fd, &mut buffer);
+        assert!(len >= 0);
+        if len == 0 {
+            break;
+        }
+        for &byte in &buffer[..len as usize] {
+            assert_eq!(byte, pattern(total));
+            total += 1;
+        }
+    }
+    exit(total as i32)
+}
+
+#[no_mangle]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    let mut pipe_fd = [0usize; 2];
+    assert_eq!(pipe(&mut pipe_fd), 0);
+    println!("pipe read end {}, write end {}", pipe_fd[0], pipe_fd[1]);
+
+    assert_eq!(write(pipe_fd[1], STR.as_bytes()), STR.len() as isize);
+    // every write end is closed now, reads should drain the buffer then see EOF
+    close(pipe_fd[1]);
+
+    let mut buffer = [0u8; 32];
+    let len_read = read(pipe_fd[0], &mut buffer) as usize;
+    assert_eq!(core::str::from_utf8(&buffer[..len_read]).unwrap(), STR);
+    assert_eq!(read(pipe_fd[0], &mut buffer), 0);
+    close(pipe_fd[0]);
+
+    // the read end is gone, nothing can be written any more, and the writer
+    // is told by SIGPIPE, which would kill it without a handler
+    let action = SignalAction::new(on_sigpipe, 0);
+    assert_eq!(sigaction(SIGPIPE, Some(&action), None), 0);
+    assert_eq!(pipe(&mut pipe_fd), 0);
+    close(pipe_fd[0]);
+    assert_eq!(write(pipe_fd[1], STR.as_bytes()), -EPIPE);
+    assert_eq!(SIGPIPES.load(Ordering::SeqCst), 1);
+    close(pipe_fd[1]);
+
+    // a stream through a pipe between two threads, each blocking on the other
+    assert_eq!(pipe(&mut pipe_fd), 0);
+    PIPE_READ_END.store(pipe_fd[0], Ordering::SeqCst);
+    let tid = thread_create(reader as usize, 0);
+    assert!(tid > 0);
+    let mut chunk = [0u8; CHUNK_LEN];
+    for start in (0..STREAM_LEN).step_by(CHUNK_LEN) {
+        for (i, byte) in chunk.iter_mut().enumerate() {
+            *byte = pattern(start + i);
+        }
+        assert_eq!(write(pipe_fd[1], &chunk), CHUNK_LEN as isize);
+    }
+    close(pipe_fd[1]);
+    assert_eq!(waittid(tid as usize), STREAM_LEN as isize);
+    close(pipe_fd[0]);
+
+    // Nobody else can read this pipe, so the write stops once the buffer is
+    // full and every other task is blocked or done, instead of hanging.
+    assert_eq!(pipe(&mut pipe_fd), 0);
+    assert_eq!(write(pipe_fd[1], &chunk), PIPE_BUFFER_LEN as isize);
+    assert_eq!(write(pipe_fd[1], &chunk), -EDEADLK);
+    assert_eq!(read(pipe_fd[0], &mut chunk), PIPE_BUFFER_LEN as isize);
+    assert_eq!(read(pipe_fd[0], &mut chunk), -EDEADLK);
+    close(pipe_fd[0]);
+    close(pipe_fd[1]);
+
+    // the fd table is limited, a pipe that does not fit leaves nothing behind
+    let mut pipes = [[0usize; 2]; MAX_FDS / 2];
+    let mut opened = 0;
+    let ret = loop {
+        match pipe(&mut pipes[opened]) {
+            0 => opened += 1,
+            ret => break ret,
+        }
+    };
+    assert_eq!(ret, -EMFILE);
+    assert_eq!(opened, (MAX_FDS - 3) / 2);
+    for pipe_fd in &pipes[..opened] {
+        close(pipe_fd[0]);
+        close(pipe_fd[1]);
+    }
+    assert_eq!(pipe(&mut pipe_fd), 0);
+    close(pipe_fd[0]);
+    close(pipe_fd[1]);
+
+    println!("Test pipe OK!");
+    0
+}
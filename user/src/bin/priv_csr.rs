@@ -7,7 +7,7 @@ extern crate user_lib;
 use riscv::register::sstatus::{self, SPP};
 
 #[no_mangle]
-fn main() -> i32 {
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
     println!("Try to access privileged CSR in U Mode");
     println!("Kernel should kill this application!");
     unsafe {
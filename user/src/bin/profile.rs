@@ -0,0 +1,53 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    get_time, profile, profile_dump, write, PROFILE_DUMP_FOLDED, PROFILE_DUMP_TEXT, PROFILE_START,
+    PROFILE_STOP,
+};
+
+const STDOUT: usize = 1;
+const P: u64 = 998244353;
+
+static mut BUFFER: [u8; 0x4000] = [0; 0x4000];
+
+#[inline(never)]
+fn power(base: u64, mut exp: u64) -> u64 {
+    let mut result = 1;
+    while exp > 0 {
+        result = result * base % P;
+        exp -= 1;
+    }
+    result
+}
+
+#[inline(never)]
+fn busy(ms: usize) -> u64 {
+    let start = get_time();
+    let mut acc = 0;
+    while get_time() - start < ms {
+        acc ^= power(3, 10000);
+    }
+    acc
+}
+
+#[no_mangle]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    let buffer = unsafe { &mut *core::ptr::addr_of_mut!(BUFFER) };
+    assert_eq!(profile(PROFILE_START), 0);
+    println!("busy: {}", busy(300));
+    assert_eq!(profile(PROFILE_STOP), 0);
+
+    // the host turns this into a flamegraph with flamegraph.pl
+    let len = profile_dump(PROFILE_DUMP_FOLDED, buffer);
+    assert!(len > 0);
+    write(STDOUT, &buffer[..len as usize]);
+    let len = profile_dump(PROFILE_DUMP_TEXT, buffer);
+    assert!(len > 0);
+    write(STDOUT, &buffer[..len as usize]);
+    println!("Test profile OK!");
+    0
+}
@@ -0,0 +1,34 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::procfs::{field, read_pid_file};
+
+#[no_mangle]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    println!(
+        "{:>4} {:<12} {:>4} {:>10} {:>10}  {}",
+        "PID", "STATE", "THR", "USER(us)", "SYS(us)", "NAME"
+    );
+    let mut buf = [0u8; 512];
+    // pids are app ids, the first one that does not exist ends the table
+    for pid in 0.. {
+        let status = match read_pid_file(pid, "status", &mut buf) {
+            Ok(status) => status,
+            Err(_) => break,
+        };
+        let get = |key: &str| field(status, key).unwrap_or("?");
+        println!(
+            "{:>4} {:<12} {:>4} {:>10} {:>10}  {}",
+            pid,
+            get("State"),
+            get("Threads"),
+            get("UserTime"),
+            get("KernelTime"),
+            get("Name")
+        );
+    }
+    0
+}
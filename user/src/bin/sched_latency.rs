@@ -0,0 +1,58 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use user_lib::{clock_gettime, exit, sleep, thread_create, time::CLOCK_MONOTONIC, waittid};
+
+const HOGS: usize = 2;
+const ROUNDS: u32 = 10;
+const NAP: Duration = Duration::from_millis(5);
+
+static STOP: AtomicBool = AtomicBool::new(false);
+
+fn now() -> Duration {
+    clock_gettime(CLOCK_MONOTONIC).unwrap()
+}
+
+/// burn CPU without ever giving it up
+fn hog(_arg: usize) -> ! {
+    while !STOP.load(Ordering::Relaxed) {}
+    exit(0);
+    unreachable!()
+}
+
+/// How late a thread that mostly sleeps gets the CPU back next to CPU hogs.
+/// With `sched=mlfq` the hogs sink below it and it should be back within
+/// about a tick, round-robin has it wait for every other ready thread.
+#[no_mangle]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    let hogs = [(); HOGS].map(|_| thread_create(hog as usize, 0) as usize);
+    let mut total = Duration::ZERO;
+    let mut worst = Duration::ZERO;
+    for _ in 0..ROUNDS {
+        let start = now();
+        assert_eq!(sleep(NAP), 0);
+        let late = now() - start - NAP;
+        total += late;
+        worst = worst.max(late);
+    }
+    STOP.store(true, Ordering::Relaxed);
+    for tid in hogs {
+        assert_eq!(waittid(tid), 0);
+    }
+    println!(
+        "woke {:?} late on average, {:?} at worst",
+        total / ROUNDS,
+        worst
+    );
+    assert!(worst < Duration::from_secs(1));
+    println!("Test sched latency OK!");
+    0
+}
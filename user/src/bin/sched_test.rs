@@ -0,0 +1,78 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use user_lib::{
+    clock_gettime,
+    errno::{EINVAL, ESRCH},
+    exit,
+    sched::{SCHED_FIFO, SCHED_OTHER, SCHED_RR},
+    sched_getparam, sched_getscheduler, sched_rr_get_interval, sched_setscheduler,
+    syscall::{sys_sched_get_priority_max, sys_sched_get_priority_min},
+    thread_create,
+    time::CLOCK_MONOTONIC,
+    waittid, yield_,
+};
+
+static RAN: AtomicUsize = AtomicUsize::new(0);
+
+fn mark(arg: usize) -> ! {
+    RAN.store(arg, Ordering::SeqCst);
+    exit(0);
+    unreachable!()
+}
+
+/// busy for `ms` without giving up the CPU
+fn spin(ms: u64) {
+    let end = clock_gettime(CLOCK_MONOTONIC).unwrap() + Duration::from_millis(ms);
+    while clock_gettime(CLOCK_MONOTONIC).unwrap() < end {}
+}
+
+#[no_mangle]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    assert_eq!(sched_getscheduler(0), SCHED_OTHER as isize);
+    assert_eq!(sched_getparam(0), Ok(0));
+    assert_eq!(sched_rr_get_interval(0), Ok(Duration::from_millis(10)));
+    assert_eq!(sys_sched_get_priority_min(SCHED_FIFO), 1);
+    assert_eq!(sys_sched_get_priority_max(SCHED_RR), 99);
+    assert_eq!(sys_sched_get_priority_max(SCHED_OTHER), 0);
+
+    assert_eq!(sched_setscheduler(0, SCHED_FIFO, 0), -EINVAL);
+    assert_eq!(sched_setscheduler(0, SCHED_RR, 100), -EINVAL);
+    assert_eq!(sched_setscheduler(0, SCHED_OTHER, 1), -EINVAL);
+    assert_eq!(sched_setscheduler(0, 7, 0), -EINVAL);
+    assert_eq!(sched_setscheduler(100000, SCHED_OTHER, 0), -ESRCH);
+
+    assert_eq!(sched_setscheduler(0, SCHED_RR, 10), 0);
+    assert_eq!(sched_getscheduler(0), SCHED_RR as isize);
+    assert_eq!(sched_getparam(0), Ok(10));
+    assert_eq!(sched_rr_get_interval(0), Ok(Duration::from_millis(100)));
+
+    // a thread of the same FIFO priority waits until the caller yields
+    assert_eq!(sched_setscheduler(0, SCHED_FIFO, 20), 0);
+    assert_eq!(sched_rr_get_interval(0), Ok(Duration::ZERO));
+    let tid = thread_create(mark as usize, 1) as usize;
+    assert_eq!(sched_getscheduler(tid), SCHED_FIFO as isize);
+    spin(30);
+    assert_eq!(RAN.load(Ordering::SeqCst), 0);
+    yield_();
+    assert_eq!(RAN.load(Ordering::SeqCst), 1);
+    assert_eq!(waittid(tid), 0);
+
+    // a thread raised above the caller runs at once
+    let tid = thread_create(mark as usize, 2) as usize;
+    assert_eq!(sched_setscheduler(tid, SCHED_FIFO, 30), 0);
+    assert_eq!(RAN.load(Ordering::SeqCst), 2);
+    assert_eq!(waittid(tid), 0);
+
+    assert_eq!(sched_setscheduler(0, SCHED_OTHER, 0), 0);
+    println!("Test sched OK!");
+    0
+}
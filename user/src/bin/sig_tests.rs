@@ -0,0 +1,77 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use user_lib::{
+    errno::EINVAL,
+    exit, getpid, kill, sigaction,
+    signal::{sigmask, SignalAction, SIGSEGV, SIGUSR1, SIGUSR2, SIG_BLOCK, SIG_UNBLOCK},
+    sigprocmask, sigreturn, thread_create, waittid,
+};
+
+static RECEIVED: AtomicUsize = AtomicUsize::new(0);
+
+/// the handler is not running on this thread, it has nothing to return from
+fn other_thread_sigreturn(_arg: usize) -> ! {
+    exit(sigreturn() as i32)
+}
+
+fn on_usr1(signum: usize) {
+    println!("handler: got signal {}", signum);
+    // the mask of the action and the signal itself are blocked meanwhile
+    let mut mask = 0;
+    assert_eq!(sigprocmask(SIG_BLOCK, None, Some(&mut mask)), 0);
+    assert_eq!(mask, sigmask(SIGUSR1) | sigmask(SIGUSR2));
+    if RECEIVED.load(Ordering::SeqCst) == 0 {
+        let tid = thread_create(other_thread_sigreturn as usize, 0);
+        assert!(tid > 0);
+        assert_eq!(waittid(tid as usize), -EINVAL);
+    }
+    RECEIVED.fetch_add(1, Ordering::SeqCst);
+}
+
+fn on_segv(signum: usize) {
+    assert_eq!(signum, SIGSEGV);
+    println!("handler: caught SIGSEGV, exit gracefully");
+    println!("Test sig OK!");
+    exit(0);
+}
+
+#[no_mangle]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    let pid = getpid() as usize;
+
+    let action = SignalAction::new(on_usr1, sigmask(SIGUSR2));
+    assert_eq!(sigaction(SIGUSR1, Some(&action), None), 0);
+    assert_eq!(kill(pid, SIGUSR1), 0);
+    assert_eq!(RECEIVED.load(Ordering::SeqCst), 1);
+    // and unblocked again once the handler returns
+    let mut mask = 0;
+    assert_eq!(sigprocmask(SIG_BLOCK, None, Some(&mut mask)), 0);
+    assert_eq!(mask, 0);
+    println!("signal handler returned");
+    // nor is there one to return from now
+    assert_eq!(sigreturn(), -EINVAL);
+
+    // a blocked signal stays pending until it is unblocked
+    assert_eq!(sigprocmask(SIG_BLOCK, Some(sigmask(SIGUSR1)), None), 0);
+    assert_eq!(kill(pid, SIGUSR1), 0);
+    assert_eq!(RECEIVED.load(Ordering::SeqCst), 1);
+    assert_eq!(sigprocmask(SIG_UNBLOCK, Some(sigmask(SIGUSR1)), None), 0);
+    assert_eq!(RECEIVED.load(Ordering::SeqCst), 2);
+    let mut mask = 0;
+    assert_eq!(sigprocmask(SIG_BLOCK, None, Some(&mut mask)), 0);
+    assert_eq!(mask, 0);
+    println!("blocked signal delivered after unblock");
+
+    let action = SignalAction::new(on_segv, 0);
+    assert_eq!(sigaction(SIGSEGV, Some(&action), None), 0);
+    unsafe {
+        core::ptr::null_mut::<u8>().write_volatile(0);
+    }
+    panic!("should not come back from SIGSEGV handler");
+}
@@ -0,0 +1,54 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::time::Duration;
+
+use user_lib::{
+    clock_gettime,
+    errno::EINVAL,
+    exit, sleep,
+    syscall::sys_nanosleep,
+    thread_create,
+    time::{TimeSpec, CLOCK_MONOTONIC},
+    waittid,
+};
+
+fn now() -> Duration {
+    clock_gettime(CLOCK_MONOTONIC).unwrap()
+}
+
+fn sleeper(ms: usize) -> ! {
+    let start = now();
+    assert_eq!(sleep(Duration::from_millis(ms as u64)), 0);
+    assert!(now() - start >= Duration::from_millis(ms as u64));
+    exit(ms as i32);
+    unreachable!()
+}
+
+#[no_mangle]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    let start = now();
+    assert_eq!(sleep(Duration::from_millis(50)), 0);
+    let slept = now() - start;
+    assert!(slept >= Duration::from_millis(50), "woke after {:?}", slept);
+    println!("slept {:?} for 50ms", slept);
+
+    let bad = TimeSpec {
+        sec: 0,
+        nsec: 1_000_000_000,
+    };
+    assert_eq!(sys_nanosleep(&bad), -EINVAL);
+    let bad = TimeSpec { sec: -1, nsec: 0 };
+    assert_eq!(sys_nanosleep(&bad), -EINVAL);
+
+    // the threads sleep at the same time, each on its own timer
+    let tids = [30, 10, 20].map(|ms| thread_create(sleeper as usize, ms) as usize);
+    for (tid, ms) in tids.into_iter().zip([30, 10, 20]) {
+        assert_eq!(waittid(tid), ms);
+    }
+    println!("Test nanosleep OK!");
+    0
+}
@@ -0,0 +1,32 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, errno::EINVAL, getpid, open, pipe, read, trace, write, O_RDONLY, TRACE_ALL, TRACE_FS,
+};
+
+#[no_mangle]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    let pid = getpid() as usize;
+    assert_eq!(trace(pid, TRACE_FS), 0);
+    // only these show up in the kernel log
+    let mut pipe_fd = [0usize; 2];
+    pipe(&mut pipe_fd);
+    write(pipe_fd[1], b"traced\n");
+    let mut buf = [0u8; 16];
+    read(pipe_fd[0], &mut buf);
+    close(pipe_fd[0]);
+    close(pipe_fd[1]);
+    open("/no/such/file", O_RDONLY);
+    getpid();
+
+    assert_eq!(trace(pid, TRACE_ALL), TRACE_FS as isize);
+    getpid();
+    assert_eq!(trace(pid, 0), TRACE_ALL as isize);
+    assert_eq!(trace(usize::MAX, 0), -EINVAL);
+    println!("Test strace OK!");
+    0
+}
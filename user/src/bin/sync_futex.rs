@@ -0,0 +1,122 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit,
+    sync::{Condvar, Mutex, RwLock, Semaphore},
+    thread_create, waittid, yield_,
+};
+
+const THREAD_NUM: usize = 4;
+const PER_THREAD: usize = 1000;
+
+static COUNTER: Mutex<usize> = Mutex::new(0);
+static SLOTS: Semaphore = Semaphore::new(0);
+static READY: Mutex<bool> = Mutex::new(false);
+static READY_CV: Condvar = Condvar::new();
+static TABLE: RwLock<[usize; 4]> = RwLock::new([0; 4]);
+
+fn spawn_all(entry: fn(usize) -> !) {
+    let tids: [isize; THREAD_NUM] = [0, 1, 2, 3].map(|arg| thread_create(entry as usize, arg));
+    for tid in tids {
+        assert!(tid > 0);
+        assert_eq!(waittid(tid as usize), 0);
+    }
+}
+
+fn adder(_arg: usize) -> ! {
+    for i in 0..PER_THREAD {
+        let mut counter = COUNTER.lock();
+        let old = *counter;
+        if i % 100 == 0 {
+            // get preempted while holding the lock
+            yield_();
+        }
+        *counter = old + 1;
+    }
+    exit(0)
+}
+
+fn consumer(_arg: usize) -> ! {
+    SLOTS.down();
+    exit(0)
+}
+
+fn producer(_arg: usize) -> ! {
+    for _ in 0..THREAD_NUM {
+        SLOTS.up();
+    }
+    exit(0)
+}
+
+fn waiter(_arg: usize) -> ! {
+    let mut ready = READY.lock();
+    while !*ready {
+        ready = READY_CV.wait(ready);
+    }
+    drop(ready);
+    exit(0)
+}
+
+fn notifier(_arg: usize) -> ! {
+    yield_();
+    *READY.lock() = true;
+    READY_CV.notify_all();
+    exit(0)
+}
+
+fn table_user(arg: usize) -> ! {
+    for _ in 0..PER_THREAD / 10 {
+        if arg == 0 {
+            let mut table = TABLE.write();
+            for slot in table.iter_mut() {
+                *slot += 1;
+            }
+        } else {
+            let table = TABLE.read();
+            // a writer never leaves the table half-updated
+            assert!(table.iter().all(|slot| *slot == table[0]));
+        }
+    }
+    exit(0)
+}
+
+#[no_mangle]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    spawn_all(adder);
+    assert_eq!(*COUNTER.lock(), THREAD_NUM * PER_THREAD);
+    println!("mutex OK");
+
+    let tids = [
+        thread_create(consumer as usize, 0),
+        thread_create(consumer as usize, 1),
+        thread_create(consumer as usize, 2),
+        thread_create(consumer as usize, 3),
+        thread_create(producer as usize, 0),
+    ];
+    for tid in tids {
+        assert_eq!(waittid(tid as usize), 0);
+    }
+    assert!(!SLOTS.try_down());
+    println!("semaphore OK");
+
+    let tids = [
+        thread_create(waiter as usize, 0),
+        thread_create(waiter as usize, 1),
+        thread_create(notifier as usize, 0),
+    ];
+    for tid in tids {
+        assert_eq!(waittid(tid as usize), 0);
+    }
+    println!("condvar OK");
+
+    spawn_all(table_user);
+    assert_eq!(*TABLE.read(), [PER_THREAD / 10; 4]);
+    println!("rwlock OK");
+
+    println!("Test sync_futex OK!");
+    0
+}
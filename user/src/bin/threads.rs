@@ -0,0 +1,99 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use user_lib::{
+    close,
+    errno::{EAGAIN, EDEADLK, ENOMEM, ESRCH},
+    exit, getpid, gettid, pipe, read, thread_create, waittid, write, yield_,
+};
+
+const THREAD_NUM: usize = 3;
+/// threads a process can have, the main one included
+const MAX_PROCESS_THREADS: usize = 8;
+
+static PIPE_WRITE_END: AtomicUsize = AtomicUsize::new(0);
+static RELEASE: AtomicBool = AtomicBool::new(false);
+
+fn worker(arg: usize) -> ! {
+    println!(
+        "thread {} of process {} got arg {}",
+        gettid(),
+        getpid(),
+        arg
+    );
+    exit(arg as i32 * 10)
+}
+
+/// stay alive until the main thread is done creating threads
+fn parked(_arg: usize) -> ! {
+    while !RELEASE.load(Ordering::SeqCst) {
+        yield_();
+    }
+    exit(0)
+}
+
+/// the fd table is shared, so a pipe opened by the main thread works here
+fn writer(_arg: usize) -> ! {
+    let fd = PIPE_WRITE_END.load(Ordering::SeqCst);
+    write(fd, b"from another thread");
+    close(fd);
+    exit(0)
+}
+
+#[no_mangle]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    let tids: [isize; THREAD_NUM] =
+        [1, 2, 3].map(|arg| thread_create(worker as usize, arg as usize));
+    for (i, tid) in tids.iter().enumerate() {
+        assert!(*tid > 0);
+        assert_eq!(waittid(*tid as usize), (i as isize + 1) * 10);
+    }
+    println!("all workers joined");
+
+    let mut pipe_fd = [0usize; 2];
+    assert_eq!(pipe(&mut pipe_fd), 0);
+    PIPE_WRITE_END.store(pipe_fd[1], Ordering::SeqCst);
+    let tid = thread_create(writer as usize, 0);
+    // blocks until the writer thread gets scheduled
+    let mut buffer = [0u8; 32];
+    let len = read(pipe_fd[0], &mut buffer) as usize;
+    assert_eq!(&buffer[..len], b"from another thread");
+    assert_eq!(waittid(tid as usize), 0);
+    close(pipe_fd[0]);
+
+    // Threads are limited, per process and in all, and so is the heap their
+    // stacks live on, running into any is an error rather than a kernel
+    // panic. Other apps may hold threads too, so fewer than our share may fit.
+    let mut parked_tids = [0usize; MAX_PROCESS_THREADS];
+    let mut created = 0;
+    let ret = loop {
+        match thread_create(parked as usize, 0) {
+            tid if tid > 0 => {
+                parked_tids[created] = tid as usize;
+                created += 1;
+            }
+            ret => break ret,
+        }
+    };
+    assert!(ret == -EAGAIN || ret == -ENOMEM);
+    assert!(created < MAX_PROCESS_THREADS);
+    RELEASE.store(true, Ordering::SeqCst);
+    for tid in &parked_tids[..created] {
+        assert_eq!(waittid(*tid), 0);
+    }
+    println!("{} threads before -EAGAIN", created);
+
+    assert_eq!(waittid(gettid() as usize), -EDEADLK);
+    // joined already, never existed, and the main thread of another process
+    assert_eq!(waittid(tid as usize), -ESRCH);
+    assert_eq!(waittid(usize::MAX), -ESRCH);
+    let other = if getpid() == 0 { 1 } else { 0 };
+    assert_eq!(waittid(other), -ESRCH);
+    println!("Test threads OK!");
+    0
+}
@@ -0,0 +1,71 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    get_time,
+    procfs::{field, read_file, read_pid_file},
+    yield_,
+};
+
+const MAX_PID: usize = 64;
+const ROUNDS: usize = 3;
+const INTERVAL_MS: usize = 500;
+
+/// user + kernel time of `pid` in us, `None` once there is no such pid
+fn cpu_time(pid: usize, buf: &mut [u8]) -> Option<u64> {
+    let status = read_pid_file(pid, "status", buf).ok()?;
+    let time = |key: &str| field(status, key).and_then(|v| v.parse::<u64>().ok());
+    Some(time("UserTime")? + time("KernelTime")?)
+}
+
+#[no_mangle]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    let mut buf = [0u8; 512];
+    let mut last = [0u64; MAX_PID];
+    let mut last_time = get_time();
+    for pid in 0..MAX_PID {
+        match cpu_time(pid, &mut buf) {
+            Some(time) => last[pid] = time,
+            None => break,
+        }
+    }
+
+    for _ in 0..ROUNDS {
+        while get_time() - last_time < INTERVAL_MS {
+            yield_();
+        }
+        let now = get_time();
+        let elapsed_us = ((now - last_time) * 1000) as u64;
+        last_time = now;
+
+        let uptime = read_file("/proc/uptime", &mut buf).unwrap_or("?\n");
+        print!("uptime {}", uptime);
+        let meminfo = read_file("/proc/meminfo", &mut buf).unwrap_or("");
+        println!(
+            "heap used {} of {}",
+            field(meminfo, "HeapUsed").unwrap_or("?"),
+            field(meminfo, "HeapTotal").unwrap_or("?")
+        );
+        println!("{:>4} {:>6} {:<12}  {}", "PID", "%CPU", "STATE", "NAME");
+        for pid in 0..MAX_PID {
+            let time = match cpu_time(pid, &mut buf) {
+                Some(time) => time,
+                None => break,
+            };
+            let cpu = time.saturating_sub(last[pid]) * 100 / elapsed_us.max(1);
+            last[pid] = time;
+            let status = read_pid_file(pid, "status", &mut buf).unwrap_or("");
+            println!(
+                "{:>4} {:>5}% {:<12}  {}",
+                pid,
+                cpu,
+                field(status, "State").unwrap_or("?"),
+                field(status, "Name").unwrap_or("?")
+            );
+        }
+    }
+    0
+}
@@ -7,7 +7,7 @@ use user_lib::syscall::sys_write;
 extern crate user_lib;
 
 #[no_mangle]
-fn main() -> i32 {
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
     println!("Hellol, world!");
 
     let v = unsafe { core::slice::from_raw_parts(11 as *const u8, 3) };
@@ -0,0 +1,66 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close,
+    errno::EFAULT,
+    mmap, mprotect, munmap, pipe, read,
+    syscall::{sys_pipe, sys_sigprocmask},
+    write, MAP_PRIVATE, PROT_NONE, PROT_READ, PROT_WRITE,
+};
+
+const PAGE_SIZE: usize = 4096;
+
+fn user_slice(addr: usize, len: usize) -> &'static mut [u8] {
+    unsafe { core::slice::from_raw_parts_mut(addr as *mut u8, len) }
+}
+
+#[no_mangle]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    let mut pipe_fd = [0usize; 2];
+    assert_eq!(pipe(&mut pipe_fd), 0);
+    let (rx, tx) = (pipe_fd[0], pipe_fd[1]);
+
+    // nowhere near the app
+    assert_eq!(write(tx, user_slice(11, 3)), -EFAULT);
+    assert_eq!(read(rx, user_slice(11, 3)), -EFAULT);
+
+    // a buffer across two pages
+    let area = mmap(0, 3 * PAGE_SIZE, PROT_READ | PROT_WRITE, MAP_PRIVATE) as usize;
+    let across = user_slice(area + PAGE_SIZE - 4, 8);
+    across.copy_from_slice(b"01234567");
+    assert_eq!(write(tx, across), 8);
+    let mut buf = [0u8; 8];
+    assert_eq!(read(rx, &mut buf), 8);
+    assert_eq!(&buf, b"01234567");
+
+    // read-only memory may be written out but not read into
+    assert_eq!(mprotect(area, PAGE_SIZE, PROT_READ), 0);
+    assert_eq!(write(tx, user_slice(area, 4)), 4);
+    assert_eq!(read(rx, user_slice(area, 4)), -EFAULT);
+    // nothing was consumed by the failed read
+    assert_eq!(read(rx, &mut buf[..4]), 4);
+    // a buffer running into a read-only page fails as a whole
+    assert_eq!(read(rx, user_slice(area + PAGE_SIZE - 4, 8)), -EFAULT);
+    assert_eq!(mprotect(area + 2 * PAGE_SIZE, PAGE_SIZE, PROT_NONE), 0);
+    assert_eq!(write(tx, user_slice(area + 2 * PAGE_SIZE - 4, 8)), -EFAULT);
+
+    // unmapped memory is gone for the kernel too
+    assert_eq!(munmap(area, 3 * PAGE_SIZE), 0);
+    assert_eq!(write(tx, user_slice(area, 4)), -EFAULT);
+
+    let fds = unsafe { &mut *(area as *mut [i32; 2]) };
+    assert_eq!(sys_pipe(fds, 0), -EFAULT);
+    assert_eq!(
+        sys_sigprocmask(0, area as *const u64, core::ptr::null_mut()),
+        -EFAULT
+    );
+
+    close(rx);
+    close(tx);
+    println!("Test user_ptr OK!");
+    0
+}
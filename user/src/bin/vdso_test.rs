@@ -0,0 +1,73 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::time::Duration;
+
+use user_lib::{
+    clock_gettime,
+    env::{self, AT_VVAR},
+    get_taskinfo, get_time,
+    syscall::{sys_clock_gettime, sys_settimeofday},
+    task::TaskInfo,
+    time::{vdso_clock_gettime, TimeSpec, TimeVal, CLOCK_MONOTONIC, CLOCK_REALTIME},
+};
+
+const SYSCALL_CLOCK_GETTIME: usize = 113;
+
+fn clock_gettime_calls() -> usize {
+    let mut info = TaskInfo::new();
+    assert_eq!(get_taskinfo(&mut info), 0);
+    info.syscall_times(SYSCALL_CLOCK_GETTIME)
+}
+
+/// the time of `clock_id` the slow way
+fn syscall_time(clock_id: usize) -> Duration {
+    let mut ts = TimeSpec::default();
+    assert_eq!(sys_clock_gettime(clock_id, &mut ts), 0);
+    Duration::new(ts.sec as u64, ts.nsec as u32)
+}
+
+#[no_mangle]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    assert!(env::auxv(AT_VVAR).is_some());
+
+    // polling the time makes no syscall
+    let calls = clock_gettime_calls();
+    let start = get_time();
+    let mut last = start;
+    while last - start < 20 {
+        let now = get_time();
+        assert!(now >= last);
+        last = now;
+    }
+    assert_eq!(clock_gettime_calls(), calls);
+
+    // and agrees with the kernel
+    for clock_id in [CLOCK_MONOTONIC, CLOCK_REALTIME] {
+        let before = syscall_time(clock_id);
+        let fast = vdso_clock_gettime(clock_id).unwrap();
+        let after = syscall_time(clock_id);
+        assert!(before <= fast && fast <= after);
+    }
+
+    // a new wall-clock time shows up right away
+    let saved = clock_gettime(CLOCK_REALTIME).unwrap();
+    let y2k = TimeVal {
+        sec: 946684800,
+        usec: 0,
+    };
+    assert_eq!(sys_settimeofday(&y2k), 0);
+    let now = vdso_clock_gettime(CLOCK_REALTIME).unwrap();
+    assert!(now - Duration::from(y2k) < Duration::from_secs(1));
+    let restore = TimeVal {
+        sec: saved.as_secs() as i64,
+        usec: saved.subsec_micros() as i64,
+    };
+    assert_eq!(sys_settimeofday(&restore), 0);
+
+    println!("Test vdso OK!");
+    0
+}
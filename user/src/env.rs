@@ -0,0 +1,80 @@
+//! Arguments, environment and auxiliary vector the program was started with
+//!
+//! The kernel leaves them on the stack, `_start` records where.
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+pub const AT_NULL: usize = 0;
+pub const AT_PHDR: usize = 3;
+pub const AT_PHENT: usize = 4;
+pub const AT_PHNUM: usize = 5;
+pub const AT_PAGESZ: usize = 6;
+pub const AT_ENTRY: usize = 9;
+pub const AT_UID: usize = 11;
+pub const AT_EUID: usize = 12;
+pub const AT_GID: usize = 13;
+pub const AT_EGID: usize = 14;
+pub const AT_RANDOM: usize = 25;
+pub const AT_EXECFN: usize = 31;
+/// the kernel's vvar page, see [`crate::time`]
+pub const AT_VVAR: usize = 0x1000;
+
+static ARGC: AtomicUsize = AtomicUsize::new(0);
+static ARGV: AtomicUsize = AtomicUsize::new(0);
+static ENVP: AtomicUsize = AtomicUsize::new(0);
+
+pub(crate) fn init(argc: usize, argv: *const *const u8, envp: *const *const u8) {
+    ARGC.store(argc, Ordering::Relaxed);
+    ARGV.store(argv as usize, Ordering::Relaxed);
+    ENVP.store(envp as usize, Ordering::Relaxed);
+}
+
+/// the NUL-terminated string at `ptr`
+fn cstr(ptr: *const u8) -> &'static str {
+    let len = (0..).find(|i| unsafe { *ptr.add(*i) } == 0).unwrap();
+    let bytes = unsafe { core::slice::from_raw_parts(ptr, len) };
+    core::str::from_utf8(bytes).unwrap_or("")
+}
+
+/// the strings of a NULL-terminated array
+fn strings(array: usize) -> impl Iterator<Item = &'static str> {
+    let array = array as *const *const u8;
+    let len = match array.is_null() {
+        true => 0,
+        false => (0..).find(|i| unsafe { *array.add(*i) }.is_null()).unwrap(),
+    };
+    (0..len).map(move |i| cstr(unsafe { *array.add(i) }))
+}
+
+/// `argv[0]`, the program name, first
+pub fn args() -> impl Iterator<Item = &'static str> {
+    strings(ARGV.load(Ordering::Relaxed)).take(ARGC.load(Ordering::Relaxed))
+}
+
+/// the `KEY=value` pairs of the environment
+pub fn vars() -> impl Iterator<Item = (&'static str, &'static str)> {
+    strings(ENVP.load(Ordering::Relaxed)).map(|var| var.split_once('=').unwrap_or((var, "")))
+}
+
+pub fn var(key: &str) -> Option<&'static str> {
+    vars().find(|(k, _)| *k == key).map(|(_, value)| value)
+}
+
+/// the value of `AT_*` entry `key` of the auxiliary vector
+pub fn auxv(key: usize) -> Option<usize> {
+    let envp = ENVP.load(Ordering::Relaxed) as *const usize;
+    if envp.is_null() {
+        return None;
+    }
+    // the vector starts right after the NULL that ends envp
+    let envc = strings(envp as usize).count();
+    let mut entry = unsafe { envp.add(envc + 1) };
+    loop {
+        let (k, value) = unsafe { (*entry, *entry.add(1)) };
+        match k {
+            AT_NULL => return None,
+            k if k == key => return Some(value),
+            _ => entry = unsafe { entry.add(2) },
+        }
+    }
+}
@@ -0,0 +1,66 @@
+//! The heap of a program, grown with `sbrk` whenever an allocation does not
+//! fit. The allocator owns the program break, don't move it by hand while
+//! using `alloc`.
+
+use core::{
+    alloc::{GlobalAlloc, Layout},
+    ptr::{null_mut, NonNull},
+};
+
+use linked_list_allocator::Heap;
+
+use crate::{sbrk, sync::Mutex};
+
+/// the heap grows by at least this much at a time
+const GROW_MIN: usize = 0x4000;
+const HEAP_ALIGN: usize = 16;
+
+struct SbrkHeap(Mutex<Heap>);
+
+#[global_allocator]
+static HEAP: SbrkHeap = SbrkHeap(Mutex::new(Heap::empty()));
+
+#[alloc_error_handler]
+pub fn handle_alloc_error(layout: Layout) -> ! {
+    panic!("Heap allocation error, layout = {:?}", layout);
+}
+
+/// Make room for `layout` at the top of the heap, false if the break
+/// cannot move that far.
+fn grow(heap: &mut Heap, layout: Layout) -> bool {
+    let by = (layout.size() + layout.align() + HEAP_ALIGN).max(GROW_MIN);
+    let old = sbrk(by as isize);
+    if old < 0 {
+        return false;
+    }
+    let old = old as usize;
+    unsafe {
+        if heap.size() == 0 {
+            let bottom = (old + HEAP_ALIGN - 1) & !(HEAP_ALIGN - 1);
+            heap.init(bottom, by - (bottom - old));
+        } else {
+            heap.extend(by);
+        }
+    }
+    true
+}
+
+unsafe impl GlobalAlloc for SbrkHeap {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let mut heap = self.0.lock();
+        loop {
+            if let Ok(ptr) = heap.allocate_first_fit(layout) {
+                return ptr.as_ptr();
+            }
+            if !grow(&mut heap, layout) {
+                return null_mut();
+            }
+        }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.0
+            .lock()
+            .deallocate(NonNull::new_unchecked(ptr), layout);
+    }
+}
@@ -1,21 +1,220 @@
 #![no_std]
 #![feature(panic_info_message)]
 #![feature(linkage)]
+#![feature(alloc_error_handler)]
+
+extern crate alloc;
+
+use alloc::{string::String, vec::Vec};
+use core::time::Duration;
 
 #[macro_use]
 pub mod console;
+pub mod env;
+pub mod errno;
+pub mod heap;
 pub mod lang;
+pub mod procfs;
+pub mod sched;
+pub mod signal;
+pub mod sync;
 pub mod syscall;
+pub mod task;
+pub mod time;
 
 pub use console::*;
+use sched::{SchedAttr, SchedParam};
+use signal::SignalAction;
 use syscall::*;
+use task::TaskInfo;
+use time::{TimeSpec, CLOCK_MONOTONIC};
+
+pub fn read(fd: usize, buf: &mut [u8]) -> isize {
+    sys_read(fd, buf)
+}
+
+pub const O_RDONLY: usize = 0;
+pub const AT_FDCWD: isize = -100;
+
+/// open `path`, returns an fd
+pub fn open(path: &str, flags: usize) -> isize {
+    // the kernel wants a NUL-terminated string
+    let mut buf = [0u8; 256];
+    if path.len() >= buf.len() {
+        return -errno::ENAMETOOLONG;
+    }
+    buf[..path.len()].copy_from_slice(path.as_bytes());
+    sys_openat(AT_FDCWD, &buf, flags, 0)
+}
+
+/// Start app `path` over with `args` and `envs`, the latter as `KEY=value`.
+/// Only returns on failure.
+pub fn exec(path: &str, args: &[&str], envs: &[&str]) -> isize {
+    // the kernel wants NUL-terminated strings in NULL-terminated arrays
+    let c_string = |s: &str| {
+        let mut s = String::from(s);
+        s.push('\0');
+        s
+    };
+    let path = c_string(path);
+    let args: Vec<String> = args.iter().map(|arg| c_string(arg)).collect();
+    let envs: Vec<String> = envs.iter().map(|env| c_string(env)).collect();
+    let c_array = |strings: &[String]| {
+        strings
+            .iter()
+            .map(|s| s.as_ptr())
+            .chain([core::ptr::null()])
+            .collect::<Vec<_>>()
+    };
+    sys_exec(
+        path.as_ptr(),
+        c_array(&args).as_ptr(),
+        c_array(&envs).as_ptr(),
+    )
+}
+
+pub fn close(fd: usize) -> isize {
+    sys_close(fd)
+}
+
+pub fn pipe(pipe_fd: &mut [usize]) -> isize {
+    let mut fds = [0i32; 2];
+    let ret = sys_pipe(&mut fds, 0);
+    if ret == 0 {
+        pipe_fd[0] = fds[0] as usize;
+        pipe_fd[1] = fds[1] as usize;
+    }
+    ret
+}
 
 pub fn write(fd: usize, buf: &[u8]) -> isize {
     sys_write(fd, buf)
 }
 
+/// Set the program break, 0 only queries it. Returns the break, which
+/// stays where it was if `addr` does not fit.
+pub fn brk(addr: usize) -> usize {
+    sys_brk(addr) as usize
+}
+
+/// move the program break, returns the old break or `-ENOMEM`
+pub fn sbrk(increment: isize) -> isize {
+    sys_sbrk(increment)
+}
+
+pub const PROT_NONE: usize = 0;
+pub const PROT_READ: usize = 1;
+pub const PROT_WRITE: usize = 2;
+pub const PROT_EXEC: usize = 4;
+
+pub const MAP_SHARED: usize = 0x01;
+pub const MAP_PRIVATE: usize = 0x02;
+pub const MAP_FIXED: usize = 0x10;
+pub const MAP_ANONYMOUS: usize = 0x20;
+pub const MAP_FIXED_NOREPLACE: usize = 0x100000;
+
+/// map `len` bytes of zeroed memory, returns the address or an errno
+pub fn mmap(addr: usize, len: usize, prot: usize, flags: usize) -> isize {
+    sys_mmap(addr, len, prot, flags | MAP_ANONYMOUS, -1, 0)
+}
+
+pub fn munmap(addr: usize, len: usize) -> isize {
+    sys_munmap(addr, len)
+}
+
+pub fn mprotect(addr: usize, len: usize, prot: usize) -> isize {
+    sys_mprotect(addr, len, prot)
+}
+
+pub const SYSLOG_ACTION_READ: usize = 2;
+pub const SYSLOG_ACTION_READ_ALL: usize = 3;
+pub const SYSLOG_ACTION_READ_CLEAR: usize = 4;
+pub const SYSLOG_ACTION_CLEAR: usize = 5;
+pub const SYSLOG_ACTION_CONSOLE_OFF: usize = 6;
+pub const SYSLOG_ACTION_CONSOLE_ON: usize = 7;
+pub const SYSLOG_ACTION_CONSOLE_LEVEL: usize = 8;
+pub const SYSLOG_ACTION_SIZE_UNREAD: usize = 9;
+pub const SYSLOG_ACTION_SIZE_BUFFER: usize = 10;
+
+/// read the kernel log with one of the `SYSLOG_ACTION_READ*` actions,
+/// returns the bytes read
+pub fn syslog_read(action: usize, buf: &mut [u8]) -> isize {
+    sys_syslog(action, buf)
+}
+
+/// the other `syslog` actions, `arg` is the level of
+/// `SYSLOG_ACTION_CONSOLE_LEVEL`
+pub fn syslog(action: usize, arg: usize) -> isize {
+    sys_syslog_arg(action, arg)
+}
+
+/// Change the kernel log filter, e.g. to `info,toyos::task=trace`.
+/// `-EINVAL` if `spec` does not parse.
+pub fn set_log_filter(spec: &str) -> isize {
+    let mut buf = [0u8; 256];
+    if spec.len() >= buf.len() {
+        return -errno::EINVAL;
+    }
+    buf[..spec.len()].copy_from_slice(spec.as_bytes());
+    sys_set_log_filter(&buf)
+}
+
+pub const TRACE_FS: usize = 1 << 0;
+pub const TRACE_PROCESS: usize = 1 << 1;
+pub const TRACE_SIGNAL: usize = 1 << 2;
+pub const TRACE_SYNC: usize = 1 << 3;
+pub const TRACE_INFO: usize = 1 << 4;
+pub const TRACE_MEMORY: usize = 1 << 5;
+pub const TRACE_ALL: usize =
+    TRACE_FS | TRACE_PROCESS | TRACE_SIGNAL | TRACE_SYNC | TRACE_INFO | TRACE_MEMORY;
+
+/// Have the kernel log the syscalls of process `pid` in the classes of
+/// `mask`, 0 turns it off. Returns the old mask.
+pub fn trace(pid: usize, mask: usize) -> isize {
+    sys_trace(pid, mask)
+}
+
+pub const PROFILE_STOP: usize = 0;
+pub const PROFILE_START: usize = 1;
+pub const PROFILE_DUMP_TEXT: usize = 2;
+pub const PROFILE_DUMP_FOLDED: usize = 3;
+
+/// `PROFILE_START` or `PROFILE_STOP` the kernel's sampling profiler
+pub fn profile(cmd: usize) -> isize {
+    sys_profile(cmd, &mut [])
+}
+
+/// copy the profile in the format of `cmd` into `buf`, returns its length
+pub fn profile_dump(cmd: usize, buf: &mut [u8]) -> isize {
+    sys_profile(cmd, buf)
+}
+
+/// the time of `clock_id`, one of `time::CLOCK_*`
+pub fn clock_gettime(clock_id: usize) -> Result<Duration, isize> {
+    if let Some(time) = time::vdso_clock_gettime(clock_id) {
+        return Ok(time);
+    }
+    let mut ts = TimeSpec::default();
+    match sys_clock_gettime(clock_id, &mut ts) {
+        0 => Ok(ts.into()),
+        errno => Err(errno),
+    }
+}
+
+/// `SIGALRM` in `seconds`, 0 cancels it; returns the seconds left on the
+/// previous alarm
+pub fn alarm(seconds: usize) -> usize {
+    sys_alarm(seconds) as usize
+}
+
+/// block for `time`, other tasks run meanwhile
+pub fn sleep(time: Duration) -> isize {
+    sys_nanosleep(&time.into())
+}
+
+/// milliseconds since boot
 pub fn get_time() -> usize {
-    sys_get_time() as usize
+    clock_gettime(CLOCK_MONOTONIC).unwrap().as_millis() as usize
 }
 
 pub fn exit(exit_code: i32) -> isize {
@@ -23,7 +222,169 @@ pub fn exit(exit_code: i32) -> isize {
 }
 
 pub fn yield_() -> isize {
-    sys_yield()
+    sys_sched_yield()
+}
+
+/// Schedule thread `tid`, 0 for the caller, by `policy`, one of
+/// `sched::SCHED_*`, at `priority`, which is 0 for `SCHED_OTHER` and 1 to 99
+/// otherwise.
+pub fn sched_setscheduler(tid: usize, policy: usize, priority: i32) -> isize {
+    sys_sched_setscheduler(tid, policy, &SchedParam { priority })
+}
+
+/// the policy of thread `tid`, 0 for the caller
+pub fn sched_getscheduler(tid: usize) -> isize {
+    sys_sched_getscheduler(tid)
+}
+
+/// the priority of thread `tid`, 0 for the caller
+pub fn sched_getparam(tid: usize) -> Result<i32, isize> {
+    let mut param = SchedParam::default();
+    match sys_sched_getparam(tid, &mut param) {
+        0 => Ok(param.priority),
+        errno => Err(errno),
+    }
+}
+
+/// Schedule thread `tid`, 0 for the caller, by `attr`, e.g. a
+/// [`SchedAttr::deadline`]. `-EBUSY` if the deadline threads would need
+/// more than the whole CPU.
+pub fn sched_setattr(tid: usize, attr: &SchedAttr) -> isize {
+    sys_sched_setattr(tid, attr)
+}
+
+pub fn sched_getattr(tid: usize) -> Result<SchedAttr, isize> {
+    let mut attr = SchedAttr::default();
+    match sys_sched_getattr(tid, &mut attr) {
+        0 => Ok(attr),
+        errno => Err(errno),
+    }
+}
+
+/// the time slice of thread `tid`, zero if it has none
+pub fn sched_rr_get_interval(tid: usize) -> Result<Duration, isize> {
+    let mut ts = TimeSpec::default();
+    match sys_sched_rr_get_interval(tid, &mut ts) {
+        0 => Ok(ts.into()),
+        errno => Err(errno),
+    }
+}
+
+pub fn getpid() -> isize {
+    sys_getpid()
+}
+
+/// an fd referring to process `pid`
+pub fn pidfd_open(pid: usize) -> isize {
+    sys_pidfd_open(pid, 0)
+}
+
+/// a copy of the file `targetfd` of the process `pidfd` refers to, which is
+/// how another process gets at the end of a pipe
+pub fn pidfd_getfd(pidfd: usize, targetfd: usize) -> isize {
+    sys_pidfd_getfd(pidfd, targetfd, 0)
+}
+
+/// accounting of current process
+pub fn get_taskinfo(info: &mut TaskInfo) -> isize {
+    sys_get_taskinfo(info)
+}
+
+/// accounting of process `pid`, `-ESRCH` if there is no such process
+pub fn get_pid_taskinfo(pid: usize, info: &mut TaskInfo) -> isize {
+    sys_get_pid_taskinfo(pid, info)
+}
+
+pub fn gettid() -> isize {
+    sys_gettid()
+}
+
+/// start a thread at `entry` with `arg`, the thread has to finish with
+/// [`exit`], returns its tid
+pub fn thread_create(entry: usize, arg: usize) -> isize {
+    sys_thread_create(entry, arg)
+}
+
+/// wait for thread `tid` to exit, returns its exit code, `-ESRCH` if there
+/// is no such thread in this process, `-EDEADLK` if it is the caller
+pub fn waittid(tid: usize) -> isize {
+    sys_waittid(tid)
+}
+
+/// create a kernel mutex, returns its id
+pub fn mutex_create() -> isize {
+    sys_mutex_create()
+}
+
+/// `-EDEADLK` instead of blocking if deadlock detection refuses the wait
+pub fn mutex_lock(mutex_id: usize) -> isize {
+    sys_mutex_lock(mutex_id)
+}
+
+pub fn mutex_unlock(mutex_id: usize) -> isize {
+    sys_mutex_unlock(mutex_id)
+}
+
+/// create a kernel semaphore with `count` resources, returns its id
+pub fn semaphore_create(count: usize) -> isize {
+    sys_semaphore_create(count)
+}
+
+pub fn semaphore_up(sem_id: usize) -> isize {
+    sys_semaphore_up(sem_id)
+}
+
+/// `-EDEADLK` instead of blocking if deadlock detection refuses the wait
+pub fn semaphore_down(sem_id: usize) -> isize {
+    sys_semaphore_down(sem_id)
+}
+
+/// create a kernel condition variable, returns its id
+pub fn condvar_create() -> isize {
+    sys_condvar_create()
+}
+
+pub fn condvar_signal(condvar_id: usize) -> isize {
+    sys_condvar_signal(condvar_id)
+}
+
+/// wait on `condvar_id`, releasing the held mutex `mutex_id` meanwhile
+pub fn condvar_wait(condvar_id: usize, mutex_id: usize) -> isize {
+    sys_condvar_wait(condvar_id, mutex_id)
+}
+
+/// turn banker's algorithm deadlock detection on or off for this process
+pub fn enable_deadlock_detect(enabled: bool) -> isize {
+    sys_enable_deadlock_detect(enabled as usize)
+}
+
+pub fn kill(pid: usize, signum: usize) -> isize {
+    sys_kill(pid, signum)
+}
+
+pub fn sigaction(
+    signum: usize,
+    action: Option<&SignalAction>,
+    old_action: Option<&mut SignalAction>,
+) -> isize {
+    sys_sigaction(
+        signum,
+        action.map_or(core::ptr::null(), |a| a as *const SignalAction),
+        old_action.map_or(core::ptr::null_mut(), |a| a as *mut SignalAction),
+    )
+}
+
+pub fn sigprocmask(how: usize, set: Option<u64>, old_set: Option<&mut u64>) -> isize {
+    let set = set.as_ref();
+    sys_sigprocmask(
+        how,
+        set.map_or(core::ptr::null(), |s| s as *const u64),
+        old_set.map_or(core::ptr::null_mut(), |s| s as *mut u64),
+    )
+}
+
+pub fn sigreturn() -> isize {
+    sys_sigreturn()
 }
 
 fn clear_bss() {
@@ -38,14 +399,18 @@ fn clear_bss() {
 
 #[linkage = "weak"]
 #[no_mangle]
-fn main() -> i32 {
+fn main(_argc: usize, _argv: &[&'static str]) -> i32 {
     panic!("Cannot find main!");
 }
 
+/// the kernel passes argc, argv and envp in `a0`..`a2` as well as on the stack
 #[no_mangle]
 #[link_section = ".text.entry"]
-pub extern "C" fn _start() -> ! {
+pub extern "C" fn _start(argc: usize, argv: *const *const u8, envp: *const *const u8) -> ! {
     clear_bss();
-    exit(main());
+    env::init(argc, argv, envp);
+    time::init();
+    let args: Vec<&'static str> = env::args().collect();
+    exit(main(argc, &args));
     panic!("unreachable after sys_exit!");
 }
@@ -0,0 +1,70 @@
+//! Helpers for reading the kernel's `/proc`
+
+use core::fmt::{self, Write};
+
+use crate::{close, open, read, O_RDONLY};
+
+/// A string formatted into a fixed buffer, there is no heap in user space
+pub struct FixedString<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> FixedString<N> {
+    pub const fn new() -> Self {
+        Self {
+            buf: [0; N],
+            len: 0,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        core::str::from_utf8(&self.buf[..self.len]).unwrap_or("")
+    }
+}
+
+impl<const N: usize> Write for FixedString<N> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.len + s.len();
+        if end > N {
+            return Err(fmt::Error);
+        }
+        self.buf[self.len..end].copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+/// Read the whole file at `path` into `buf`, returns the text read or the
+/// negated errno of `open`.
+pub fn read_file<'a>(path: &str, buf: &'a mut [u8]) -> Result<&'a str, isize> {
+    let fd = open(path, O_RDONLY);
+    if fd < 0 {
+        return Err(fd);
+    }
+    let mut len = 0;
+    while len < buf.len() {
+        let n = read(fd as usize, &mut buf[len..]);
+        if n <= 0 {
+            break;
+        }
+        len += n as usize;
+    }
+    close(fd as usize);
+    Ok(core::str::from_utf8(&buf[..len]).unwrap_or(""))
+}
+
+/// Read `/proc/<pid>/<file>` into `buf`.
+pub fn read_pid_file<'a>(pid: usize, file: &str, buf: &'a mut [u8]) -> Result<&'a str, isize> {
+    let mut path = FixedString::<32>::new();
+    write!(path, "/proc/{}/{}", pid, file).map_err(|_| -crate::errno::ENAMETOOLONG)?;
+    read_file(path.as_str(), buf)
+}
+
+/// the value of `key` in a `Key:\tvalue` style file
+pub fn field<'a>(text: &'a str, key: &str) -> Option<&'a str> {
+    text.lines().find_map(|line| {
+        let (k, v) = line.split_once(':')?;
+        (k == key).then(|| v.trim())
+    })
+}
@@ -0,0 +1,44 @@
+//! Scheduling ABI, mirrors the kernel's
+
+use core::time::Duration;
+
+pub const SCHED_OTHER: usize = 0;
+pub const SCHED_FIFO: usize = 1;
+pub const SCHED_RR: usize = 2;
+pub const SCHED_DEADLINE: usize = 6;
+
+/// `struct sched_param`
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SchedParam {
+    pub priority: i32,
+}
+
+/// `struct sched_attr`, times in nanoseconds
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SchedAttr {
+    pub size: u32,
+    pub policy: u32,
+    pub flags: u64,
+    pub nice: i32,
+    pub priority: u32,
+    pub runtime: u64,
+    pub deadline: u64,
+    pub period: u64,
+}
+
+impl SchedAttr {
+    /// `SCHED_DEADLINE` asking for `runtime` within `deadline` of the start
+    /// of every `period`
+    pub fn deadline(runtime: Duration, deadline: Duration, period: Duration) -> Self {
+        SchedAttr {
+            size: core::mem::size_of::<SchedAttr>() as u32,
+            policy: SCHED_DEADLINE as u32,
+            runtime: runtime.as_nanos() as u64,
+            deadline: deadline.as_nanos() as u64,
+            period: period.as_nanos() as u64,
+            ..SchedAttr::default()
+        }
+    }
+}
@@ -0,0 +1,73 @@
+//! Signal numbers and the `sys_sigaction` ABI, mirrors `os/src/task/signal.rs`
+
+pub const MAX_SIG: usize = 31;
+
+pub const SIGHUP: usize = 1;
+pub const SIGINT: usize = 2;
+pub const SIGQUIT: usize = 3;
+pub const SIGILL: usize = 4;
+pub const SIGTRAP: usize = 5;
+pub const SIGABRT: usize = 6;
+pub const SIGBUS: usize = 7;
+pub const SIGFPE: usize = 8;
+pub const SIGKILL: usize = 9;
+pub const SIGUSR1: usize = 10;
+pub const SIGSEGV: usize = 11;
+pub const SIGUSR2: usize = 12;
+pub const SIGPIPE: usize = 13;
+pub const SIGALRM: usize = 14;
+pub const SIGTERM: usize = 15;
+pub const SIGSTKFLT: usize = 16;
+pub const SIGCHLD: usize = 17;
+pub const SIGCONT: usize = 18;
+pub const SIGSTOP: usize = 19;
+pub const SIGTSTP: usize = 20;
+pub const SIGTTIN: usize = 21;
+pub const SIGTTOU: usize = 22;
+pub const SIGURG: usize = 23;
+pub const SIGXCPU: usize = 24;
+pub const SIGXFSZ: usize = 25;
+pub const SIGVTALRM: usize = 26;
+pub const SIGPROF: usize = 27;
+pub const SIGWINCH: usize = 28;
+pub const SIGIO: usize = 29;
+pub const SIGPWR: usize = 30;
+pub const SIGSYS: usize = 31;
+
+pub const SIG_DFL: usize = 0;
+pub const SIG_IGN: usize = 1;
+
+pub const SIG_BLOCK: usize = 0;
+pub const SIG_UNBLOCK: usize = 1;
+pub const SIG_SETMASK: usize = 2;
+
+pub const SA_NODEFER: usize = 0x4000_0000;
+
+/// `sigsetsize` of `sys_sigaction` and `sys_sigprocmask`
+pub const SIGSET_SIZE: usize = core::mem::size_of::<u64>();
+
+/// signal `signum` in a set, at bit `signum - 1` as on Linux
+pub const fn sigmask(signum: usize) -> u64 {
+    1 << (signum - 1)
+}
+
+/// `struct sigaction`
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default)]
+pub struct SignalAction {
+    pub handler: usize,
+    pub flags: usize,
+    pub mask: u64,
+}
+
+impl SignalAction {
+    /// run `handler` on delivery, the kernel makes it return through its own
+    /// trampoline to `sys_sigreturn`
+    pub fn new(handler: fn(usize), mask: u64) -> Self {
+        Self {
+            handler: handler as usize,
+            flags: 0,
+            mask,
+        }
+    }
+}
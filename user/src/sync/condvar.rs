@@ -0,0 +1,36 @@
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use super::{futex_wait, futex_wake, MutexGuard};
+
+/// Every notify bumps `seq`, so a waiter that read `seq` before releasing the
+/// mutex cannot miss a notify that happens before it goes to sleep.
+pub struct Condvar {
+    seq: AtomicU32,
+}
+
+impl Condvar {
+    pub const fn new() -> Self {
+        Self {
+            seq: AtomicU32::new(0),
+        }
+    }
+
+    /// release `guard`, sleep until notified, then lock the mutex again
+    pub fn wait<'a, T>(&self, guard: MutexGuard<'a, T>) -> MutexGuard<'a, T> {
+        let seq = self.seq.load(Ordering::Acquire);
+        let mutex = guard.mutex();
+        drop(guard);
+        futex_wait(&self.seq, seq);
+        mutex.lock()
+    }
+
+    pub fn notify_one(&self) {
+        self.seq.fetch_add(1, Ordering::Release);
+        futex_wake(&self.seq, 1);
+    }
+
+    pub fn notify_all(&self) {
+        self.seq.fetch_add(1, Ordering::Release);
+        futex_wake(&self.seq, usize::MAX);
+    }
+}
@@ -0,0 +1,36 @@
+//! Blocking locks built on `sys_futex`
+//!
+//! Every primitive keeps its state in an `AtomicU32`. The uncontended path is
+//! a few atomic instructions in user space; only a thread that really has to
+//! wait makes a `FUTEX_WAIT` call, and the releasing side only makes a
+//! `FUTEX_WAKE` call when it knows there may be waiters.
+
+mod condvar;
+mod mutex;
+mod rwlock;
+mod semaphore;
+
+use core::sync::atomic::AtomicU32;
+
+pub use condvar::Condvar;
+pub use mutex::{Mutex, MutexGuard};
+pub use rwlock::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+pub use semaphore::Semaphore;
+
+use crate::syscall::sys_futex;
+
+const FUTEX_WAIT: usize = 0;
+const FUTEX_WAKE: usize = 1;
+
+/// how many times to retry in user space before going to sleep
+const SPIN_LIMIT: usize = 100;
+
+/// sleep while `*futex == val`
+pub fn futex_wait(futex: &AtomicU32, val: u32) -> isize {
+    sys_futex(futex.as_ptr(), FUTEX_WAIT, val as usize)
+}
+
+/// wake at most `count` threads sleeping on `futex`
+pub fn futex_wake(futex: &AtomicU32, count: usize) -> isize {
+    sys_futex(futex.as_ptr(), FUTEX_WAKE, count)
+}
@@ -0,0 +1,118 @@
+use core::{
+    cell::UnsafeCell,
+    hint::spin_loop,
+    ops::{Deref, DerefMut},
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use super::{futex_wait, futex_wake, SPIN_LIMIT};
+
+/// `state` is the number of readers, or `WRITER` while write-locked
+const WRITER: u32 = u32::MAX;
+
+pub struct RwLock<T> {
+    state: AtomicU32,
+    waiters: AtomicU32,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: Send + Sync> Sync for RwLock<T> {}
+unsafe impl<T: Send> Send for RwLock<T> {}
+
+pub struct RwLockReadGuard<'a, T> {
+    lock: &'a RwLock<T>,
+}
+
+pub struct RwLockWriteGuard<'a, T> {
+    lock: &'a RwLock<T>,
+}
+
+impl<T> RwLock<T> {
+    pub const fn new(data: T) -> Self {
+        Self {
+            state: AtomicU32::new(0),
+            waiters: AtomicU32::new(0),
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    pub fn read(&self) -> RwLockReadGuard<'_, T> {
+        self.acquire(|state| (state < WRITER - 1).then(|| state + 1));
+        RwLockReadGuard { lock: self }
+    }
+
+    pub fn write(&self) -> RwLockWriteGuard<'_, T> {
+        self.acquire(|state| (state == 0).then(|| WRITER));
+        RwLockWriteGuard { lock: self }
+    }
+
+    /// move `state` along `next`, sleep while `next` says it cannot
+    fn acquire(&self, next: impl Fn(u32) -> Option<u32>) {
+        let mut spin = 0;
+        loop {
+            let state = self.state.load(Ordering::Relaxed);
+            match next(state) {
+                Some(new) => {
+                    if self
+                        .state
+                        .compare_exchange_weak(state, new, Ordering::Acquire, Ordering::Relaxed)
+                        .is_ok()
+                    {
+                        return;
+                    }
+                }
+                None if spin < SPIN_LIMIT => {
+                    spin += 1;
+                    spin_loop();
+                }
+                None => {
+                    self.waiters.fetch_add(1, Ordering::SeqCst);
+                    futex_wait(&self.state, state);
+                    self.waiters.fetch_sub(1, Ordering::SeqCst);
+                }
+            }
+        }
+    }
+
+    /// readers and writers may both be waiting, let them race for it
+    fn wake_waiters(&self) {
+        if self.waiters.load(Ordering::SeqCst) > 0 {
+            futex_wake(&self.state, usize::MAX);
+        }
+    }
+}
+
+impl<T> Deref for RwLockReadGuard<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T> Drop for RwLockReadGuard<'_, T> {
+    fn drop(&mut self) {
+        if self.lock.state.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.lock.wake_waiters();
+        }
+    }
+}
+
+impl<T> Deref for RwLockWriteGuard<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T> DerefMut for RwLockWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+
+impl<T> Drop for RwLockWriteGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.state.store(0, Ordering::SeqCst);
+        self.lock.wake_waiters();
+    }
+}
@@ -0,0 +1,57 @@
+use core::{
+    hint::spin_loop,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use super::{futex_wait, futex_wake, SPIN_LIMIT};
+
+pub struct Semaphore {
+    count: AtomicU32,
+    waiters: AtomicU32,
+}
+
+impl Semaphore {
+    pub const fn new(count: u32) -> Self {
+        Self {
+            count: AtomicU32::new(count),
+            waiters: AtomicU32::new(0),
+        }
+    }
+
+    pub fn down(&self) {
+        for _ in 0..SPIN_LIMIT {
+            if self.try_down() {
+                return;
+            }
+            spin_loop();
+        }
+        while !self.try_down() {
+            self.waiters.fetch_add(1, Ordering::SeqCst);
+            futex_wait(&self.count, 0);
+            self.waiters.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    pub fn try_down(&self) -> bool {
+        let mut count = self.count.load(Ordering::Relaxed);
+        while count > 0 {
+            match self.count.compare_exchange_weak(
+                count,
+                count - 1,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return true,
+                Err(current) => count = current,
+            }
+        }
+        false
+    }
+
+    pub fn up(&self) {
+        self.count.fetch_add(1, Ordering::SeqCst);
+        if self.waiters.load(Ordering::SeqCst) > 0 {
+            futex_wake(&self.count, 1);
+        }
+    }
+}
@@ -1,10 +1,75 @@
 use core::arch::asm;
 
+use crate::{
+    sched::{SchedAttr, SchedParam},
+    signal::{SignalAction, SIGSET_SIZE},
+    task::TaskInfo,
+    time::{ITimerVal, TimeSpec, TimeVal},
+};
+
+const SYSCALL_IOCTL: usize = 29;
+const SYSCALL_OPENAT: usize = 56;
+const SYSCALL_CLOSE: usize = 57;
+const SYSCALL_PIPE: usize = 59;
+const SYSCALL_READ: usize = 63;
 const SYSCALL_WRITE: usize = 64;
+const SYSCALL_READV: usize = 65;
+const SYSCALL_WRITEV: usize = 66;
 const SYSCALL_EXIT: usize = 93;
-const SYSCALL_YIELD: usize = 124;
-const SYSCALL_GET_TIME: usize = 169;
+const SYSCALL_EXIT_GROUP: usize = 94;
+const SYSCALL_SET_TID_ADDRESS: usize = 96;
+const SYSCALL_FUTEX: usize = 98;
+const SYSCALL_NANOSLEEP: usize = 101;
+const SYSCALL_GETITIMER: usize = 102;
+const SYSCALL_SETITIMER: usize = 103;
+const SYSCALL_CLOCK_GETTIME: usize = 113;
+const SYSCALL_SYSLOG: usize = 116;
+const SYSCALL_SCHED_SETPARAM: usize = 118;
+const SYSCALL_SCHED_SETSCHEDULER: usize = 119;
+const SYSCALL_SCHED_GETSCHEDULER: usize = 120;
+const SYSCALL_SCHED_GETPARAM: usize = 121;
+const SYSCALL_SCHED_YIELD: usize = 124;
+const SYSCALL_SCHED_GET_PRIORITY_MAX: usize = 125;
+const SYSCALL_SCHED_GET_PRIORITY_MIN: usize = 126;
+const SYSCALL_SCHED_RR_GET_INTERVAL: usize = 127;
+const SYSCALL_KILL: usize = 129;
+const SYSCALL_SIGACTION: usize = 134;
+const SYSCALL_SIGPROCMASK: usize = 135;
+const SYSCALL_SIGRETURN: usize = 139;
+const SYSCALL_UNAME: usize = 160;
+const SYSCALL_GETTIMEOFDAY: usize = 169;
+const SYSCALL_SETTIMEOFDAY: usize = 170;
+const SYSCALL_GETPID: usize = 172;
+const SYSCALL_GETUID: usize = 174;
+const SYSCALL_GETTID: usize = 178;
+const SYSCALL_BRK: usize = 214;
+const SYSCALL_MUNMAP: usize = 215;
+const SYSCALL_EXEC: usize = 221;
+const SYSCALL_MMAP: usize = 222;
+const SYSCALL_MPROTECT: usize = 226;
 const SYSCALL_GET_TASKINFO: usize = 233;
+const SYSCALL_SCHED_SETATTR: usize = 274;
+const SYSCALL_SCHED_GETATTR: usize = 275;
+const SYSCALL_PIDFD_OPEN: usize = 434;
+const SYSCALL_PIDFD_GETFD: usize = 438;
+const SYSCALL_ENABLE_DEADLOCK_DETECT: usize = 469;
+const SYSCALL_THREAD_CREATE: usize = 1000;
+const SYSCALL_WAITTID: usize = 1002;
+const SYSCALL_MUTEX_CREATE: usize = 1010;
+const SYSCALL_MUTEX_LOCK: usize = 1011;
+const SYSCALL_MUTEX_UNLOCK: usize = 1012;
+const SYSCALL_SEMAPHORE_CREATE: usize = 1020;
+const SYSCALL_SEMAPHORE_UP: usize = 1021;
+const SYSCALL_SEMAPHORE_DOWN: usize = 1022;
+const SYSCALL_CONDVAR_CREATE: usize = 1030;
+const SYSCALL_CONDVAR_SIGNAL: usize = 1031;
+const SYSCALL_CONDVAR_WAIT: usize = 1032;
+const SYSCALL_GET_PID_TASKINFO: usize = 1100;
+const SYSCALL_SET_LOG_FILTER: usize = 1101;
+const SYSCALL_TRACE: usize = 1102;
+const SYSCALL_PROFILE: usize = 1103;
+const SYSCALL_SBRK: usize = 1104;
+const SYSCALL_ALARM: usize = 1105;
 
 fn syscall(id: usize, args: [usize; 3]) -> isize {
     let mut ret: isize;
@@ -20,25 +85,373 @@ fn syscall(id: usize, args: [usize; 3]) -> isize {
     ret
 }
 
-pub fn sys_get_time() -> isize {
-    syscall(SYSCALL_GET_TIME, [0, 0, 0])
+/// a raw syscall, for the ones without a wrapper
+pub fn syscall6(id: usize, args: [usize; 6]) -> isize {
+    let mut ret: isize;
+    unsafe {
+        asm!(
+            "ecall",
+            inlateout("x10") args[0] => ret,
+            in("x11") args[1],
+            in("x12") args[2],
+            in("x13") args[3],
+            in("x14") args[4],
+            in("x15") args[5],
+            in("x17") id
+        );
+    }
+    ret
+}
+
+pub fn sys_gettimeofday(tv: &mut TimeVal) -> isize {
+    syscall(SYSCALL_GETTIMEOFDAY, [tv as *mut TimeVal as usize, 0, 0])
+}
+
+pub fn sys_settimeofday(tv: &TimeVal) -> isize {
+    syscall(SYSCALL_SETTIMEOFDAY, [tv as *const TimeVal as usize, 0, 0])
 }
 
+pub fn sys_nanosleep(req: &TimeSpec) -> isize {
+    syscall(SYSCALL_NANOSLEEP, [req as *const TimeSpec as usize, 0, 0])
+}
+
+pub fn sys_getitimer(which: usize, curr: &mut ITimerVal) -> isize {
+    syscall(
+        SYSCALL_GETITIMER,
+        [which, curr as *mut ITimerVal as usize, 0],
+    )
+}
+
+pub fn sys_setitimer(which: usize, new: &ITimerVal, old: Option<&mut ITimerVal>) -> isize {
+    let old = old.map_or(0, |old| old as *mut ITimerVal as usize);
+    syscall(
+        SYSCALL_SETITIMER,
+        [which, new as *const ITimerVal as usize, old],
+    )
+}
+
+pub fn sys_alarm(seconds: usize) -> isize {
+    syscall(SYSCALL_ALARM, [seconds, 0, 0])
+}
+
+pub fn sys_clock_gettime(clock_id: usize, tp: &mut TimeSpec) -> isize {
+    syscall(
+        SYSCALL_CLOCK_GETTIME,
+        [clock_id, tp as *mut TimeSpec as usize, 0],
+    )
+}
+
+pub fn sys_sched_setparam(pid: usize, param: &SchedParam) -> isize {
+    syscall(
+        SYSCALL_SCHED_SETPARAM,
+        [pid, param as *const SchedParam as usize, 0],
+    )
+}
+
+pub fn sys_sched_setscheduler(pid: usize, policy: usize, param: &SchedParam) -> isize {
+    syscall(
+        SYSCALL_SCHED_SETSCHEDULER,
+        [pid, policy, param as *const SchedParam as usize],
+    )
+}
+
+pub fn sys_sched_getscheduler(pid: usize) -> isize {
+    syscall(SYSCALL_SCHED_GETSCHEDULER, [pid, 0, 0])
+}
+
+pub fn sys_sched_getparam(pid: usize, param: &mut SchedParam) -> isize {
+    syscall(
+        SYSCALL_SCHED_GETPARAM,
+        [pid, param as *mut SchedParam as usize, 0],
+    )
+}
+
+pub fn sys_sched_yield() -> isize {
+    syscall(SYSCALL_SCHED_YIELD, [0, 0, 0])
+}
+
+/// `sched_yield` under its old name
 pub fn sys_yield() -> isize {
-    syscall(SYSCALL_YIELD, [0, 0, 0])
+    sys_sched_yield()
+}
+
+pub fn sys_sched_get_priority_max(policy: usize) -> isize {
+    syscall(SYSCALL_SCHED_GET_PRIORITY_MAX, [policy, 0, 0])
+}
+
+pub fn sys_sched_get_priority_min(policy: usize) -> isize {
+    syscall(SYSCALL_SCHED_GET_PRIORITY_MIN, [policy, 0, 0])
+}
+
+pub fn sys_sched_rr_get_interval(pid: usize, tp: &mut TimeSpec) -> isize {
+    syscall(
+        SYSCALL_SCHED_RR_GET_INTERVAL,
+        [pid, tp as *mut TimeSpec as usize, 0],
+    )
+}
+
+pub fn sys_openat(dirfd: isize, path: &[u8], flags: usize, mode: usize) -> isize {
+    syscall6(
+        SYSCALL_OPENAT,
+        [dirfd as usize, path.as_ptr() as usize, flags, mode, 0, 0],
+    )
+}
+
+pub fn sys_close(fd: usize) -> isize {
+    syscall(SYSCALL_CLOSE, [fd, 0, 0])
+}
+
+/// `pipe2`
+pub fn sys_pipe(pipe: &mut [i32; 2], flags: usize) -> isize {
+    syscall(SYSCALL_PIPE, [pipe.as_mut_ptr() as usize, flags, 0])
+}
+
+pub fn sys_read(fd: usize, buffer: &mut [u8]) -> isize {
+    syscall(
+        SYSCALL_READ,
+        [fd, buffer.as_mut_ptr() as usize, buffer.len()],
+    )
 }
 
 pub fn sys_write(fd: usize, buffer: &[u8]) -> isize {
     syscall(SYSCALL_WRITE, [fd, buffer.as_ptr() as usize, buffer.len()])
 }
 
+/// `struct iovec`
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct IoVec {
+    pub base: usize,
+    pub len: usize,
+}
+
+pub fn sys_readv(fd: usize, iov: &[IoVec]) -> isize {
+    syscall(SYSCALL_READV, [fd, iov.as_ptr() as usize, iov.len()])
+}
+
+pub fn sys_writev(fd: usize, iov: &[IoVec]) -> isize {
+    syscall(SYSCALL_WRITEV, [fd, iov.as_ptr() as usize, iov.len()])
+}
+
+pub fn sys_ioctl(fd: usize, cmd: usize, arg: usize) -> isize {
+    syscall(SYSCALL_IOCTL, [fd, cmd, arg])
+}
+
 pub fn sys_exit(exit_code: i32) -> isize {
     syscall(SYSCALL_EXIT, [exit_code as usize, 0, 0])
 }
 
-pub fn sys_get_taskinfo(buffer: &mut [u8]) -> isize {
+pub fn sys_exit_group(exit_code: i32) -> isize {
+    syscall(SYSCALL_EXIT_GROUP, [exit_code as usize, 0, 0])
+}
+
+pub fn sys_set_tid_address(tidptr: *mut u32) -> isize {
+    syscall(SYSCALL_SET_TID_ADDRESS, [tidptr as usize, 0, 0])
+}
+
+/// `struct utsname`
+#[repr(C)]
+pub struct UtsName {
+    pub sysname: [u8; 65],
+    pub nodename: [u8; 65],
+    pub release: [u8; 65],
+    pub version: [u8; 65],
+    pub machine: [u8; 65],
+    pub domainname: [u8; 65],
+}
+
+pub fn sys_uname(buf: &mut UtsName) -> isize {
+    syscall(SYSCALL_UNAME, [buf as *mut UtsName as usize, 0, 0])
+}
+
+pub fn sys_getuid() -> isize {
+    syscall(SYSCALL_GETUID, [0, 0, 0])
+}
+
+pub fn sys_get_taskinfo(info: &mut TaskInfo) -> isize {
+    syscall(SYSCALL_GET_TASKINFO, [info as *mut TaskInfo as usize, 0, 0])
+}
+
+pub fn sys_sched_setattr(pid: usize, attr: &SchedAttr) -> isize {
     syscall(
-        SYSCALL_GET_TASKINFO,
-        [buffer.as_ptr() as usize, buffer.len(), 0],
+        SYSCALL_SCHED_SETATTR,
+        [pid, attr as *const SchedAttr as usize, 0],
     )
 }
+
+pub fn sys_sched_getattr(pid: usize, attr: &mut SchedAttr) -> isize {
+    syscall6(
+        SYSCALL_SCHED_GETATTR,
+        [
+            pid,
+            attr as *mut SchedAttr as usize,
+            core::mem::size_of::<SchedAttr>(),
+            0,
+            0,
+            0,
+        ],
+    )
+}
+
+pub fn sys_pidfd_open(pid: usize, flags: usize) -> isize {
+    syscall(SYSCALL_PIDFD_OPEN, [pid, flags, 0])
+}
+
+pub fn sys_pidfd_getfd(pidfd: usize, targetfd: usize, flags: usize) -> isize {
+    syscall(SYSCALL_PIDFD_GETFD, [pidfd, targetfd, flags])
+}
+
+pub fn sys_get_pid_taskinfo(pid: usize, info: &mut TaskInfo) -> isize {
+    syscall(
+        SYSCALL_GET_PID_TASKINFO,
+        [pid, info as *mut TaskInfo as usize, 0],
+    )
+}
+
+pub fn sys_getpid() -> isize {
+    syscall(SYSCALL_GETPID, [0, 0, 0])
+}
+
+pub fn sys_kill(pid: usize, signum: usize) -> isize {
+    syscall(SYSCALL_KILL, [pid, signum, 0])
+}
+
+pub fn sys_sigaction(
+    signum: usize,
+    action: *const SignalAction,
+    old_action: *mut SignalAction,
+) -> isize {
+    syscall6(
+        SYSCALL_SIGACTION,
+        [
+            signum,
+            action as usize,
+            old_action as usize,
+            SIGSET_SIZE,
+            0,
+            0,
+        ],
+    )
+}
+
+pub fn sys_sigprocmask(how: usize, set: *const u64, old_set: *mut u64) -> isize {
+    syscall6(
+        SYSCALL_SIGPROCMASK,
+        [how, set as usize, old_set as usize, SIGSET_SIZE, 0, 0],
+    )
+}
+
+pub fn sys_sigreturn() -> isize {
+    syscall(SYSCALL_SIGRETURN, [0, 0, 0])
+}
+
+pub fn sys_gettid() -> isize {
+    syscall(SYSCALL_GETTID, [0, 0, 0])
+}
+
+pub fn sys_thread_create(entry: usize, arg: usize) -> isize {
+    syscall(SYSCALL_THREAD_CREATE, [entry, arg, 0])
+}
+
+pub fn sys_waittid(tid: usize) -> isize {
+    syscall(SYSCALL_WAITTID, [tid, 0, 0])
+}
+
+pub fn sys_futex(uaddr: *const u32, op: usize, val: usize) -> isize {
+    syscall(SYSCALL_FUTEX, [uaddr as usize, op, val])
+}
+
+pub fn sys_mutex_create() -> isize {
+    syscall(SYSCALL_MUTEX_CREATE, [0, 0, 0])
+}
+
+pub fn sys_mutex_lock(mutex_id: usize) -> isize {
+    syscall(SYSCALL_MUTEX_LOCK, [mutex_id, 0, 0])
+}
+
+pub fn sys_mutex_unlock(mutex_id: usize) -> isize {
+    syscall(SYSCALL_MUTEX_UNLOCK, [mutex_id, 0, 0])
+}
+
+pub fn sys_semaphore_create(count: usize) -> isize {
+    syscall(SYSCALL_SEMAPHORE_CREATE, [count, 0, 0])
+}
+
+pub fn sys_semaphore_up(sem_id: usize) -> isize {
+    syscall(SYSCALL_SEMAPHORE_UP, [sem_id, 0, 0])
+}
+
+pub fn sys_semaphore_down(sem_id: usize) -> isize {
+    syscall(SYSCALL_SEMAPHORE_DOWN, [sem_id, 0, 0])
+}
+
+pub fn sys_condvar_create() -> isize {
+    syscall(SYSCALL_CONDVAR_CREATE, [0, 0, 0])
+}
+
+pub fn sys_condvar_signal(condvar_id: usize) -> isize {
+    syscall(SYSCALL_CONDVAR_SIGNAL, [condvar_id, 0, 0])
+}
+
+pub fn sys_condvar_wait(condvar_id: usize, mutex_id: usize) -> isize {
+    syscall(SYSCALL_CONDVAR_WAIT, [condvar_id, mutex_id, 0])
+}
+
+pub fn sys_enable_deadlock_detect(enabled: usize) -> isize {
+    syscall(SYSCALL_ENABLE_DEADLOCK_DETECT, [enabled, 0, 0])
+}
+
+pub fn sys_syslog(action: usize, buf: &mut [u8]) -> isize {
+    syscall(
+        SYSCALL_SYSLOG,
+        [action, buf.as_mut_ptr() as usize, buf.len()],
+    )
+}
+
+/// the actions of `syslog` that take a number instead of a buffer
+pub fn sys_syslog_arg(action: usize, arg: usize) -> isize {
+    syscall(SYSCALL_SYSLOG, [action, 0, arg])
+}
+
+pub fn sys_set_log_filter(spec: &[u8]) -> isize {
+    syscall(SYSCALL_SET_LOG_FILTER, [spec.as_ptr() as usize, 0, 0])
+}
+
+pub fn sys_trace(pid: usize, mask: usize) -> isize {
+    syscall(SYSCALL_TRACE, [pid, mask, 0])
+}
+
+pub fn sys_profile(cmd: usize, buf: &mut [u8]) -> isize {
+    syscall(SYSCALL_PROFILE, [cmd, buf.as_mut_ptr() as usize, buf.len()])
+}
+
+pub fn sys_brk(addr: usize) -> isize {
+    syscall(SYSCALL_BRK, [addr, 0, 0])
+}
+
+pub fn sys_sbrk(increment: isize) -> isize {
+    syscall(SYSCALL_SBRK, [increment as usize, 0, 0])
+}
+
+pub fn sys_mmap(
+    addr: usize,
+    len: usize,
+    prot: usize,
+    flags: usize,
+    fd: isize,
+    offset: usize,
+) -> isize {
+    syscall6(SYSCALL_MMAP, [addr, len, prot, flags, fd as usize, offset])
+}
+
+pub fn sys_munmap(addr: usize, len: usize) -> isize {
+    syscall(SYSCALL_MUNMAP, [addr, len, 0])
+}
+
+pub fn sys_mprotect(addr: usize, len: usize, prot: usize) -> isize {
+    syscall(SYSCALL_MPROTECT, [addr, len, prot])
+}
+
+pub fn sys_exec(path: *const u8, argv: *const *const u8, envp: *const *const u8) -> isize {
+    syscall(SYSCALL_EXEC, [path as usize, argv as usize, envp as usize])
+}
@@ -0,0 +1,88 @@
+//! Task accounting ABI, mirrors `UserTaskInfo` of the kernel
+
+/// room for syscall counts, `syscall_num` tells how many are used
+pub const TASK_INFO_SYSCALLS: usize = 64;
+pub const TASK_NAME_LEN: usize = 32;
+/// the size of the first version of [`TaskInfo`], up to `start_time_us`
+pub const TASK_INFO_SIZE_VER0: usize = 1112;
+
+#[repr(usize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TaskStatus {
+    Uninit = 0,
+    Ready = 1,
+    Running = 2,
+    Blocked = 3,
+    Exited = 4,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default)]
+pub struct SyscallCount {
+    pub id: usize,
+    pub times: usize,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct TaskInfo {
+    /// the size of this struct, the kernel sets it to the size it filled
+    pub size: usize,
+    pub pid: usize,
+    pub status: TaskStatus,
+    pub name: [u8; TASK_NAME_LEN],
+    pub syscall_num: usize,
+    pub syscalls: [SyscallCount; TASK_INFO_SYSCALLS],
+    pub user_time_us: u64,
+    pub kernel_time_us: u64,
+    pub start_time_us: u64,
+    // `TASK_INFO_SIZE_VER0` ends here, read later fields through their
+    // accessors, an older kernel leaves them out
+    /// jobs of deadline threads that did not get their runtime in time
+    pub deadline_misses: usize,
+}
+
+impl TaskInfo {
+    pub const fn new() -> Self {
+        Self {
+            size: core::mem::size_of::<Self>(),
+            pid: 0,
+            status: TaskStatus::Uninit,
+            name: [0; TASK_NAME_LEN],
+            syscall_num: 0,
+            syscalls: [SyscallCount { id: 0, times: 0 }; TASK_INFO_SYSCALLS],
+            user_time_us: 0,
+            kernel_time_us: 0,
+            start_time_us: 0,
+            deadline_misses: 0,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        let len = self
+            .name
+            .iter()
+            .position(|c| *c == 0)
+            .unwrap_or(TASK_NAME_LEN);
+        core::str::from_utf8(&self.name[..len]).unwrap_or("?")
+    }
+
+    pub fn syscalls(&self) -> &[SyscallCount] {
+        &self.syscalls[..self.syscall_num.min(TASK_INFO_SYSCALLS)]
+    }
+
+    /// `None` if the kernel is older than the field, the first one after
+    /// `TASK_INFO_SIZE_VER0`
+    pub fn deadline_misses(&self) -> Option<usize> {
+        (self.size >= TASK_INFO_SIZE_VER0 + core::mem::size_of::<usize>())
+            .then_some(self.deadline_misses)
+    }
+
+    /// how many times syscall `id` was made
+    pub fn syscall_times(&self, id: usize) -> usize {
+        self.syscalls()
+            .iter()
+            .find(|count| count.id == id)
+            .map_or(0, |count| count.times)
+    }
+}
@@ -0,0 +1,122 @@
+//! Clock ABI, mirrors the kernel's
+//!
+//! `CLOCK_REALTIME` and `CLOCK_MONOTONIC` are read without a syscall, from
+//! the `time` CSR and the kernel's vvar page.
+
+use core::{
+    arch::asm,
+    sync::atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use crate::env::{self, AT_VVAR};
+
+pub const CLOCK_REALTIME: usize = 0;
+pub const CLOCK_MONOTONIC: usize = 1;
+pub const CLOCK_PROCESS_CPUTIME_ID: usize = 2;
+pub const CLOCK_THREAD_CPUTIME_ID: usize = 3;
+
+pub const ITIMER_REAL: usize = 0;
+pub const ITIMER_VIRTUAL: usize = 1;
+pub const ITIMER_PROF: usize = 2;
+
+/// `struct timespec`
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TimeSpec {
+    pub sec: i64,
+    pub nsec: i64,
+}
+
+/// `struct timeval`
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TimeVal {
+    pub sec: i64,
+    pub usec: i64,
+}
+
+/// `struct itimerval`
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ITimerVal {
+    pub interval: TimeVal,
+    pub value: TimeVal,
+}
+
+impl From<Duration> for TimeVal {
+    fn from(time: Duration) -> Self {
+        TimeVal {
+            sec: time.as_secs() as i64,
+            usec: time.subsec_micros() as i64,
+        }
+    }
+}
+
+impl From<Duration> for TimeSpec {
+    fn from(time: Duration) -> Self {
+        TimeSpec {
+            sec: time.as_secs() as i64,
+            nsec: time.subsec_nanos() as i64,
+        }
+    }
+}
+
+impl From<TimeSpec> for Duration {
+    fn from(ts: TimeSpec) -> Self {
+        Duration::new(ts.sec as u64, ts.nsec as u32)
+    }
+}
+
+impl From<TimeVal> for Duration {
+    fn from(tv: TimeVal) -> Self {
+        Duration::new(tv.sec as u64, tv.usec as u32 * 1000)
+    }
+}
+
+/// Layout of the vvar page, mirrors the kernel's
+#[repr(C)]
+struct Vvar {
+    /// odd while the kernel writes
+    seq: AtomicU32,
+    clock_freq: AtomicU64,
+    boot_realtime_ns: AtomicU64,
+}
+
+static VVAR: AtomicUsize = AtomicUsize::new(0);
+
+pub(crate) fn init() {
+    VVAR.store(env::auxv(AT_VVAR).unwrap_or(0), Ordering::Relaxed);
+}
+
+fn rdtime() -> u64 {
+    let time;
+    unsafe { asm!("rdtime {}", out(reg) time) };
+    time
+}
+
+/// The time of `clock_id` without a syscall, `None` for the clocks that
+/// need the kernel or if there is no vvar page.
+pub fn vdso_clock_gettime(clock_id: usize) -> Option<Duration> {
+    let vvar = match VVAR.load(Ordering::Relaxed) {
+        0 => return None,
+        addr => unsafe { &*(addr as *const Vvar) },
+    };
+    let (ticks, freq, boot_ns) = loop {
+        let seq = vvar.seq.load(Ordering::Acquire);
+        let values = (
+            rdtime(),
+            vvar.clock_freq.load(Ordering::Acquire),
+            vvar.boot_realtime_ns.load(Ordering::Acquire),
+        );
+        if seq % 2 == 0 && vvar.seq.load(Ordering::Acquire) == seq {
+            break values;
+        }
+    };
+    let uptime = Duration::new(ticks / freq, ((ticks % freq) * 1_000_000_000 / freq) as u32);
+    match clock_id {
+        CLOCK_REALTIME => Some(uptime + Duration::from_nanos(boot_ns)),
+        CLOCK_MONOTONIC => Some(uptime),
+        _ => None,
+    }
+}